@@ -0,0 +1,113 @@
+#[macro_use]
+extern crate criterion;
+extern crate libipfix;
+
+use criterion::Criterion;
+use libipfix::*;
+
+// observation domain, template id and contents of a data set
+type DataSet<'a> = (u32, u16, &'a [u8]);
+
+// templates and data sets of tests/ipfix.dump
+fn data_sets(dump : &[u8]) -> (TemplateCache, Vec<DataSet<'_>>) {
+	let registry = Registry::default();
+	let mut cache = TemplateCache::default();
+	let mut data_sets = Vec::new();
+
+	let mut input = dump;
+	while let Ok((rest, message)) = message_parser(input) {
+		input = rest;
+		let observation_domain_id = message.header.observation_domain_id;
+		for (set_header, data) in message.sets {
+			match set_header.set_id {
+				TEMPLATE_SET_ID | OPTIONS_TEMPLATE_SET_ID => {
+					let (_, templates) = template_records_parser(data, set_header).unwrap();
+					for template in templates {
+						if verify_template(&template, &registry).is_ok() {
+							let _ = cache.update_with(observation_domain_id, template);
+						}
+					}
+				}
+				id => {
+					if cache.lookup(observation_domain_id, id).is_some() {
+						data_sets.push((observation_domain_id, id, data));
+					}
+				}
+			}
+		}
+	}
+	(cache, data_sets)
+}
+
+fn decode(c : &mut Criterion) {
+	let dump = &include_bytes!("../tests/ipfix.dump")[..];
+	let registry = Registry::default();
+	let options = DecoderOptions::strict();
+	let (mut cache, data_sets) = data_sets(dump);
+
+	c.bench_function("data_records_parser", |b| {
+		b.iter(|| {
+			for &(observation_domain_id, id, data) in &data_sets {
+				let template = cache.lookup(observation_domain_id, id).unwrap();
+				let mut report = DecodeReport::default();
+				let length = data.len() as u16;
+				data_records_parser(data, length, template, &registry, &options, &mut report)
+					.unwrap();
+			}
+		})
+	});
+
+	c.bench_function("decode_plan", |b| {
+		b.iter(|| {
+			for &(observation_domain_id, id, data) in &data_sets {
+				let (_, plan) = cache
+					.lookup_planned(observation_domain_id, id, &registry, &options)
+					.unwrap()
+					.unwrap();
+				let mut report = DecodeReport::default();
+				plan.decode(data, &mut report).unwrap();
+			}
+		})
+	});
+
+	c.bench_function("decode_plan_owned", |b| {
+		b.iter(|| {
+			for &(observation_domain_id, id, data) in &data_sets {
+				let (_, plan) = cache
+					.lookup_planned(observation_domain_id, id, &registry, &options)
+					.unwrap()
+					.unwrap();
+				let mut report = DecodeReport::default();
+				let (_, records) = plan.decode(data, &mut report).unwrap();
+				let _ : Vec<_> = records
+					.into_iter()
+					.map(DataRecordRef::into_owned)
+					.collect();
+			}
+		})
+	});
+
+	let projection = Projection::from_names(
+		&registry,
+		&["sourceIPv4Address", "destinationIPv4Address", "octetDeltaCount"],
+	)
+	.unwrap();
+	let plans = data_sets
+		.iter()
+		.map(|&(observation_domain_id, id, _)| {
+			let template = cache.lookup(observation_domain_id, id).unwrap();
+			projection.compile(template, &registry, &options).unwrap()
+		})
+		.collect::<Vec<_>>();
+	c.bench_function("projection", |b| {
+		b.iter(|| {
+			for (&(_, _, data), plan) in data_sets.iter().zip(plans.iter()) {
+				let mut report = DecodeReport::default();
+				plan.decode(data, &mut report).unwrap();
+			}
+		})
+	});
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
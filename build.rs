@@ -0,0 +1,221 @@
+extern crate roxmltree;
+
+#[path = "src/iana_xml.rs"]
+mod iana_xml;
+
+use std::collections::HashSet;
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::path::Path;
+
+const REGISTRY : &str = "registry/ipfix.xml";
+
+fn main() {
+	println!("cargo:rerun-if-changed={}", REGISTRY);
+	println!("cargo:rerun-if-changed=src/iana_xml.rs");
+
+	let xml = fs::read_to_string(REGISTRY).expect("vendored registry not readable");
+	let mut records = iana_xml::parse(&xml).expect("vendored registry not parseable");
+	// a newer registry may use values this crate doesn't know yet
+	records.retain(|record| {
+		let known = DATA_TYPES.contains(&&record.data_type[..]);
+		if !known {
+			warning(record, "data type", &record.data_type, "information element left out");
+		}
+		known
+	});
+	records.sort_by_key(|record| record.id);
+
+	let mut out = String::new();
+	writeln!(out, "pub static INFORMATION_ELEMENTS : &[InformationElement] = &[").unwrap();
+	for record in &records {
+		writeln!(out, "\tInformationElement {{").unwrap();
+		writeln!(out, "\t\tid : {},", record.id).unwrap();
+		writeln!(out, "\t\tenterprise_number : None,").unwrap();
+		writeln!(out, "\t\tname : Cow::Borrowed({:?}),", record.name).unwrap();
+		writeln!(
+			out,
+			"\t\tabstract_data_type : AbstractDataType::{},",
+			variant(&record.data_type)
+		).unwrap();
+		writeln!(
+			out,
+			"\t\tdata_type_semantics : {},",
+			option(
+				record
+					.data_type_semantics
+					.as_ref()
+					.and_then(|s| known(record, "data type semantics", s, SEMANTICS))
+					.map(|s| format!("DataTypeSemantics::{}", variant(s)))
+			)
+		).unwrap();
+		writeln!(
+			out,
+			"\t\tunits : {},",
+			option(
+				record
+					.units
+					.as_ref()
+					.and_then(|u| units_variant(record, u))
+					.map(|u| format!("Units::{}", u))
+			)
+		).unwrap();
+		writeln!(
+			out,
+			"\t\trange : {},",
+			option(record.range.map(|(begin, end)| format!("({}, {})", begin, end)))
+		).unwrap();
+		writeln!(out, "\t\tstatus : Status::{},", variant(&record.status)).unwrap();
+		writeln!(
+			out,
+			"\t\tdescription : Cow::Borrowed({:?}),",
+			record.description
+		).unwrap();
+		writeln!(out, "\t}},").unwrap();
+	}
+	writeln!(out, "];").unwrap();
+
+	// indices into INFORMATION_ELEMENTS sorted by name
+	let mut by_name = (0..records.len()).collect::<Vec<_>>();
+	by_name.sort_by_key(|&index| &records[index].name);
+	writeln!(out, "static INFORMATION_ELEMENTS_BY_NAME : &[usize] = &{:?};", by_name).unwrap();
+
+	let out_dir = env::var("OUT_DIR").unwrap();
+	fs::write(Path::new(&out_dir).join("information_elements.rs"), out).unwrap();
+
+	let mut constants = String::new();
+	let mut constant_names = HashSet::new();
+	for record in &records {
+		let constant_name = screaming_snake_case(&record.name);
+		if !constant_names.insert(constant_name.clone()) {
+			panic!("duplicate constant {} in vendored registry", constant_name);
+		}
+		writeln!(constants, "/// {}", record.name).unwrap();
+		writeln!(constants, "pub const {} : u16 = {};", constant_name, record.id).unwrap();
+	}
+	fs::write(Path::new(&out_dir).join("ie.rs"), constants).unwrap();
+}
+
+// sourceIPv4Address -> SOURCE_IPV4_ADDRESS
+// postNATSourceIPv4Address -> POST_NAT_SOURCE_IPV4_ADDRESS
+fn screaming_snake_case(name : &str) -> String {
+	let chars = name
+		.replace("IPv4", "Ipv4")
+		.replace("IPv6", "Ipv6")
+		.chars()
+		.collect::<Vec<_>>();
+	let mut result = String::new();
+	for (i, &c) in chars.iter().enumerate() {
+		if i > 0 && c.is_uppercase() {
+			let previous = chars[i - 1];
+			let next_is_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+			if previous.is_lowercase()
+				|| previous.is_ascii_digit()
+				|| previous.is_uppercase() && next_is_lowercase
+			{
+				result.push('_');
+			}
+		}
+		result.extend(c.to_uppercase());
+	}
+	result
+}
+
+// IANA names are lower camel case versions of the variant names
+fn variant(iana_name : &str) -> String {
+	let mut chars = iana_name.chars();
+	match chars.next() {
+		Some(first) => first.to_uppercase().chain(chars).collect(),
+		None => panic!("empty name in vendored registry"),
+	}
+}
+
+const DATA_TYPES : &[&str] = &[
+	"unsigned8",
+	"unsigned16",
+	"unsigned32",
+	"unsigned64",
+	"signed8",
+	"signed16",
+	"signed32",
+	"signed64",
+	"float32",
+	"float64",
+	"boolean",
+	"macAddress",
+	"octetArray",
+	"string",
+	"dateTimeSeconds",
+	"dateTimeMilliseconds",
+	"dateTimeMicroseconds",
+	"dateTimeNanoseconds",
+	"ipv4Address",
+	"ipv6Address",
+	"basicList",
+	"subTemplateList",
+	"subTemplateMultiList",
+];
+
+const SEMANTICS : &[&str] = &[
+	"quantity",
+	"totalCounter",
+	"deltaCounter",
+	"identifier",
+	"flags",
+	"list",
+	"snmpCounter",
+	"snmpGauge",
+];
+
+fn warning(record : &iana_xml::IanaRecord, field : &str, value : &str, consequence : &str) {
+	println!(
+		"cargo:warning=information element {} {} has unknown {} {}, {}",
+		record.id, record.name, field, value, consequence
+	);
+}
+
+fn known<'a>(
+	record : &iana_xml::IanaRecord,
+	field : &str,
+	value : &'a str,
+	values : &[&str],
+) -> Option<&'a str> {
+	if values.contains(&value) {
+		Some(value)
+	} else {
+		warning(record, field, value, "left unset");
+		None
+	}
+}
+
+fn units_variant(record : &iana_xml::IanaRecord, units : &str) -> Option<&'static str> {
+	Some(match units {
+		"bits" => "Bits",
+		"octets" => "Octets",
+		"packets" => "Packets",
+		"flows" => "Flows",
+		"seconds" => "Seconds",
+		"milliseconds" => "Milliseconds",
+		"microseconds" => "Microseconds",
+		"nanoseconds" => "Nanoseconds",
+		"4-octet words" => "FourOctetWords",
+		"messages" => "Messages",
+		"hops" => "Hops",
+		"entries" => "Entries",
+		"frames" => "Frames",
+		"ports" => "Ports",
+		"inferred" => "Inferred",
+		units => {
+			warning(record, "units", units, "left unset");
+			return None;
+		}
+	})
+}
+
+fn option(value : Option<String>) -> String {
+	match value {
+		Some(value) => format!("Some({})", value),
+		None => "None".to_string(),
+	}
+}
@@ -0,0 +1,157 @@
+//! `#[derive(IpfixRecord)]` for structs with one field per information element,
+//! see `libipfix::IpfixRecord`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, LitInt, LitStr};
+
+#[proc_macro_derive(IpfixRecord, attributes(ipfix))]
+pub fn derive_ipfix_record(input : TokenStream) -> TokenStream {
+	let input = syn::parse_macro_input!(input as DeriveInput);
+	match ipfix_record(&input) {
+		Ok(tokens) => tokens.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+// information element of a struct field
+enum Key {
+	Id { id : u16, enterprise_number : Option<u32> },
+	Name(String),
+}
+
+impl Key {
+	fn tokens(&self) -> TokenStream2 {
+		match *self {
+			Key::Id {
+				id,
+				enterprise_number : Some(enterprise_number),
+			} => quote!((::std::option::Option::Some(#enterprise_number), #id)),
+			Key::Id {
+				id,
+				enterprise_number : None,
+			} => quote!((::std::option::Option::None::<u32>, #id)),
+			Key::Name(ref name) => quote!(::libipfix::resolve_name(registry, #name)?),
+		}
+	}
+}
+
+fn ipfix_record(input : &DeriveInput) -> syn::Result<TokenStream2> {
+	let fields = match input.data {
+		Data::Struct(ref data) => match data.fields {
+			Fields::Named(ref fields) => &fields.named,
+			_ => {
+				return Err(syn::Error::new(
+					input.span(),
+					"IpfixRecord needs a struct with named fields",
+				))
+			}
+		},
+		_ => {
+			return Err(syn::Error::new(
+				input.span(),
+				"IpfixRecord can only be derived for structs",
+			))
+		}
+	};
+
+	let mut idents = Vec::new();
+	let mut names = Vec::new();
+	let mut types = Vec::new();
+	let mut keys = Vec::new();
+	for field in fields {
+		let ident = field.ident.clone().unwrap();
+		names.push(ident.to_string());
+		keys.push(key(field)?.tokens());
+		types.push(field.ty.clone());
+		idents.push(ident);
+	}
+
+	let name = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::libipfix::IpfixRecord for #name #type_generics #where_clause {
+			#[allow(unused_variables)]
+			fn field_specifiers(
+				registry : &::libipfix::Registry,
+			) -> ::std::result::Result<
+				::std::vec::Vec<::libipfix::FieldSpecifier>,
+				::libipfix::RecordError,
+			> {
+				::std::result::Result::Ok(vec![
+					#(::libipfix::field_specifier::<#types>(registry, #keys, #names)?),*
+				])
+			}
+
+			#[allow(unused_variables)]
+			fn from_record(
+				record : &::libipfix::TypedDataRecord,
+				registry : &::libipfix::Registry,
+			) -> ::std::result::Result<Self, ::libipfix::RecordError> {
+				::std::result::Result::Ok(#name {
+					#(#idents : ::libipfix::decode_field(record, #keys, #names)?),*
+				})
+			}
+
+			#[allow(unused_variables)]
+			fn to_record(
+				&self,
+				registry : &::libipfix::Registry,
+			) -> ::std::result::Result<::libipfix::DataRecord, ::libipfix::RecordError> {
+				::std::result::Result::Ok(::libipfix::DataRecord {
+					fields : vec![#(
+						::libipfix::encode_field(&self.#idents, registry, #keys, #names)?
+					),*],
+				})
+			}
+		}
+	})
+}
+
+// #[ipfix(ie = 8)], #[ipfix(ie = 18, enterprise = 6871)] or #[ipfix(name = "octetDeltaCount")]
+fn key(field : &syn::Field) -> syn::Result<Key> {
+	let mut id = None;
+	let mut enterprise_number = None;
+	let mut name = None;
+	for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("ipfix")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("ie") {
+				id = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<u16>()?);
+			} else if meta.path.is_ident("enterprise") {
+				enterprise_number = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<u32>()?);
+			} else if meta.path.is_ident("name") {
+				name = Some(meta.value()?.parse::<LitStr>()?.value());
+			} else {
+				return Err(meta.error("expected ie, enterprise or name"));
+			}
+			Ok(())
+		})?;
+	}
+
+	match (id, name) {
+		(Some(id), None) => Ok(Key::Id {
+			id,
+			enterprise_number,
+		}),
+		(None, Some(name)) if enterprise_number.is_none() => Ok(Key::Name(name)),
+		(None, Some(_)) => Err(syn::Error::new(
+			field.span(),
+			"enterprise is only used with ie",
+		)),
+		(Some(_), Some(_)) => Err(syn::Error::new(
+			field.span(),
+			"use either #[ipfix(ie = ...)] or #[ipfix(name = ...)]",
+		)),
+		(None, None) => Err(syn::Error::new(
+			field.span(),
+			"missing #[ipfix(ie = ...)] or #[ipfix(name = ...)]",
+		)),
+	}
+}
@@ -0,0 +1,487 @@
+use arrow_array::builder::*;
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use data_ref::*;
+use decode_plan::*;
+use decoder_options::*;
+use nom::IResult;
+use registry::*;
+use sink::*;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+use structs::*;
+use template_management::*;
+
+/// Arrow representation of IPv4 and IPv6 addresses
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AddressFormat {
+	/// Fixed size binary of 4 or 16 octets in network byte order
+	#[default]
+	Binary,
+	/// Strings like `"192.0.2.1"` and `"2001:db8::1"`
+	String,
+}
+
+/// Schema of the records of a template, one nullable column per field named like
+/// `unique_field_names`. Unknown fields and fields of invalid length are binary columns,
+/// `dateTime*` fields UTC timestamps of the same precision.
+pub fn template_schema(
+	template : &TemplateRecord,
+	registry : &Registry,
+	addresses : AddressFormat,
+) -> Schema {
+	let fields = template
+		.scope_fields
+		.iter()
+		.chain(template.fields.iter())
+		.zip(unique_field_names(template, registry))
+		.map(|(field, name)| {
+			let column = ColumnType::new(field, registry);
+			Field::new(name, column.data_type(addresses), true)
+		})
+		.collect::<Vec<_>>();
+	Schema::new(fields)
+}
+
+/// Collects data records of one template into Arrow record batches.
+/// Values that don't fit their column, like raw booleans of lenient decoding, are null.
+pub struct RecordBatchBuilder {
+	schema : SchemaRef,
+	columns : Vec<Column>,
+	rows : usize,
+}
+
+// column of a field, the abstract data type if it can be decoded as such
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ColumnType {
+	Typed(AbstractDataType),
+	Raw,
+}
+
+enum Column {
+	UInt8(UInt8Builder),
+	UInt16(UInt16Builder),
+	UInt32(UInt32Builder),
+	UInt64(UInt64Builder),
+	Int8(Int8Builder),
+	Int16(Int16Builder),
+	Int32(Int32Builder),
+	Int64(Int64Builder),
+	Float32(Float32Builder),
+	Float64(Float64Builder),
+	Boolean(BooleanBuilder),
+	MacAddress(FixedSizeBinaryBuilder),
+	Binary(BinaryBuilder),
+	String(StringBuilder),
+	TimestampSecond(TimestampSecondBuilder),
+	TimestampMillisecond(TimestampMillisecondBuilder),
+	TimestampMicrosecond(TimestampMicrosecondBuilder),
+	TimestampNanosecond(TimestampNanosecondBuilder),
+	Ipv4Address(FixedSizeBinaryBuilder),
+	Ipv6Address(FixedSizeBinaryBuilder),
+	AddressString(StringBuilder),
+}
+
+const UTC : &str = "UTC";
+
+impl ColumnType {
+	fn new(field : &FieldSpecifier, registry : &Registry) -> ColumnType {
+		match registry.lookup_field(field) {
+			Some(information_element)
+				if verify_field_length(information_element.abstract_data_type, field.field_length)
+					.is_ok() =>
+			{
+				ColumnType::Typed(information_element.abstract_data_type)
+			}
+			_ => ColumnType::Raw,
+		}
+	}
+
+	fn data_type(self, addresses : AddressFormat) -> DataType {
+		use structs::AbstractDataType::*;
+
+		let timestamp = |unit| DataType::Timestamp(unit, Some(UTC.into()));
+		match self {
+			ColumnType::Raw => DataType::Binary,
+			ColumnType::Typed(abstract_data_type) => match abstract_data_type {
+				Unsigned8 => DataType::UInt8,
+				Unsigned16 => DataType::UInt16,
+				Unsigned32 => DataType::UInt32,
+				Unsigned64 => DataType::UInt64,
+				Signed8 => DataType::Int8,
+				Signed16 => DataType::Int16,
+				Signed32 => DataType::Int32,
+				Signed64 => DataType::Int64,
+				Float32 => DataType::Float32,
+				Float64 => DataType::Float64,
+				Boolean => DataType::Boolean,
+				MacAddress => DataType::FixedSizeBinary(6),
+				OctetArray | BasicList | SubTemplateList | SubTemplateMultiList => DataType::Binary,
+				String => DataType::Utf8,
+				DateTimeSeconds => timestamp(TimeUnit::Second),
+				DateTimeMilliseconds => timestamp(TimeUnit::Millisecond),
+				DateTimeMicroseconds => timestamp(TimeUnit::Microsecond),
+				DateTimeNanoseconds => timestamp(TimeUnit::Nanosecond),
+				Ipv4Address | Ipv6Address if addresses == AddressFormat::String => DataType::Utf8,
+				Ipv4Address => DataType::FixedSizeBinary(4),
+				Ipv6Address => DataType::FixedSizeBinary(16),
+			},
+		}
+	}
+}
+
+impl RecordBatchBuilder {
+	pub fn new(
+		template : &TemplateRecord,
+		registry : &Registry,
+		addresses : AddressFormat,
+	) -> RecordBatchBuilder {
+		let columns = template
+			.scope_fields
+			.iter()
+			.chain(template.fields.iter())
+			.map(|field| Column::new(ColumnType::new(field, registry), addresses))
+			.collect();
+		RecordBatchBuilder {
+			schema : Arc::new(template_schema(template, registry, addresses)),
+			columns,
+			rows : 0,
+		}
+	}
+
+	pub fn schema(&self) -> SchemaRef {
+		self.schema.clone()
+	}
+
+	/// Records appended since the last batch
+	pub fn len(&self) -> usize {
+		self.rows
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.rows == 0
+	}
+
+	/// Missing trailing fields are null
+	pub fn append(&mut self, record : &DataRecordRef) {
+		for (i, column) in self.columns.iter_mut().enumerate() {
+			column.append(record.fields.get(i));
+		}
+		self.rows += 1;
+	}
+
+	/// Decodes the data records of a set body of the template with `plan` and appends them,
+	/// returns their number
+	pub fn decode<'a>(
+		&mut self,
+		plan : &DecodePlan,
+		input : &'a [u8],
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], usize> {
+		let (rest, records) = plan.decode(input, report)?;
+		for record in &records {
+			self.append(record);
+		}
+		Ok((rest, records.len()))
+	}
+
+	/// Batch of the records appended so far, the builder is empty afterwards
+	pub fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+		let columns = self.columns.iter_mut().map(Column::finish).collect();
+		self.rows = 0;
+		RecordBatch::try_new(self.schema.clone(), columns)
+	}
+}
+
+impl Column {
+	fn new(column_type : ColumnType, addresses : AddressFormat) -> Column {
+		use structs::AbstractDataType::*;
+
+		match column_type {
+			ColumnType::Raw => Column::Binary(BinaryBuilder::new()),
+			ColumnType::Typed(abstract_data_type) => match abstract_data_type {
+				Unsigned8 => Column::UInt8(UInt8Builder::new()),
+				Unsigned16 => Column::UInt16(UInt16Builder::new()),
+				Unsigned32 => Column::UInt32(UInt32Builder::new()),
+				Unsigned64 => Column::UInt64(UInt64Builder::new()),
+				Signed8 => Column::Int8(Int8Builder::new()),
+				Signed16 => Column::Int16(Int16Builder::new()),
+				Signed32 => Column::Int32(Int32Builder::new()),
+				Signed64 => Column::Int64(Int64Builder::new()),
+				Float32 => Column::Float32(Float32Builder::new()),
+				Float64 => Column::Float64(Float64Builder::new()),
+				Boolean => Column::Boolean(BooleanBuilder::new()),
+				MacAddress => Column::MacAddress(FixedSizeBinaryBuilder::new(6)),
+				OctetArray | BasicList | SubTemplateList | SubTemplateMultiList => {
+					Column::Binary(BinaryBuilder::new())
+				}
+				String => Column::String(StringBuilder::new()),
+				DateTimeSeconds => {
+					Column::TimestampSecond(TimestampSecondBuilder::new().with_timezone(UTC))
+				}
+				DateTimeMilliseconds => Column::TimestampMillisecond(
+					TimestampMillisecondBuilder::new().with_timezone(UTC),
+				),
+				DateTimeMicroseconds => Column::TimestampMicrosecond(
+					TimestampMicrosecondBuilder::new().with_timezone(UTC),
+				),
+				DateTimeNanoseconds => Column::TimestampNanosecond(
+					TimestampNanosecondBuilder::new().with_timezone(UTC),
+				),
+				Ipv4Address | Ipv6Address if addresses == AddressFormat::String => {
+					Column::AddressString(StringBuilder::new())
+				}
+				Ipv4Address => Column::Ipv4Address(FixedSizeBinaryBuilder::new(4)),
+				Ipv6Address => Column::Ipv6Address(FixedSizeBinaryBuilder::new(16)),
+			},
+		}
+	}
+
+	// reduced size encodings are widened
+	fn append(&mut self, value : Option<&DataValueRef>) {
+		use data_ref::DataValueRef as V;
+
+		match (self, value) {
+			(&mut Column::UInt8(ref mut b), Some(&V::Unsigned8(u))) => b.append_value(u),
+			(&mut Column::UInt16(ref mut b), Some(&V::Unsigned8(u))) => b.append_value(u.into()),
+			(&mut Column::UInt16(ref mut b), Some(&V::Unsigned16(u))) => b.append_value(u),
+			(&mut Column::UInt32(ref mut b), Some(value)) => b.append_option(unsigned(value)),
+			(&mut Column::UInt64(ref mut b), Some(value)) => b.append_option(unsigned(value)),
+			(&mut Column::Int8(ref mut b), Some(&V::Signed8(i))) => b.append_value(i),
+			(&mut Column::Int16(ref mut b), Some(&V::Signed8(i))) => b.append_value(i.into()),
+			(&mut Column::Int16(ref mut b), Some(&V::Signed16(i))) => b.append_value(i),
+			(&mut Column::Int32(ref mut b), Some(value)) => b.append_option(signed(value)),
+			(&mut Column::Int64(ref mut b), Some(value)) => b.append_option(signed(value)),
+			(&mut Column::Float32(ref mut b), Some(&V::Float32(f))) => b.append_value(f),
+			(&mut Column::Float64(ref mut b), Some(&V::Float32(f))) => b.append_value(f.into()),
+			(&mut Column::Float64(ref mut b), Some(&V::Float64(f))) => b.append_value(f),
+			(&mut Column::Boolean(ref mut b), Some(&V::Boolean(v))) => b.append_value(v),
+			(&mut Column::MacAddress(ref mut b), Some(&V::MacAddress(bytes))) => {
+				append_fixed_size(b, bytes)
+			}
+			(&mut Column::Binary(ref mut b), Some(&V::OctetArray(bytes))) => b.append_value(bytes),
+			(&mut Column::String(ref mut b), Some(V::String(s))) => b.append_value(s),
+			(&mut Column::TimestampSecond(ref mut b), Some(value)) => {
+				b.append_option(timestamp(value, |d| d.as_secs() as i64))
+			}
+			(&mut Column::TimestampMillisecond(ref mut b), Some(value)) => {
+				b.append_option(timestamp(value, |d| d.as_millis() as i64))
+			}
+			(&mut Column::TimestampMicrosecond(ref mut b), Some(value)) => {
+				b.append_option(timestamp(value, |d| d.as_micros() as i64))
+			}
+			(&mut Column::TimestampNanosecond(ref mut b), Some(value)) => {
+				b.append_option(timestamp(value, |d| d.as_nanos() as i64))
+			}
+			(&mut Column::Ipv4Address(ref mut b), Some(&V::Ipv4Address(addr))) => {
+				append_fixed_size(b, &addr.octets())
+			}
+			(&mut Column::Ipv6Address(ref mut b), Some(&V::Ipv6Address(addr))) => {
+				append_fixed_size(b, &addr.octets())
+			}
+			(&mut Column::AddressString(ref mut b), Some(&V::Ipv4Address(addr))) => {
+				b.append_value(addr.to_string())
+			}
+			(&mut Column::AddressString(ref mut b), Some(&V::Ipv6Address(addr))) => {
+				b.append_value(addr.to_string())
+			}
+			(column, _) => column.append_null(),
+		}
+	}
+
+	fn append_null(&mut self) {
+		match *self {
+			Column::UInt8(ref mut b) => b.append_null(),
+			Column::UInt16(ref mut b) => b.append_null(),
+			Column::UInt32(ref mut b) => b.append_null(),
+			Column::UInt64(ref mut b) => b.append_null(),
+			Column::Int8(ref mut b) => b.append_null(),
+			Column::Int16(ref mut b) => b.append_null(),
+			Column::Int32(ref mut b) => b.append_null(),
+			Column::Int64(ref mut b) => b.append_null(),
+			Column::Float32(ref mut b) => b.append_null(),
+			Column::Float64(ref mut b) => b.append_null(),
+			Column::Boolean(ref mut b) => b.append_null(),
+			Column::MacAddress(ref mut b)
+			| Column::Ipv4Address(ref mut b)
+			| Column::Ipv6Address(ref mut b) => b.append_null(),
+			Column::Binary(ref mut b) => b.append_null(),
+			Column::String(ref mut b) | Column::AddressString(ref mut b) => b.append_null(),
+			Column::TimestampSecond(ref mut b) => b.append_null(),
+			Column::TimestampMillisecond(ref mut b) => b.append_null(),
+			Column::TimestampMicrosecond(ref mut b) => b.append_null(),
+			Column::TimestampNanosecond(ref mut b) => b.append_null(),
+		}
+	}
+
+	fn finish(&mut self) -> ArrayRef {
+		match *self {
+			Column::UInt8(ref mut b) => Arc::new(b.finish()),
+			Column::UInt16(ref mut b) => Arc::new(b.finish()),
+			Column::UInt32(ref mut b) => Arc::new(b.finish()),
+			Column::UInt64(ref mut b) => Arc::new(b.finish()),
+			Column::Int8(ref mut b) => Arc::new(b.finish()),
+			Column::Int16(ref mut b) => Arc::new(b.finish()),
+			Column::Int32(ref mut b) => Arc::new(b.finish()),
+			Column::Int64(ref mut b) => Arc::new(b.finish()),
+			Column::Float32(ref mut b) => Arc::new(b.finish()),
+			Column::Float64(ref mut b) => Arc::new(b.finish()),
+			Column::Boolean(ref mut b) => Arc::new(b.finish()),
+			Column::MacAddress(ref mut b)
+			| Column::Ipv4Address(ref mut b)
+			| Column::Ipv6Address(ref mut b) => Arc::new(b.finish()),
+			Column::Binary(ref mut b) => Arc::new(b.finish()),
+			Column::String(ref mut b) | Column::AddressString(ref mut b) => Arc::new(b.finish()),
+			Column::TimestampSecond(ref mut b) => Arc::new(b.finish()),
+			Column::TimestampMillisecond(ref mut b) => Arc::new(b.finish()),
+			Column::TimestampMicrosecond(ref mut b) => Arc::new(b.finish()),
+			Column::TimestampNanosecond(ref mut b) => Arc::new(b.finish()),
+		}
+	}
+}
+
+fn unsigned<T>(value : &DataValueRef) -> Option<T>
+where
+	T : From<u8> + From<u16> + From<u32> + TryFrom<u64>,
+{
+	match *value {
+		DataValueRef::Unsigned8(u) => Some(u.into()),
+		DataValueRef::Unsigned16(u) => Some(u.into()),
+		DataValueRef::Unsigned32(u) => Some(u.into()),
+		DataValueRef::Unsigned64(u) => T::try_from(u).ok(),
+		_ => None,
+	}
+}
+
+fn signed<T>(value : &DataValueRef) -> Option<T>
+where
+	T : From<i8> + From<i16> + From<i32> + TryFrom<i64>,
+{
+	match *value {
+		DataValueRef::Signed8(i) => Some(i.into()),
+		DataValueRef::Signed16(i) => Some(i.into()),
+		DataValueRef::Signed32(i) => Some(i.into()),
+		DataValueRef::Signed64(i) => T::try_from(i).ok(),
+		_ => None,
+	}
+}
+
+// time since the Unix epoch in the unit of the column
+fn timestamp<F : Fn(Duration) -> i64>(value : &DataValueRef, unit : F) -> Option<i64> {
+	match *value {
+		DataValueRef::DateTimeSeconds(_)
+		| DataValueRef::DateTimeMilliseconds(_)
+		| DataValueRef::DateTimeMicroseconds { .. }
+		| DataValueRef::DateTimeNanoseconds { .. } => {
+			// time values own nothing
+			value.clone().into_owned().as_unix_duration().map(unit)
+		}
+		_ => None,
+	}
+}
+
+fn append_fixed_size(builder : &mut FixedSizeBinaryBuilder, bytes : &[u8]) {
+	if builder.append_value(bytes).is_err() {
+		builder.append_null();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use arrow_array::*;
+	use std::borrow::Cow;
+	use std::net::Ipv4Addr;
+
+	#[test]
+	fn record_batch_test() {
+		let registry = Registry::default();
+		let field = |information_element_id, field_length| FieldSpecifier {
+			information_element_id,
+			field_length,
+			enterprise_number : None,
+		};
+		// sourceIPv4Address, octetDeltaCount of reduced size, flowStartMilliseconds,
+		// interfaceName, unknown, protocolIdentifier twice
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 0,
+				field_count : 7,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				field(8, 4),
+				field(1, 4),
+				field(152, 8),
+				field(82, 0xffff),
+				field(32767, 2),
+				field(4, 1),
+				field(4, 1),
+			],
+		};
+
+		let schema = template_schema(&template, &registry, AddressFormat::Binary);
+		let types = schema.fields().iter().map(|f| f.data_type().clone()).collect::<Vec<_>>();
+		assert_eq!(
+			types,
+			vec![
+				DataType::FixedSizeBinary(4),
+				DataType::UInt64,
+				DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+				DataType::Utf8,
+				DataType::Binary,
+				DataType::UInt8,
+				DataType::UInt8,
+			]
+		);
+		assert_eq!(schema.field(5).name(), "protocolIdentifier");
+		assert_eq!(schema.field(6).name(), "protocolIdentifier#2");
+		assert_eq!(schema.field(4).name(), "unknown:32767");
+
+		let options = DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			..DecoderOptions::strict()
+		};
+		let plan = DecodePlan::compile(&template, &registry, &options).unwrap();
+		let data : &[u8] = &[
+			192, 0, 2, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0x03, 0xe8, 4, b'e', b't', b'h', b'0',
+			0xaa, 0xbb, 6, 17,
+		];
+		let mut builder = RecordBatchBuilder::new(&template, &registry, AddressFormat::Binary);
+		let (_, records) = builder.decode(&plan, data, &mut DecodeReport::default()).unwrap();
+		assert_eq!(records, 1);
+		// raw booleans and other values of the wrong type are null
+		builder.append(&DataRecordRef {
+			fields : vec![DataValueRef::String(Cow::Borrowed("192.0.2.2"))],
+		});
+		assert_eq!(builder.len(), 2);
+
+		let batch = builder.finish().unwrap();
+		assert!(builder.is_empty());
+		assert_eq!(batch.num_rows(), 2);
+		let addresses = batch.column(0).as_any().downcast_ref::<FixedSizeBinaryArray>().unwrap();
+		assert_eq!(addresses.value(0), &[192, 0, 2, 1]);
+		assert!(addresses.is_null(1));
+		let octets = batch.column(1).as_any().downcast_ref::<UInt64Array>().unwrap();
+		assert_eq!(octets.value(0), 256);
+		let starts = batch.column(2).as_any().downcast_ref::<TimestampMillisecondArray>().unwrap();
+		assert_eq!(starts.value(0), 1000);
+		let names = batch.column(3).as_any().downcast_ref::<StringArray>().unwrap();
+		assert_eq!(names.value(0), "eth0");
+		let unknown = batch.column(4).as_any().downcast_ref::<BinaryArray>().unwrap();
+		assert_eq!(unknown.value(0), &[0xaa, 0xbb]);
+		let protocols = batch.column(6).as_any().downcast_ref::<UInt8Array>().unwrap();
+		assert_eq!(protocols.value(0), 17);
+		assert!(protocols.is_null(1));
+
+		let mut builder = RecordBatchBuilder::new(&template, &registry, AddressFormat::String);
+		builder.append(&DataRecordRef {
+			fields : vec![DataValueRef::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1))],
+		});
+		let batch = builder.finish().unwrap();
+		let addresses = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+		assert_eq!(addresses.value(0), "192.0.2.1");
+	}
+}
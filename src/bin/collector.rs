@@ -4,14 +4,188 @@ extern crate env_logger;
 #[macro_use]
 extern crate log;
 
+use std::env;
+use std::io;
 use std::io::BufReader;
 use std::net;
+use std::process;
 
 fn main() {
 	env_logger::init();
 
+	let mut collector = libipfix::Collector::default();
+	let mut format = libipfix::JsonFormat::Names;
+	let mut time_format = libipfix::TimeFormat::Raw;
+	let mut delimited = None;
+	let mut columns = None;
+	let mut parquet = None;
+	let mut window = None;
+	let mut max_file_size = None;
+	let mut sqlite = None;
+	let mut bulk = false;
+	let mut bulk_options = libipfix::BulkOptions::default();
+	let mut index_template = false;
+	let mut filter = None;
+
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match &arg[..] {
+			"--registry" => {
+				let path = args.next().unwrap_or_else(|| usage());
+				collector.registry = match libipfix::Registry::load(&path) {
+					Ok(registry) => registry,
+					Err(e) => {
+						error!("{}: {}", path, e);
+						process::exit(1);
+					}
+				};
+				info!("loaded information elements from {}", path);
+			}
+			"--templates" => {
+				let path = args.next().unwrap_or_else(|| usage());
+				match libipfix::TemplateCache::load(&path) {
+					Ok(cache) => {
+						info!("restored {} templates from {}", cache.templates().len(), path);
+						collector.set_templates(cache);
+					}
+					Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+					Err(e) => {
+						error!("{}: {}", path, e);
+						process::exit(1);
+					}
+				}
+				collector.templates_path = Some(path.into());
+			}
+			"--lenient" => collector.options = libipfix::DecoderOptions::lenient(),
+			"--unknown" => {
+				collector.options.unknown_fields = match args.next().as_ref().map(|s| &s[..]) {
+					Some("reject") => libipfix::UnknownFieldPolicy::Reject,
+					Some("raw") => libipfix::UnknownFieldPolicy::KeepRaw,
+					_ => usage(),
+				}
+			}
+			"--json" => {
+				format = match args.next().as_ref().map(|s| &s[..]) {
+					Some("names") => libipfix::JsonFormat::Names,
+					Some("ids") => libipfix::JsonFormat::Ids,
+					Some("entries") => libipfix::JsonFormat::Entries,
+					_ => usage(),
+				}
+			}
+			"--time" => {
+				time_format = match args.next().as_ref().map(|s| &s[..]) {
+					Some("raw") => libipfix::TimeFormat::Raw,
+					Some("rfc3339") => libipfix::TimeFormat::Rfc3339,
+					_ => usage(),
+				}
+			}
+			"--csv" => delimited = Some(b','),
+			"--tsv" => delimited = Some(b'\t'),
+			"--columns" => {
+				let list = args.next().unwrap_or_else(|| usage());
+				columns = Some(list.split(',').map(str::to_string).collect());
+			}
+			"--parquet" => parquet = Some(args.next().unwrap_or_else(|| usage())),
+			"--window" => window = Some(number(args.next())),
+			"--max-file-size" => max_file_size = Some(number(args.next())),
+			"--sqlite" => sqlite = Some(args.next().unwrap_or_else(|| usage())),
+			"--bulk" => {
+				bulk_options.index = args.next().unwrap_or_else(|| usage());
+				bulk = true;
+			}
+			"--ecs" => bulk_options.mapping = libipfix::FieldMapping::Ecs,
+			"--index-template" => index_template = true,
+			"--filter" => filter = Some(args.next().unwrap_or_else(|| usage())),
+			_ => usage(),
+		}
+	}
+	if index_template {
+		let template = libipfix::index_template(&collector.registry, &bulk_options);
+		println!("{:#}", template);
+		return;
+	}
+	let mut sink : Box<dyn libipfix::Sink> = match (parquet, sqlite) {
+		#[cfg(feature = "parquet")]
+		(Some(directory), _) => {
+			let mut options = libipfix::ParquetOptions::default();
+			if let Some(window) = window {
+				options.window = std::time::Duration::from_secs(window);
+			}
+			if let Some(max_file_size) = max_file_size {
+				options.max_file_size = max_file_size;
+			}
+			info!("writing parquet files to {}", directory);
+			Box::new(libipfix::ParquetSink::new(directory, options))
+		}
+		#[cfg(not(feature = "parquet"))]
+		(Some(_), _) => {
+			let _ = (window, max_file_size);
+			error!("built without the parquet feature");
+			process::exit(2);
+		}
+		#[cfg(feature = "sqlite")]
+		(None, Some(path)) => {
+			match libipfix::SqliteSink::open(&path, libipfix::SqliteOptions::default()) {
+				Ok(sink) => {
+					info!("writing to {}", path);
+					Box::new(sink)
+				}
+				Err(e) => {
+					error!("{}: {}", path, e);
+					process::exit(1);
+				}
+			}
+		}
+		#[cfg(not(feature = "sqlite"))]
+		(None, Some(_)) => {
+			error!("built without the sqlite feature");
+			process::exit(2);
+		}
+		(None, None) => match (bulk, delimited) {
+			(true, _) => Box::new(libipfix::BulkSink::new(io::stdout(), bulk_options)),
+			(false, Some(delimiter)) => {
+				let mut sink = libipfix::CsvSink::new(io::stdout(), delimiter);
+				sink.columns = columns;
+				Box::new(sink)
+			}
+			(false, None) => {
+				let mut sink = libipfix::JsonSink::new(io::stdout(), format);
+				sink.time_format = time_format;
+				Box::new(sink)
+			}
+		},
+	};
+
+	if let Some(expression) = filter {
+		match libipfix::Filter::parse(&expression, &collector.registry) {
+			Ok(filter) => sink = Box::new(libipfix::FilterSink::new(filter, sink)),
+			Err(e) => {
+				error!("{}: {}", expression, e);
+				process::exit(2);
+			}
+		}
+	}
+
 	let listener = net::TcpListener::bind("127.0.0.1:8080").unwrap();
 	info!("listening on 127.0.0.1:8080");
 	let stream = listener.accept().unwrap().0;
-	libipfix::collect(&mut BufReader::new(stream));
+	collector.collect(&mut BufReader::new(stream), &mut sink);
+	if !collector.report().is_empty() {
+		info!("relaxed decoding: {}", collector.report());
+	}
+}
+
+fn number(arg : Option<String>) -> u64 {
+	arg.and_then(|arg| arg.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+	eprintln!(
+		"usage: collector [--registry ipfix.xml] [--templates templates.json] \
+		 [--lenient] [--unknown reject|raw] [--json names|ids|entries] [--time raw|rfc3339] \
+		 [--csv|--tsv [--columns NAME,...]] \
+		 [--parquet DIR [--window SECONDS] [--max-file-size BYTES]] [--sqlite FILE] \
+		 [--bulk INDEX_PATTERN] [--ecs] [--index-template] [--filter EXPRESSION]"
+	);
+	process::exit(2);
 }
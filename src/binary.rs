@@ -0,0 +1,553 @@
+use registry::*;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_content::{self, Value};
+use sink::*;
+use std;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use structs::*;
+
+#[cfg(feature = "cbor")]
+use ciborium;
+#[cfg(feature = "msgpack")]
+use rmp_serde;
+
+// Binary outputs are a stream of frames, maps with a "type" key followed by the keys below:
+//
+// {"type": "template", "domain": 1, "template": <TemplateRecord>}
+// {"type": "record", "domain": 1, "export_time": 1518864590, "template_id": 256,
+//  "fields": {"8": "192.0.2.1", "unknown:6871:18": 6}}
+//
+// A template frame precedes the first record of each template,
+// fields are keyed like `unique_field_keys`.
+// Readers accept the keys of a frame in any order.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinaryFormat {
+	#[cfg(feature = "cbor")]
+	Cbor,
+	/// MessagePack with structs as maps
+	#[cfg(feature = "msgpack")]
+	MessagePack,
+}
+
+#[derive(Debug)]
+pub enum BinaryError {
+	Io(std::io::Error),
+	/// invalid frame or record of an unknown template
+	Decode(String),
+}
+
+impl std::fmt::Display for BinaryError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		match *self {
+			BinaryError::Io(ref e) => write!(f, "{}", e),
+			BinaryError::Decode(ref e) => write!(f, "invalid frame: {}", e),
+		}
+	}
+}
+
+impl From<std::io::Error> for BinaryError {
+	fn from(e : std::io::Error) -> BinaryError {
+		BinaryError::Io(e)
+	}
+}
+
+/// Data record read from a binary stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryRecord {
+	pub observation_domain_id : u32,
+	pub export_time : u32,
+	pub template_id : u16,
+	pub record : DataRecord,
+}
+
+/// Writes data records as CBOR or MessagePack frames
+pub struct BinarySink<W : Write> {
+	writer : W,
+	format : BinaryFormat,
+	// templates already written
+	templates : HashMap<(u32, u16), TemplateRecord>,
+}
+
+impl<W : Write> BinarySink<W> {
+	pub fn new(writer : W, format : BinaryFormat) -> BinarySink<W> {
+		BinarySink {
+			writer,
+			format,
+			templates : HashMap::new(),
+		}
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+
+	fn write_frame<T : Serialize>(&mut self, frame : &T) -> std::io::Result<()> {
+		match self.format {
+			#[cfg(feature = "cbor")]
+			BinaryFormat::Cbor => {
+				ciborium::into_writer(frame, &mut self.writer).map_err(|e| match e {
+					ciborium::ser::Error::Io(e) => e,
+					e => std::io::Error::other(e),
+				})
+			}
+			#[cfg(feature = "msgpack")]
+			BinaryFormat::MessagePack => rmp_serde::encode::write_named(&mut self.writer, frame)
+				.map_err(std::io::Error::other),
+		}
+	}
+}
+
+impl<W : Write> Sink for BinarySink<W> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		let observation_domain_id = context.header.observation_domain_id;
+		let key = (observation_domain_id, context.template.header.template_id);
+		if self.templates.get(&key) != Some(context.template) {
+			self.write_frame(&FrameRef::Template {
+				observation_domain_id,
+				template : context.template,
+			})?;
+			self.templates.insert(key, context.template.clone());
+		}
+		self.write_frame(&FrameRef::Record {
+			observation_domain_id,
+			export_time : context.header.export_time,
+			record : context.typed(record),
+			registry : context.registry,
+		})
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+enum FrameRef<'a> {
+	Template {
+		observation_domain_id : u32,
+		template : &'a TemplateRecord,
+	},
+	Record {
+		observation_domain_id : u32,
+		export_time : u32,
+		record : TypedDataRecord<'a>,
+		registry : &'a Registry,
+	},
+}
+
+impl<'a> Serialize for FrameRef<'a> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		match *self {
+			FrameRef::Template {
+				observation_domain_id,
+				template,
+			} => {
+				let mut map = s.serialize_map(Some(3))?;
+				map.serialize_entry("type", "template")?;
+				map.serialize_entry("domain", &observation_domain_id)?;
+				map.serialize_entry("template", template)?;
+				map.end()
+			}
+			FrameRef::Record {
+				observation_domain_id,
+				export_time,
+				ref record,
+				registry,
+			} => {
+				let mut map = s.serialize_map(Some(5))?;
+				map.serialize_entry("type", "record")?;
+				map.serialize_entry("domain", &observation_domain_id)?;
+				map.serialize_entry("export_time", &export_time)?;
+				map.serialize_entry("template_id", &record.template.header.template_id)?;
+				map.serialize_entry("fields", &FieldsRef { record, registry })?;
+				map.end()
+			}
+		}
+	}
+}
+
+// unlike `TypedDataRecord` keyed with enterprise numbers
+struct FieldsRef<'a, 'b : 'a> {
+	record : &'a TypedDataRecord<'b>,
+	registry : &'a Registry,
+}
+
+impl<'a, 'b> Serialize for FieldsRef<'a, 'b> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		let keys = unique_field_keys(self.record.template, self.registry);
+		let mut map = s.serialize_map(Some(self.record.data.fields.len()))?;
+		for (key, value) in keys.iter().zip(self.record.data.fields.iter()) {
+			map.serialize_entry(key, value)?;
+		}
+		map.end()
+	}
+}
+
+/// Reads the data records written by `BinarySink`, typed by the templates of the stream
+pub struct BinaryReader<'a, R : BufRead> {
+	reader : R,
+	format : BinaryFormat,
+	registry : &'a Registry,
+	templates : HashMap<(u32, u16), TemplateRecord>,
+}
+
+enum Frame {
+	Template {
+		observation_domain_id : u32,
+		template : TemplateRecord,
+	},
+	Record(BinaryRecord),
+}
+
+impl<'a, R : BufRead> BinaryReader<'a, R> {
+	pub fn new(
+		reader : R,
+		format : BinaryFormat,
+		registry : &'a Registry,
+	) -> BinaryReader<'a, R> {
+		BinaryReader {
+			reader,
+			format,
+			registry,
+			templates : HashMap::new(),
+		}
+	}
+
+	/// Template of the records read so far
+	pub fn template(
+		&self,
+		observation_domain_id : u32,
+		template_id : u16,
+	) -> Option<&TemplateRecord> {
+		self.templates.get(&(observation_domain_id, template_id))
+	}
+
+	/// Next data record, `None` at the end of the stream
+	pub fn read_record(&mut self) -> Result<Option<BinaryRecord>, BinaryError> {
+		loop {
+			if self.reader.fill_buf()?.is_empty() {
+				return Ok(None);
+			}
+			match self.read_frame()? {
+				Frame::Template {
+					observation_domain_id,
+					template,
+				} => {
+					let key = (observation_domain_id, template.header.template_id);
+					self.templates.insert(key, template);
+				}
+				Frame::Record(record) => return Ok(Some(record)),
+			}
+		}
+	}
+
+	fn read_frame(&mut self) -> Result<Frame, BinaryError> {
+		let seed = FrameSeed {
+			registry : self.registry,
+			templates : &self.templates,
+		};
+		match self.format {
+			// ciborium only reads owned values, the frame is typed afterwards
+			#[cfg(feature = "cbor")]
+			BinaryFormat::Cbor => {
+				let frame = ciborium::from_reader::<Value, _>(&mut self.reader).map_err(|e| {
+					match e {
+						ciborium::de::Error::Io(e) => BinaryError::Io(e),
+						e => BinaryError::Decode(e.to_string()),
+					}
+				})?;
+				seed.deserialize(serde_content::Deserializer::new(frame).coerce_numbers())
+					.map_err(|e| BinaryError::Decode(e.to_string()))
+			}
+			#[cfg(feature = "msgpack")]
+			BinaryFormat::MessagePack => {
+				let mut d = rmp_serde::Deserializer::new(&mut self.reader);
+				seed.deserialize(&mut d).map_err(|e| match e {
+					rmp_serde::decode::Error::InvalidMarkerRead(e)
+					| rmp_serde::decode::Error::InvalidDataRead(e) => BinaryError::Io(e),
+					e => BinaryError::Decode(e.to_string()),
+				})
+			}
+		}
+	}
+}
+
+impl<'a, R : BufRead> Iterator for BinaryReader<'a, R> {
+	type Item = Result<BinaryRecord, BinaryError>;
+
+	fn next(&mut self) -> Option<Result<BinaryRecord, BinaryError>> {
+		self.read_record().transpose()
+	}
+}
+
+#[derive(Clone, Copy)]
+struct FrameSeed<'a> {
+	registry : &'a Registry,
+	templates : &'a HashMap<(u32, u16), TemplateRecord>,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for FrameSeed<'a> {
+	type Value = Frame;
+
+	fn deserialize<D>(self, d : D) -> Result<Frame, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		d.deserialize_map(self)
+	}
+}
+
+impl<'a, 'de> Visitor<'de> for FrameSeed<'a> {
+	type Value = Frame;
+
+	fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "a template or record frame")
+	}
+
+	fn visit_map<A : MapAccess<'de>>(self, mut map : A) -> Result<Frame, A::Error> {
+		let mut kind : Option<String> = None;
+		let mut observation_domain_id = None;
+		let mut export_time = None;
+		let mut template_id = None;
+		let mut template = None;
+		let mut record = None;
+		// fields before their template id and domain, typed once the map ends
+		let mut buffered_fields = None;
+
+		while let Some(key) = map.next_key::<String>()? {
+			match &key[..] {
+				"type" => kind = Some(map.next_value()?),
+				"domain" => observation_domain_id = Some(map.next_value()?),
+				"export_time" => export_time = Some(map.next_value()?),
+				"template_id" => template_id = Some(map.next_value()?),
+				"template" => template = Some(map.next_value()?),
+				"fields" => match (observation_domain_id, template_id) {
+					(Some(observation_domain_id), Some(template_id)) => {
+						let seed = self.record_seed(observation_domain_id, template_id)?;
+						record = Some(map.next_value_seed(seed)?);
+					}
+					_ => buffered_fields = Some(map.next_value::<Value>()?),
+				},
+				_ => {
+					map.next_value::<de::IgnoredAny>()?;
+				}
+			}
+		}
+
+		let kind = kind.ok_or_else(|| de::Error::missing_field("type"))?;
+		let observation_domain_id =
+			observation_domain_id.ok_or_else(|| de::Error::missing_field("domain"))?;
+		match &kind[..] {
+			"template" => Ok(Frame::Template {
+				observation_domain_id,
+				template : template.ok_or_else(|| de::Error::missing_field("template"))?,
+			}),
+			"record" => {
+				let template_id =
+					template_id.ok_or_else(|| de::Error::missing_field("template_id"))?;
+				let record = match (record, buffered_fields) {
+					(Some(record), _) => record,
+					(None, Some(fields)) => self
+						.record_seed(observation_domain_id, template_id)?
+						.deserialize(serde_content::Deserializer::new(fields).coerce_numbers())
+						.map_err(de::Error::custom)?,
+					(None, None) => return Err(de::Error::missing_field("fields")),
+				};
+				Ok(Frame::Record(BinaryRecord {
+					observation_domain_id,
+					export_time : export_time
+						.ok_or_else(|| de::Error::missing_field("export_time"))?,
+					template_id,
+					record,
+				}))
+			}
+			_ => Err(de::Error::unknown_variant(&kind, &["template", "record"])),
+		}
+	}
+}
+
+impl<'a> FrameSeed<'a> {
+	fn record_seed<E : de::Error>(
+		&self,
+		observation_domain_id : u32,
+		template_id : u16,
+	) -> Result<TypedDataRecordSeed<'a>, E> {
+		let template =
+			self.templates.get(&(observation_domain_id, template_id)).ok_or_else(|| {
+				de::Error::custom(format_args!(
+					"record of unknown template {} in observation domain {}",
+					template_id, observation_domain_id
+				))
+			})?;
+		Ok(TypedDataRecordSeed {
+			template,
+			registry : self.registry,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::Ipv4Addr;
+
+	fn roundtrip(format : BinaryFormat) {
+		let registry = Registry::default();
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 0,
+				field_count : 4,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				// sourceIPv4Address, flowStartMicroseconds, interfaceName, unknown enterprise
+				FieldSpecifier {
+					information_element_id : 8,
+					field_length : 4,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 154,
+					field_length : 8,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 82,
+					field_length : 0xffff,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 18,
+					field_length : 2,
+					enterprise_number : Some(6871),
+				},
+			],
+		};
+		let header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 1_518_864_590,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let record = |name : &str| DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::DateTimeMicroseconds {
+					seconds : 3_727_853_390,
+					fraction : 0x8000_0000,
+				},
+				DataValue::String(name.to_string()),
+				DataValue::OctetArray(vec![0, 6]),
+			],
+		};
+		let context = RecordContext {
+			header : &header,
+			template : &template,
+			registry : &registry,
+		};
+
+		let mut sink = BinarySink::new(Vec::new(), format);
+		sink.record(&context, &record("eth0")).unwrap();
+		let template_frame_end = sink.writer.len();
+		sink.record(&context, &record("eth1")).unwrap();
+		let bytes = sink.into_inner();
+
+		// the record frame is smaller than its JSON
+		let json = serde_json::to_vec(&FrameRef::Record {
+			observation_domain_id : 1,
+			export_time : header.export_time,
+			record : context.typed(&record("eth1")),
+			registry : &registry,
+		})
+		.unwrap();
+		let record_frame_length = (bytes.len() - template_frame_end) / 2;
+		assert!(record_frame_length < json.len() * 3 / 4);
+
+		let mut reader = BinaryReader::new(&bytes[..], format, &registry);
+		let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(
+			records,
+			vec![
+				BinaryRecord {
+					observation_domain_id : 1,
+					export_time : 1_518_864_590,
+					template_id : 256,
+					record : record("eth0"),
+				},
+				BinaryRecord {
+					observation_domain_id : 1,
+					export_time : 1_518_864_590,
+					template_id : 256,
+					record : record("eth1"),
+				},
+			]
+		);
+		assert_eq!(reader.template(1, 256), Some(&template));
+
+		// keys in any order
+		let mut sink = BinarySink::new(bytes[..template_frame_end].to_vec(), format);
+		sink.write_frame(&ShuffledRecord {
+			record : context.typed(&record("eth2")),
+		})
+		.unwrap();
+		let bytes = sink.into_inner();
+		let records = BinaryReader::new(&bytes[..], format, &registry)
+			.map(|record| record.unwrap().record)
+			.collect::<Vec<_>>();
+		assert_eq!(records, vec![record("eth0"), record("eth2")]);
+
+		// records need their template frame
+		match BinaryReader::new(&bytes[template_frame_end..], format, &registry).next() {
+			Some(Err(BinaryError::Decode(_))) => {}
+			other => panic!("{:?}", other),
+		}
+	}
+
+	// a record frame with the keys written last to first
+	struct ShuffledRecord<'a> {
+		record : TypedDataRecord<'a>,
+	}
+
+	impl<'a> Serialize for ShuffledRecord<'a> {
+		fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+		where
+			S : Serializer,
+		{
+			let mut map = s.serialize_map(Some(5))?;
+			map.serialize_entry(
+				"fields",
+				&FieldsRef {
+					record : &self.record,
+					registry : &Registry::default(),
+				},
+			)?;
+			map.serialize_entry("template_id", &self.record.template.header.template_id)?;
+			map.serialize_entry("export_time", &1_518_864_590u32)?;
+			map.serialize_entry("domain", &1u32)?;
+			map.serialize_entry("type", "record")?;
+			map.end()
+		}
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_test() {
+		roundtrip(BinaryFormat::Cbor);
+	}
+
+	#[cfg(feature = "msgpack")]
+	#[test]
+	fn msgpack_test() {
+		roundtrip(BinaryFormat::MessagePack);
+	}
+}
@@ -1,127 +1,384 @@
 use std;
+use std::path::PathBuf;
 
+use decoder_options::*;
+use json::*;
 use parser::*;
+use registry::*;
+use sink::*;
 use structs::*;
 use template_management::*;
+use type_options::*;
 
 use nom;
 use nom::Needed;
-use serde_json;
 
+/// Prints data records as JSON keyed by information element ids to stdout
 pub fn collect<Reader>(reader : &mut Reader)
 where
 	Reader : std::io::Read,
 {
-	let mut buffer = Vec::<u8>::new();
-	let mut cache = TemplateCache::default();
-
-	let mut message_num = 0;
-	loop {
-		match message_parser(&buffer.clone()[..]) {
-			Ok((rest, message)) => {
-				trace!("message header {}: {:?}", message_num, message.header);
-				collect_message_body(&mut cache, message, message_num);
-				message_num += 1;
-
-				// discard consumed front of buffer
-				let consumed_len = buffer.len() - rest.len();
-				buffer = buffer.split_off(consumed_len);
-			}
-			Err(nom::Err::Incomplete(needed)) => {
-				let old_len = buffer.len();
-				let length = match needed {
-					Needed::Size(length) => length,
-					Needed::Unknown => 1,
-				};
-
-				// get more input
-				buffer.resize(old_len + length, 0x00);
-				if reader.read_exact(&mut buffer[old_len..]).is_err() {
-					return;
+	let mut sink = JsonSink::new(std::io::stdout(), JsonFormat::Ids);
+	sink.context = false;
+	Collector::default().collect(reader, &mut sink)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Collector {
+	pub registry : Registry,
+	/// Saved to whenever a message changes the templates
+	pub templates_path : Option<PathBuf>,
+	pub options : DecoderOptions,
+	cache : TemplateCache,
+	report : DecodeReport,
+}
+
+impl Collector {
+	pub fn new(registry : Registry) -> Collector {
+		Collector {
+			registry,
+			templates_path : None,
+			options : DecoderOptions::strict(),
+			cache : TemplateCache::default(),
+			report : DecodeReport::default(),
+		}
+	}
+
+	/// Relaxations of all data sets collected so far
+	pub fn report(&self) -> &DecodeReport {
+		&self.report
+	}
+
+	pub fn templates(&self) -> &TemplateCache {
+		&self.cache
+	}
+
+	/// Warm start with templates restored by `TemplateCache::load`
+	pub fn set_templates(&mut self, cache : TemplateCache) {
+		self.cache = cache;
+	}
+
+	/// Collects messages until the input ends or is unparseable, then flushes the sink
+	pub fn collect<Reader, S>(&mut self, reader : &mut Reader, sink : &mut S)
+	where
+		Reader : std::io::Read,
+		S : Sink,
+	{
+		let mut buffer = Vec::<u8>::new();
+
+		let mut message_num = 0;
+		loop {
+			match message_parser(&buffer.clone()[..]) {
+				Ok((rest, message)) => {
+					trace!("message header {}: {:?}", message_num, message.header);
+					self.collect_message_body(message, message_num, sink);
+					message_num += 1;
+
+					// discard consumed front of buffer
+					let consumed_len = buffer.len() - rest.len();
+					buffer = buffer.split_off(consumed_len);
+				}
+				Err(nom::Err::Incomplete(needed)) => {
+					let old_len = buffer.len();
+					let length = match needed {
+						Needed::Size(length) => length,
+						Needed::Unknown => 1,
+					};
+
+					// get more input
+					buffer.resize(old_len + length, 0x00);
+					if reader.read_exact(&mut buffer[old_len..]).is_err() {
+						break;
+					}
+				}
+				// parser error and failure
+				Err(e) => {
+					error!("message {} unparseable", message_num);
+					println!("error {:?}", e);
+					println!("input = {:?}", buffer);
+					break;
 				}
 			}
-			// parser error and failure
-			Err(e) => {
-				error!("message {} unparseable", message_num);
-				println!("error {:?}", e);
-				println!("input = {:?}", buffer);
-				return;
-			}
+		}
+
+		if let Err(e) = sink.flush() {
+			error!("{}", e);
 		}
 	}
-}
 
-fn collect_message_body(cache : &mut TemplateCache, message : Message, message_num : usize) {
-	let mut set_num = 0;
-	for (set_header, data) in message.sets {
-		trace!("set header {}.{}: {:?}", message_num, set_num, set_header);
-
-		match set_header.set_id {
-			TEMPLATE_SET_ID | OPTIONS_TEMPLATE_SET_ID => {
-				let templates = match template_records_parser(data, set_header) {
-					Ok((_, t)) => t,
-					Err(_) => {
-						error!("template set {}.{} unparseable", message_num, set_num);
-						continue;
-					}
-				};
-				for template in &templates {
-					trace!(
-						"template {}.{}.{}: {:?}",
-						message_num,
-						set_num,
-						template.header.template_id,
-						template
-					);
-					if let Err(e) = verify_template(template) {
-						error!("{:?}", e);
-						continue;
-					}
-					if let Err(e) = cache.update_with(template.clone()) {
-						error!("{:?}", e);
-						continue;
+	fn collect_message_body<S : Sink>(
+		&mut self,
+		message : Message,
+		message_num : usize,
+		sink : &mut S,
+	) {
+		let observation_domain_id = message.header.observation_domain_id;
+		let mut templates_changed = false;
+		let mut set_num = 0;
+		for (set_header, data) in message.sets {
+			trace!("set header {}.{}: {:?}", message_num, set_num, set_header);
+
+			match set_header.set_id {
+				TEMPLATE_SET_ID | OPTIONS_TEMPLATE_SET_ID => {
+					let templates = match template_records_parser(data, set_header) {
+						Ok((_, t)) => t,
+						Err(_) => {
+							error!("template set {}.{} unparseable", message_num, set_num);
+							continue;
+						}
+					};
+					for template in &templates {
+						trace!(
+							"template {}.{}.{}: {:?}",
+							message_num,
+							set_num,
+							template.header.template_id,
+							template
+						);
+						let key = (observation_domain_id, template.header.template_id);
+						// enterprise information elements may be described by type options later,
+						// data sets are decoded once the plan compiles
+						let verified = verify_template(template, &self.registry);
+						let relaxed =
+							verify_deferred_template(template, &self.registry, &self.options);
+						match (verified, relaxed) {
+							(Ok(()), _) => {}
+							(Err(_), Err(e)) => {
+								error!("{:?}", e);
+								continue;
+							}
+							(
+								Err(e @ VerifyTemplateError::EnterpriseInformationElementNotFound {
+									..
+								}),
+								Ok(()),
+							) => info!("template {} deferred: {}", key.1, e),
+							(Err(e), Ok(())) => {
+								info!("template {} decoded with raw fields: {}", key.1, e)
+							}
+						}
+						match self.cache.update_with(key.0, template.clone()) {
+							Ok(UpdateOk::Redefinition) => {}
+							Ok(UpdateOk::Addition) | Ok(UpdateOk::Withdrawal) => {
+								templates_changed = true
+							}
+							Err(e) => {
+								error!("{:?}", e);
+								// the cached template is dropped
+								templates_changed |= e == UpdateErr::RedefinitionDifferent;
+							}
+						}
 					}
 				}
-			}
-			FIRST_TEMPLATE_ID...LAST_TEMPLATE_ID => {
-				let template = match cache.lookup(set_header.set_id) {
-					None => {
-						error!("received data set without known template");
-						continue;
+				FIRST_TEMPLATE_ID..=LAST_TEMPLATE_ID => {
+					let key = (observation_domain_id, set_header.set_id);
+					let planned =
+						self.cache
+							.lookup_planned(key.0, key.1, &self.registry, &self.options);
+					let (template, plan) = match planned {
+						None => {
+							error!("received data set without known template");
+							continue;
+						}
+						Some(Err(e)) => {
+							error!("data set of template {} not decodable: {}", key.1, e);
+							continue;
+						}
+						Some(Ok(planned)) => planned,
+					};
+
+					let mut set_report = DecodeReport::default();
+					let records = match plan.decode(data, &mut set_report) {
+						Ok((_, records)) => records,
+						Err(e) => {
+							error!("{:?}", e);
+							continue;
+						}
+					};
+					if !set_report.is_empty() {
+						debug!("data set {}.{} relaxed: {}", message_num, set_num, set_report);
+						self.report.add(&set_report);
 					}
-					Some(template) => template,
-				};
-
-				let records = match data_records_parser(
-					data,
-					set_header.length - SET_HEADER_LENGTH,
-					template,
-				) {
-					Ok(o) => o,
-					Err(e) => {
-						error!("{:?}", e);
-						continue;
+					let context = RecordContext {
+						header : &message.header,
+						template,
+						registry : &self.registry,
+					};
+					for (record_num, record) in records.iter().enumerate() {
+						if let Err(e) = sink.record_ref(&context, record) {
+							error!("{}", e);
+						}
+						trace!(
+							"data record {}.{}.{}: {:?}",
+							message_num,
+							set_num,
+							record_num,
+							record
+						);
+					}
+
+					if is_type_options_template(template) {
+						let learned = records
+							.iter()
+							.map(DataRecord::from)
+							.filter_map(|data| {
+								let record = TypedDataRecord {
+									data : &data,
+									template,
+								};
+								match information_element_from_type_record(&record) {
+									Ok(information_element) => Some(information_element),
+									Err(e) => {
+										error!("{}", e);
+										None
+									}
+								}
+							})
+							// IANA information elements are known at build time
+							.filter(|information_element| {
+								information_element.enterprise_number.is_some()
+							})
+							.collect::<Vec<_>>();
+						for information_element in learned {
+							info!(
+								"learned information element {} ({}:{})",
+								information_element.name,
+								information_element.enterprise_number.unwrap(),
+								information_element.id
+							);
+							self.registry.insert(information_element);
+							self.cache.clear_plans();
+						}
 					}
-				};
-				for (record_num, record) in records.1.iter().enumerate() {
-					println!(
-						"{}",
-						serde_json::to_string(&TypedDataRecord {
-							data : record,
-							template,
-						}).unwrap()
-					);
-					trace!(
-						"data record {}.{}.{}: {:?}",
-						message_num,
-						set_num,
-						record_num,
-						record
-					);
 				}
+				id => error!("received set with reserved set id {}", id),
+			}
+			set_num += 1;
+		}
+
+		if let (true, Some(path)) = (templates_changed, self.templates_path.as_ref()) {
+			if let Err(e) = self.cache.save(path) {
+				error!("{}: {}", path.display(), e);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use data_ref::*;
+	use encode::*;
+	use information_element::ie;
+	use template_builder::*;
+
+	#[test]
+	fn templates_path_test() {
+		let registry = Registry::default();
+		let message = |templates : &[TemplateRecord]| {
+			let header = MessageHeader {
+				version_number : IPFIX_VERSION_NUMBER,
+				length : 0,
+				export_time : 1_500_000_000,
+				sequence_number : 0,
+				observation_domain_id : 1,
+			};
+			encode_message(&header, &[encode_template_set(templates).unwrap()]).unwrap()
+		};
+		let template = |field| TemplateBuilder::new(256, &registry).field(field).build().unwrap();
+		let path = std::env::temp_dir()
+			.join(format!("libipfix-collector-templates-{}.json", std::process::id()));
+		let mut collector = Collector {
+			templates_path : Some(path.clone()),
+			..Collector::default()
+		};
+		let mut collect = |message : Vec<u8>| {
+			let mut sink = JsonSink::new(Vec::new(), JsonFormat::Ids);
+			collector.collect(&mut &message[..], &mut sink);
+		};
+
+		collect(message(&[template(ie::SOURCE_IPV4_ADDRESS)]));
+		std::fs::remove_file(&path).unwrap();
+		// identical redefinition
+		collect(message(&[template(ie::SOURCE_IPV4_ADDRESS)]));
+		assert!(!path.exists());
+		// different redefinition drops the template
+		collect(message(&[template(ie::DESTINATION_IPV4_ADDRESS)]));
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	// interface names of records passed without copying
+	#[derive(Default)]
+	struct BorrowedNames(Vec<String>);
+
+	impl Sink for BorrowedNames {
+		fn record(&mut self, _ : &RecordContext, _ : &DataRecord) -> std::io::Result<()> {
+			panic!("record copied")
+		}
+
+		fn record_ref(
+			&mut self,
+			_ : &RecordContext,
+			record : &DataRecordRef,
+		) -> std::io::Result<()> {
+			if let DataValueRef::String(std::borrow::Cow::Borrowed(name)) = record.fields[0] {
+				self.0.push(name.to_string());
 			}
-			id => error!("received set with reserved set id {}", id),
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn record_ref_test() {
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.field(ie::INTERFACE_NAME)
+			.build()
+			.unwrap();
+		let record = |name : &str| DataRecord {
+			fields : vec![DataValue::String(name.to_string())],
+		};
+		let header = MessageHeader {
+			version_number : IPFIX_VERSION_NUMBER,
+			length : 0,
+			export_time : 1_500_000_000,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let sets = [
+			encode_template_set(std::slice::from_ref(&template)).unwrap(),
+			encode_data_set(&template, &[record("eth0"), record("eth1")]).unwrap(),
+		];
+		let message = encode_message(&header, &sets).unwrap();
+
+		let mut sink = BorrowedNames::default();
+		Collector::default().collect(&mut &message[..], &mut sink);
+		assert_eq!(sink.0, ["eth0", "eth1"]);
+	}
+
+	#[derive(Default)]
+	struct FlushCount(usize);
+
+	impl Sink for FlushCount {
+		fn record(&mut self, _ : &RecordContext, _ : &DataRecord) -> std::io::Result<()> {
+			Ok(())
 		}
-		set_num += 1;
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			self.0 += 1;
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn flush_test() {
+		let mut collector = Collector::default();
+		let mut sink = FlushCount::default();
+		// end of input
+		collector.collect(&mut &[][..], &mut sink);
+		assert_eq!(sink.0, 1);
+		// IPFIX version 9
+		collector.collect(&mut &[0, 9, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..], &mut sink);
+		assert_eq!(sink.0, 2);
 	}
 }
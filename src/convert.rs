@@ -0,0 +1,185 @@
+use std;
+use std::convert::TryFrom;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::SystemTime;
+use structs::*;
+
+// Reduced-size encoding (RFC 7011 section 6.2) decodes an information element
+// to any variant of the same signedness up to its type,
+// so integer conversions accept all of them and check the range of the target.
+
+impl DataValue {
+	/// Any unsigned integer
+	pub fn as_u64(&self) -> Option<u64> {
+		match *self {
+			DataValue::Unsigned8(u) => Some(u as u64),
+			DataValue::Unsigned16(u) => Some(u as u64),
+			DataValue::Unsigned32(u) => Some(u as u64),
+			DataValue::Unsigned64(u) => Some(u),
+			_ => None,
+		}
+	}
+
+	/// Any signed integer
+	pub fn as_i64(&self) -> Option<i64> {
+		match *self {
+			DataValue::Signed8(i) => Some(i as i64),
+			DataValue::Signed16(i) => Some(i as i64),
+			DataValue::Signed32(i) => Some(i as i64),
+			DataValue::Signed64(i) => Some(i),
+			_ => None,
+		}
+	}
+
+	/// Any float, `float64` may be encoded in 4 octets
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			DataValue::Float32(f) => Some(f as f64),
+			DataValue::Float64(f) => Some(f),
+			_ => None,
+		}
+	}
+
+	pub fn as_ip_addr(&self) -> Option<IpAddr> {
+		match *self {
+			DataValue::Ipv4Address(addr) => Some(IpAddr::V4(addr)),
+			DataValue::Ipv6Address(addr) => Some(IpAddr::V6(addr)),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match *self {
+			DataValue::String(ref s) => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Octet arrays and MAC addresses
+	pub fn as_bytes(&self) -> Option<&[u8]> {
+		match *self {
+			DataValue::OctetArray(ref bytes) | DataValue::MacAddress(ref bytes) => Some(bytes),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TryFromDataValueError {
+	pub target : &'static str,
+	pub value : DataValue,
+}
+
+impl std::fmt::Display for TryFromDataValueError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		write!(f, "{:?} can't be converted to {}", self.value, self.target)
+	}
+}
+
+impl std::error::Error for TryFromDataValueError {}
+
+fn error(target : &'static str, value : &DataValue) -> TryFromDataValueError {
+	TryFromDataValueError {
+		target,
+		value : value.clone(),
+	}
+}
+
+macro_rules! try_from_data_value {
+	($type:ty, $name:expr, | $value:ident | $convert:expr) => {
+		impl<'a> TryFrom<&'a DataValue> for $type {
+			type Error = TryFromDataValueError;
+
+			fn try_from($value : &'a DataValue) -> Result<$type, TryFromDataValueError> {
+				$convert.ok_or_else(|| error($name, $value))
+			}
+		}
+	};
+}
+
+try_from_data_value!(u8, "u8", |value| value.as_u64().and_then(|u| u8::try_from(u).ok()));
+try_from_data_value!(u16, "u16", |value| value.as_u64().and_then(|u| u16::try_from(u).ok()));
+try_from_data_value!(u32, "u32", |value| value.as_u64().and_then(|u| u32::try_from(u).ok()));
+try_from_data_value!(u64, "u64", |value| value.as_u64());
+try_from_data_value!(i8, "i8", |value| value.as_i64().and_then(|i| i8::try_from(i).ok()));
+try_from_data_value!(i16, "i16", |value| value.as_i64().and_then(|i| i16::try_from(i).ok()));
+try_from_data_value!(i32, "i32", |value| value.as_i64().and_then(|i| i32::try_from(i).ok()));
+try_from_data_value!(i64, "i64", |value| value.as_i64());
+try_from_data_value!(f32, "f32", |value| match *value {
+	DataValue::Float32(f) => Some(f),
+	_ => None,
+});
+try_from_data_value!(f64, "f64", |value| value.as_f64());
+try_from_data_value!(bool, "bool", |value| match *value {
+	DataValue::Boolean(b) => Some(b),
+	_ => None,
+});
+try_from_data_value!(&'a str, "str", |value| value.as_str());
+try_from_data_value!(String, "String", |value| value.as_str().map(str::to_string));
+try_from_data_value!(&'a [u8], "bytes", |value| value.as_bytes());
+try_from_data_value!(Vec<u8>, "bytes", |value| value.as_bytes().map(<[u8]>::to_vec));
+try_from_data_value!(Ipv4Addr, "Ipv4Addr", |value| match *value {
+	DataValue::Ipv4Address(addr) => Some(addr),
+	_ => None,
+});
+try_from_data_value!(Ipv6Addr, "Ipv6Addr", |value| match *value {
+	DataValue::Ipv6Address(addr) => Some(addr),
+	_ => None,
+});
+try_from_data_value!(IpAddr, "IpAddr", |value| value.as_ip_addr());
+try_from_data_value!(SystemTime, "SystemTime", |value| value.as_system_time());
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, UNIX_EPOCH};
+
+	#[test]
+	fn accessors_test() {
+		use DataValue::*;
+
+		assert_eq!(Unsigned8(8).as_u64(), Some(8));
+		assert_eq!(Unsigned32(32).as_u64(), Some(32));
+		assert_eq!(Signed8(-8).as_u64(), None);
+		assert_eq!(Signed16(-16).as_i64(), Some(-16));
+		assert_eq!(Unsigned8(8).as_i64(), None);
+		assert_eq!(Float32(0.5).as_f64(), Some(0.5));
+		assert_eq!(
+			Ipv4Address(Ipv4Addr::new(127, 0, 0, 1)).as_ip_addr(),
+			Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+		);
+		assert_eq!(String("eth0".to_string()).as_str(), Some("eth0"));
+		assert_eq!(OctetArray(vec![1, 2]).as_bytes(), Some(&[1u8, 2][..]));
+		assert_eq!(String("eth0".to_string()).as_bytes(), None);
+	}
+
+	#[test]
+	fn try_from_test() {
+		use DataValue::*;
+
+		assert_eq!(u64::try_from(&Unsigned16(443)), Ok(443));
+		assert_eq!(u16::try_from(&Unsigned64(443)), Ok(443));
+		assert_eq!(
+			u8::try_from(&Unsigned16(443)),
+			Err(TryFromDataValueError {
+				target : "u8",
+				value : Unsigned16(443),
+			})
+		);
+		assert!(u64::try_from(&Signed64(1)).is_err());
+		assert_eq!(i32::try_from(&Signed8(-1)), Ok(-1));
+		assert!(i8::try_from(&Signed32(-129)).is_err());
+		assert_eq!(f64::try_from(&Float32(0.5)), Ok(0.5));
+		assert!(f32::try_from(&Float64(0.5)).is_err());
+		assert_eq!(<&str>::try_from(&String("eth0".to_string())), Ok("eth0"));
+		assert_eq!(
+			IpAddr::try_from(&Ipv6Address(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))),
+			Ok(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+		);
+		assert!(Ipv4Addr::try_from(&Ipv6Address(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))).is_err());
+		assert_eq!(
+			SystemTime::try_from(&DateTimeSeconds(1)),
+			Ok(UNIX_EPOCH + Duration::from_secs(1))
+		);
+	}
+}
@@ -0,0 +1,216 @@
+use data_ref::*;
+use sink::*;
+use std;
+use std::io::Write;
+use structs::*;
+
+/// Writes one delimited line per data record, values formatted like `DataValue`'s `Display`.
+/// A header row of field names, see `unique_field_names`, is written before the first record
+/// and again whenever a template with different names follows.
+/// Values containing the delimiter, quotes or line breaks are quoted as in RFC 4180.
+pub struct CsvSink<W : Write> {
+	writer : W,
+	delimiter : u8,
+	/// Field names or keys like `"6871:18"` to write instead of all fields,
+	/// empty if a record has no such field
+	pub columns : Option<Vec<String>>,
+	header : Option<Vec<String>>,
+	// template of the last record and the index of each column in its fields
+	current : Option<(TemplateRecord, Vec<Option<usize>>)>,
+}
+
+impl<W : Write> CsvSink<W> {
+	/// Comma separated values
+	pub fn csv(writer : W) -> CsvSink<W> {
+		CsvSink::new(writer, b',')
+	}
+
+	/// Tab separated values
+	pub fn tsv(writer : W) -> CsvSink<W> {
+		CsvSink::new(writer, b'\t')
+	}
+
+	pub fn new(writer : W, delimiter : u8) -> CsvSink<W> {
+		CsvSink {
+			writer,
+			delimiter,
+			columns : None,
+			header : None,
+			current : None,
+		}
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+
+	fn write_line<I, S>(&mut self, values : I) -> std::io::Result<()>
+	where
+		I : IntoIterator<Item = S>,
+		S : AsRef<str>,
+	{
+		for (i, value) in values.into_iter().enumerate() {
+			if i > 0 {
+				self.writer.write_all(&[self.delimiter])?;
+			}
+			let value = value.as_ref();
+			if value.contains([self.delimiter as char, '"', '\n', '\r']) {
+				write!(self.writer, "\"{}\"", value.replace('"', "\"\""))?;
+			} else {
+				self.writer.write_all(value.as_bytes())?;
+			}
+		}
+		self.writer.write_all(b"\r\n")
+	}
+
+	// `DataValue`s or `DataValueRef`s
+	fn write_record<V>(&mut self, context : &RecordContext, fields : &[V]) -> std::io::Result<()>
+	where
+		V : std::fmt::Display,
+	{
+		let changed = match self.current {
+			Some((ref template, _)) => template != context.template,
+			None => true,
+		};
+		if changed {
+			let names = unique_field_names(context.template, context.registry);
+			let header = match self.columns {
+				Some(ref columns) => columns.clone(),
+				None => names.clone(),
+			};
+			let keys = context
+				.template
+				.scope_fields
+				.iter()
+				.chain(context.template.fields.iter())
+				.map(field_key)
+				.collect::<Vec<_>>();
+			let indexes = header
+				.iter()
+				.map(|column| {
+					names
+						.iter()
+						.position(|name| name == column)
+						.or_else(|| keys.iter().position(|key| key == column))
+				})
+				.collect();
+
+			if self.header.as_ref() != Some(&header) {
+				self.write_line(&header)?;
+				self.header = Some(header);
+			}
+			self.current = Some((context.template.clone(), indexes));
+		}
+
+		let values = match self.current {
+			Some((_, ref indexes)) => indexes
+				.iter()
+				.map(|index| match index.and_then(|index| fields.get(index)) {
+					Some(value) => value.to_string(),
+					None => String::new(),
+				})
+				.collect::<Vec<_>>(),
+			None => unreachable!(),
+		};
+		self.write_line(values)
+	}
+}
+
+impl<W : Write> Sink for CsvSink<W> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		self.write_record(context, &record.fields)
+	}
+
+	fn record_ref(
+		&mut self,
+		context : &RecordContext,
+		record : &DataRecordRef,
+	) -> std::io::Result<()> {
+		self.write_record(context, &record.fields)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use information_element::*;
+	use registry::*;
+	use std::net::Ipv4Addr;
+	use template_builder::*;
+
+	fn write(sink : &mut CsvSink<Vec<u8>>, records : &[(&TemplateRecord, DataRecord)]) -> String {
+		let registry = Registry::default();
+		let header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 0,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		for &(template, ref record) in records {
+			let context = RecordContext {
+				header : &header,
+				template,
+				registry : &registry,
+			};
+			sink.record(&context, record).unwrap();
+		}
+		String::from_utf8(std::mem::take(&mut sink.writer)).unwrap()
+	}
+
+	#[test]
+	fn csv_test() {
+		let registry = Registry::default();
+		let flows = TemplateBuilder::new(256, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::INTERFACE_NAME)
+			.build()
+			.unwrap();
+		let same_names = TemplateBuilder::new(257, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::INTERFACE_NAME)
+			.build()
+			.unwrap();
+		let times = TemplateBuilder::new(258, &registry)
+			.field(ie::FLOW_START_MILLISECONDS)
+			.build()
+			.unwrap();
+		let flow = |name : &str| DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::String(name.to_string()),
+			],
+		};
+		let time = DataRecord {
+			fields : vec![DataValue::DateTimeMilliseconds(1_518_864_590_123)],
+		};
+
+		let mut sink = CsvSink::csv(Vec::new());
+		assert_eq!(
+			write(
+				&mut sink,
+				&[
+					(&flows, flow("eth0")),
+					(&same_names, flow("uplink, \"wan\"")),
+					(&times, time.clone()),
+				]
+			),
+			"sourceIPv4Address,interfaceName\r\n\
+			 192.0.2.1,eth0\r\n\
+			 192.0.2.1,\"uplink, \"\"wan\"\"\"\r\n\
+			 flowStartMilliseconds\r\n\
+			 2018-02-17T10:49:50.123Z\r\n"
+		);
+
+		let mut sink = CsvSink::tsv(Vec::new());
+		sink.columns = Some(vec!["interfaceName".to_string(), "152".to_string()]);
+		assert_eq!(
+			write(&mut sink, &[(&flows, flow("eth0")), (&times, time)]),
+			"interfaceName\t152\r\neth0\t\r\n\t2018-02-17T10:49:50.123Z\r\n"
+		);
+	}
+}
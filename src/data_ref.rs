@@ -0,0 +1,263 @@
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use structs::*;
+
+/// `DataValue` borrowing octet arrays, MAC addresses and strings from the message.
+/// Strings are only owned if invalid UTF-8 was replaced.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataValueRef<'a> {
+	Unsigned8(u8),
+	Unsigned16(u16),
+	Unsigned32(u32),
+	Unsigned64(u64),
+	Signed8(i8),
+	Signed16(i16),
+	Signed32(i32),
+	Signed64(i64),
+	Float32(f32),
+	Float64(f64),
+	Boolean(bool),
+	MacAddress(&'a [u8]), // always length 6
+	OctetArray(&'a [u8]),
+	String(Cow<'a, str>),
+	DateTimeSeconds(u32),
+	DateTimeMilliseconds(u64),
+	DateTimeMicroseconds { seconds : u32, fraction : u32 },
+	DateTimeNanoseconds { seconds : u32, fraction : u32 },
+	Ipv4Address(Ipv4Addr),
+	Ipv6Address(Ipv6Addr),
+	BasicList,
+	SubTemplateList,
+	SubTemplateMultiList,
+}
+
+impl<'a> DataValueRef<'a> {
+	pub fn into_owned(self) -> DataValue {
+		use self::DataValueRef::*;
+
+		match self {
+			Unsigned8(u) => DataValue::Unsigned8(u),
+			Unsigned16(u) => DataValue::Unsigned16(u),
+			Unsigned32(u) => DataValue::Unsigned32(u),
+			Unsigned64(u) => DataValue::Unsigned64(u),
+			Signed8(i) => DataValue::Signed8(i),
+			Signed16(i) => DataValue::Signed16(i),
+			Signed32(i) => DataValue::Signed32(i),
+			Signed64(i) => DataValue::Signed64(i),
+			Float32(f) => DataValue::Float32(f),
+			Float64(f) => DataValue::Float64(f),
+			Boolean(b) => DataValue::Boolean(b),
+			MacAddress(bytes) => DataValue::MacAddress(bytes.to_vec()),
+			OctetArray(bytes) => DataValue::OctetArray(bytes.to_vec()),
+			String(s) => DataValue::String(s.into_owned()),
+			DateTimeSeconds(u) => DataValue::DateTimeSeconds(u),
+			DateTimeMilliseconds(u) => DataValue::DateTimeMilliseconds(u),
+			DateTimeMicroseconds { seconds, fraction } => {
+				DataValue::DateTimeMicroseconds { seconds, fraction }
+			}
+			DateTimeNanoseconds { seconds, fraction } => {
+				DataValue::DateTimeNanoseconds { seconds, fraction }
+			}
+			Ipv4Address(addr) => DataValue::Ipv4Address(addr),
+			Ipv6Address(addr) => DataValue::Ipv6Address(addr),
+			BasicList => DataValue::BasicList,
+			SubTemplateList => DataValue::SubTemplateList,
+			SubTemplateMultiList => DataValue::SubTemplateMultiList,
+		}
+	}
+}
+
+/// Same text as `DataValue`'s `Display`
+impl<'a> std::fmt::Display for DataValueRef<'a> {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use self::DataValueRef::*;
+
+		match *self {
+			Unsigned8(u) => write!(f, "{}", u),
+			Unsigned16(u) => write!(f, "{}", u),
+			Unsigned32(u) => write!(f, "{}", u),
+			Unsigned64(u) => write!(f, "{}", u),
+			Signed8(i) => write!(f, "{}", i),
+			Signed16(i) => write!(f, "{}", i),
+			Signed32(i) => write!(f, "{}", i),
+			Signed64(i) => write!(f, "{}", i),
+			Float32(x) => write!(f, "{}", x),
+			Float64(x) => write!(f, "{}", x),
+			Boolean(b) => write!(f, "{}", b),
+			MacAddress(addr) => write!(
+				f,
+				"{:02X}-{:02X}-{:02X}-{:02X}-{:02X}-{:02X}",
+				addr[0], addr[1], addr[2], addr[3], addr[4], addr[5]
+			),
+			OctetArray(arr) => {
+				for octet in arr {
+					write!(f, "{:02x}", octet)?;
+				}
+				Ok(())
+			}
+			String(ref st) => f.write_str(st),
+			DateTimeSeconds(_)
+			| DateTimeMilliseconds(_)
+			| DateTimeMicroseconds { .. }
+			| DateTimeNanoseconds { .. } => match self.clone().into_owned().to_rfc3339() {
+				Some(rfc3339) => f.write_str(&rfc3339),
+				None => write!(f, "{:?}", self),
+			},
+			Ipv4Address(addr) => write!(f, "{}", addr),
+			Ipv6Address(addr) => write!(f, "{}", addr),
+			BasicList => f.write_str("basicList"),
+			SubTemplateList => f.write_str("subTemplateList"),
+			SubTemplateMultiList => f.write_str("subTemplateMultiList"),
+		}
+	}
+}
+
+impl<'a> From<&'a DataValue> for DataValueRef<'a> {
+	fn from(value : &'a DataValue) -> DataValueRef<'a> {
+		use structs::DataValue::*;
+
+		match *value {
+			Unsigned8(u) => DataValueRef::Unsigned8(u),
+			Unsigned16(u) => DataValueRef::Unsigned16(u),
+			Unsigned32(u) => DataValueRef::Unsigned32(u),
+			Unsigned64(u) => DataValueRef::Unsigned64(u),
+			Signed8(i) => DataValueRef::Signed8(i),
+			Signed16(i) => DataValueRef::Signed16(i),
+			Signed32(i) => DataValueRef::Signed32(i),
+			Signed64(i) => DataValueRef::Signed64(i),
+			Float32(f) => DataValueRef::Float32(f),
+			Float64(f) => DataValueRef::Float64(f),
+			Boolean(b) => DataValueRef::Boolean(b),
+			MacAddress(ref bytes) => DataValueRef::MacAddress(bytes),
+			OctetArray(ref bytes) => DataValueRef::OctetArray(bytes),
+			String(ref s) => DataValueRef::String(Cow::Borrowed(s)),
+			DateTimeSeconds(u) => DataValueRef::DateTimeSeconds(u),
+			DateTimeMilliseconds(u) => DataValueRef::DateTimeMilliseconds(u),
+			DateTimeMicroseconds { seconds, fraction } => {
+				DataValueRef::DateTimeMicroseconds { seconds, fraction }
+			}
+			DateTimeNanoseconds { seconds, fraction } => {
+				DataValueRef::DateTimeNanoseconds { seconds, fraction }
+			}
+			Ipv4Address(addr) => DataValueRef::Ipv4Address(addr),
+			Ipv6Address(addr) => DataValueRef::Ipv6Address(addr),
+			BasicList => DataValueRef::BasicList,
+			SubTemplateList => DataValueRef::SubTemplateList,
+			SubTemplateMultiList => DataValueRef::SubTemplateMultiList,
+		}
+	}
+}
+
+impl<'a> From<DataValueRef<'a>> for DataValue {
+	fn from(value : DataValueRef<'a>) -> DataValue {
+		value.into_owned()
+	}
+}
+
+/// `DataRecord` of `DataValueRef`s, decoded by `data_records_ref_parser`.
+/// The values borrow from the message, but each record allocates its own `fields`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataRecordRef<'a> {
+	pub fields : Vec<DataValueRef<'a>>,
+}
+
+impl<'a> DataRecordRef<'a> {
+	pub fn into_owned(self) -> DataRecord {
+		DataRecord {
+			fields : self.fields.into_iter().map(DataValueRef::into_owned).collect(),
+		}
+	}
+}
+
+impl<'a, 'b> From<&'b DataRecordRef<'a>> for DataRecord {
+	fn from(record : &'b DataRecordRef<'a>) -> DataRecord {
+		DataRecord {
+			fields : record.fields.iter().cloned().map(DataValueRef::into_owned).collect(),
+		}
+	}
+}
+
+impl<'a> From<&'a DataRecord> for DataRecordRef<'a> {
+	fn from(record : &'a DataRecord) -> DataRecordRef<'a> {
+		DataRecordRef {
+			fields : record.fields.iter().map(DataValueRef::from).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use decoder_options::*;
+	use parser::*;
+	use registry::*;
+
+	#[test]
+	fn data_records_ref_parser_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 0,
+				field_count : 3,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 56, // sourceMacAddress
+					field_length : 6,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 82, // interfaceName
+					field_length : 0xffff,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 4, // protocolIdentifier
+					field_length : 1,
+					enterprise_number : None,
+				},
+			],
+		};
+		let data : &[u8] = &[0, 1, 2, 3, 4, 5, 4, b'e', b't', b'h', b'0', 17];
+		let (_, records) = data_records_ref_parser(
+			data,
+			data.len() as u16,
+			&template,
+			&Registry::default(),
+			&DecoderOptions::strict(),
+			&mut DecodeReport::default(),
+		)
+		.unwrap();
+
+		assert_eq!(
+			records,
+			vec![DataRecordRef {
+				fields : vec![
+					DataValueRef::MacAddress(&data[0..6]),
+					DataValueRef::String(Cow::Borrowed("eth0")),
+					DataValueRef::Unsigned8(17),
+				],
+			}]
+		);
+		match records[0].fields[0] {
+			DataValueRef::MacAddress(bytes) => assert_eq!(bytes.as_ptr(), data.as_ptr()),
+			_ => unreachable!(),
+		}
+		match records[0].fields[1] {
+			DataValueRef::String(Cow::Borrowed(_)) => {}
+			_ => panic!("string copied"),
+		}
+
+		let owned = records[0].clone().into_owned();
+		assert_eq!(
+			owned.fields,
+			vec![
+				DataValue::MacAddress(vec![0, 1, 2, 3, 4, 5]),
+				DataValue::String("eth0".to_string()),
+				DataValue::Unsigned8(17),
+			]
+		);
+		assert_eq!(DataRecordRef::from(&owned), records[0]);
+	}
+}
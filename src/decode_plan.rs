@@ -0,0 +1,380 @@
+use data_ref::*;
+use decoder_options::*;
+use nom::{Context, Err, IResult, Needed};
+use parser::error_kind;
+use registry::*;
+use std;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use structs::*;
+use template_management::*;
+
+/// Decoding steps of a template, compiled once instead of looking up
+/// the information element of every field of every record
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodePlan {
+	options : DecoderOptions,
+	fields : Vec<FieldDecoder>,
+	// sum of the field lengths if no field has variable length
+	record_length : Option<usize>,
+	// relaxations decided at compile time, counted once per record
+	report : DecodeReport,
+}
+
+// decoder of a verified field, lengths of the octet array variants may be `VARIABLE_LENGTH`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum FieldDecoder {
+	Unsigned8,
+	Unsigned16,
+	Unsigned32,
+	Unsigned64,
+	Signed8,
+	Signed16,
+	Signed32,
+	Signed64,
+	Float32,
+	Float64,
+	Boolean,
+	MacAddress,
+	OctetArray(u16),
+	String(u16),
+	DateTimeSeconds,
+	DateTimeMilliseconds,
+	DateTimeMicroseconds,
+	DateTimeNanoseconds,
+	Ipv4Address,
+	Ipv6Address,
+}
+
+impl DecodePlan {
+	/// Verifies the template like `verify_template_with`
+	pub fn compile(
+		template : &TemplateRecord,
+		registry : &Registry,
+		options : &DecoderOptions,
+	) -> Result<DecodePlan, VerifyTemplateError> {
+		verify_template_with(template, registry, options)?;
+
+		let mut report = DecodeReport::default();
+		let fields = template
+			.scope_fields
+			.iter()
+			.chain(template.fields.iter())
+			.map(|field| FieldDecoder::compile(field, registry, &mut report))
+			.collect::<Vec<_>>();
+		let record_length = fields
+			.iter()
+			.map(|field| field.length())
+			.sum::<Option<usize>>();
+
+		Ok(DecodePlan {
+			options : *options,
+			fields,
+			record_length,
+			report,
+		})
+	}
+
+	pub fn options(&self) -> &DecoderOptions {
+		&self.options
+	}
+
+	/// Length of every record of templates without variable length fields
+	pub fn record_length(&self) -> Option<usize> {
+		self.record_length
+	}
+
+	/// Data records of a set body, equivalent to `data_records_ref_parser`
+	pub fn decode<'a>(
+		&self,
+		input : &'a [u8],
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], Vec<DataRecordRef<'a>>> {
+		let mut records = match self.record_length {
+			Some(record_length) if record_length > 0 => {
+				Vec::with_capacity(input.len() / record_length)
+			}
+			_ => Vec::new(),
+		};
+
+		// at least 1 data record
+		let mut input = input;
+		loop {
+			// relaxations of incomplete records are not reported
+			let mut record_report = self.report;
+			match self.decode_record(input, &mut record_report) {
+				Ok((rest, record)) => {
+					input = rest;
+					records.push(record);
+					report.add(&record_report);
+				}
+				Err(_) if self.options.skip_trailing_octets && !records.is_empty() => {
+					report.trailing_octets += input.len() as u64;
+					input = &input[input.len()..];
+					break;
+				}
+				Err(e) => return Err(e),
+			}
+
+			if input.is_empty() {
+				break;
+			}
+		}
+		Ok((input, records))
+	}
+
+	fn decode_record<'a>(
+		&self,
+		input : &'a [u8],
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], DataRecordRef<'a>> {
+		if let Some(record_length) = self.record_length {
+			if input.len() < record_length {
+				return Err(Err::Incomplete(Needed::Size(record_length)));
+			}
+		}
+
+		let mut input = input;
+		let mut fields = Vec::with_capacity(self.fields.len());
+		for field in &self.fields {
+			let (rest, value) = field.decode(input, &self.options, report)?;
+			input = rest;
+			fields.push(value);
+		}
+		Ok((input, DataRecordRef { fields }))
+	}
+}
+
+impl FieldDecoder {
+	// relaxations of the field are added to `report`
+	pub(crate) fn compile(
+		field : &FieldSpecifier,
+		registry : &Registry,
+		report : &mut DecodeReport,
+	) -> FieldDecoder {
+		let length = field.field_length;
+		match registry.lookup_field(field) {
+			Some(information_element)
+				if verify_field_length(information_element.abstract_data_type, length).is_ok() =>
+			{
+				FieldDecoder::new(information_element.abstract_data_type, length)
+			}
+			Some(_) => {
+				report.raw_field_lengths += 1;
+				FieldDecoder::OctetArray(length)
+			}
+			None => {
+				report.unknown_fields += 1;
+				FieldDecoder::OctetArray(length)
+			}
+		}
+	}
+
+	// type and length are verified
+	fn new(abstract_data_type : AbstractDataType, length : u16) -> FieldDecoder {
+		use structs::AbstractDataType::*;
+
+		match (abstract_data_type, length) {
+			(Unsigned8, _) | (Unsigned16, 1) | (Unsigned32, 1) | (Unsigned64, 1) => {
+				FieldDecoder::Unsigned8
+			}
+			(Unsigned16, _) | (Unsigned32, 2) | (Unsigned64, 2) => FieldDecoder::Unsigned16,
+			(Unsigned32, _) | (Unsigned64, 4) => FieldDecoder::Unsigned32,
+			(Unsigned64, _) => FieldDecoder::Unsigned64,
+			(Signed8, _) | (Signed16, 1) | (Signed32, 1) | (Signed64, 1) => FieldDecoder::Signed8,
+			(Signed16, _) | (Signed32, 2) | (Signed64, 2) => FieldDecoder::Signed16,
+			(Signed32, _) | (Signed64, 4) => FieldDecoder::Signed32,
+			(Signed64, _) => FieldDecoder::Signed64,
+			(Float32, _) | (Float64, 4) => FieldDecoder::Float32,
+			(Float64, _) => FieldDecoder::Float64,
+			(Boolean, _) => FieldDecoder::Boolean,
+			(MacAddress, _) => FieldDecoder::MacAddress,
+			(OctetArray, length) => FieldDecoder::OctetArray(length),
+			(String, length) => FieldDecoder::String(length),
+			(DateTimeSeconds, _) => FieldDecoder::DateTimeSeconds,
+			(DateTimeMilliseconds, _) => FieldDecoder::DateTimeMilliseconds,
+			(DateTimeMicroseconds, _) => FieldDecoder::DateTimeMicroseconds,
+			(DateTimeNanoseconds, _) => FieldDecoder::DateTimeNanoseconds,
+			(Ipv4Address, _) => FieldDecoder::Ipv4Address,
+			(Ipv6Address, _) => FieldDecoder::Ipv6Address,
+			(BasicList, _) | (SubTemplateList, _) | (SubTemplateMultiList, _) => {
+				unreachable!("type not implemented")
+			}
+		}
+	}
+
+	pub(crate) fn length(self) -> Option<usize> {
+		use self::FieldDecoder::*;
+
+		match self {
+			Unsigned8 | Signed8 | Boolean => Some(1),
+			Unsigned16 | Signed16 => Some(2),
+			Unsigned32 | Signed32 | Float32 | DateTimeSeconds | Ipv4Address => Some(4),
+			Unsigned64 | Signed64 | Float64 => Some(8),
+			DateTimeMilliseconds | DateTimeMicroseconds | DateTimeNanoseconds => Some(8),
+			MacAddress => Some(6),
+			Ipv6Address => Some(16),
+			OctetArray(VARIABLE_LENGTH) | String(VARIABLE_LENGTH) => None,
+			OctetArray(length) | String(length) => Some(length as usize),
+		}
+	}
+
+	pub(crate) fn decode<'a>(
+		self,
+		input : &'a [u8],
+		options : &DecoderOptions,
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], DataValueRef<'a>> {
+		let (rest, bytes) = match self.length() {
+			Some(length) => take(input, length)?,
+			None => take_variable_length(input)?,
+		};
+
+		let value = match self {
+			FieldDecoder::Unsigned8 => DataValueRef::Unsigned8(bytes[0]),
+			FieldDecoder::Unsigned16 => DataValueRef::Unsigned16(u16::from_be_bytes(array(bytes))),
+			FieldDecoder::Unsigned32 => DataValueRef::Unsigned32(u32::from_be_bytes(array(bytes))),
+			FieldDecoder::Unsigned64 => DataValueRef::Unsigned64(u64::from_be_bytes(array(bytes))),
+			FieldDecoder::Signed8 => DataValueRef::Signed8(bytes[0] as i8),
+			FieldDecoder::Signed16 => DataValueRef::Signed16(i16::from_be_bytes(array(bytes))),
+			FieldDecoder::Signed32 => DataValueRef::Signed32(i32::from_be_bytes(array(bytes))),
+			FieldDecoder::Signed64 => DataValueRef::Signed64(i64::from_be_bytes(array(bytes))),
+			FieldDecoder::Float32 => DataValueRef::Float32(f32::from_be_bytes(array(bytes))),
+			FieldDecoder::Float64 => DataValueRef::Float64(f64::from_be_bytes(array(bytes))),
+			FieldDecoder::Boolean => match bytes[0] {
+				1 => DataValueRef::Boolean(true),
+				2 => DataValueRef::Boolean(false),
+				u if options.raw_booleans => {
+					report.raw_booleans += 1;
+					DataValueRef::Unsigned8(u)
+				}
+				_ => return Err(Err::Error(Context::Code(input, error_kind::BOOL_INVALID))),
+			},
+			FieldDecoder::MacAddress => DataValueRef::MacAddress(bytes),
+			FieldDecoder::OctetArray(_) => DataValueRef::OctetArray(bytes),
+			FieldDecoder::String(_) => match std::str::from_utf8(bytes) {
+				Ok(s) => DataValueRef::String(Cow::Borrowed(s)),
+				Err(_) if options.lossy_strings => {
+					report.lossy_strings += 1;
+					DataValueRef::String(std::string::String::from_utf8_lossy(bytes))
+				}
+				Err(_) => {
+					return Err(Err::Error(Context::Code(rest, error_kind::STRING_NOT_UTF8)))
+				}
+			},
+			FieldDecoder::DateTimeSeconds => {
+				DataValueRef::DateTimeSeconds(u32::from_be_bytes(array(bytes)))
+			}
+			FieldDecoder::DateTimeMilliseconds => {
+				DataValueRef::DateTimeMilliseconds(u64::from_be_bytes(array(bytes)))
+			}
+			FieldDecoder::DateTimeMicroseconds => DataValueRef::DateTimeMicroseconds {
+				seconds : u32::from_be_bytes(array(&bytes[..4])),
+				// ignore lower 11 Bit of fraction
+				fraction : u32::from_be_bytes(array(&bytes[4..])) & 0xFFFF_F800,
+			},
+			FieldDecoder::DateTimeNanoseconds => DataValueRef::DateTimeNanoseconds {
+				seconds : u32::from_be_bytes(array(&bytes[..4])),
+				fraction : u32::from_be_bytes(array(&bytes[4..])),
+			},
+			FieldDecoder::Ipv4Address => {
+				DataValueRef::Ipv4Address(Ipv4Addr::from(u32::from_be_bytes(array(bytes))))
+			}
+			FieldDecoder::Ipv6Address => DataValueRef::Ipv6Address(Ipv6Addr::from(
+				array::<[u8; 16]>(bytes),
+			)),
+		};
+		Ok((rest, value))
+	}
+}
+
+// slices are taken with the length of the array
+fn array<'a, A>(bytes : &'a [u8]) -> A
+where
+	&'a [u8] : TryInto<A>,
+{
+	match bytes.try_into() {
+		Ok(array) => array,
+		Err(_) => unreachable!(),
+	}
+}
+
+pub(crate) fn take(input : &[u8], length : usize) -> IResult<&[u8], &[u8]> {
+	if input.len() < length {
+		Err(Err::Incomplete(Needed::Size(length)))
+	} else {
+		Ok((&input[length..], &input[..length]))
+	}
+}
+
+pub(crate) fn take_variable_length(input : &[u8]) -> IResult<&[u8], &[u8]> {
+	match input.first() {
+		None => Err(Err::Incomplete(Needed::Size(1))),
+		Some(&VARIABLE_LENGTH_LONG_TAG) => {
+			let (rest, length) = take(&input[1..], 2)?;
+			take(rest, u16::from_be_bytes(array(length)) as usize)
+		}
+		Some(&length) => take(&input[1..], length as usize),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parser::*;
+	use test_util::*;
+
+	fn assert_same_as_parser(template : &TemplateRecord, data : &[u8], options : &DecoderOptions) {
+		let registry = Registry::default();
+		let plan = DecodePlan::compile(template, &registry, options).unwrap();
+
+		let mut report = DecodeReport::default();
+		let mut parser_report = DecodeReport::default();
+		assert_eq!(
+			plan.decode(data, &mut report),
+			data_records_ref_parser(
+				data,
+				data.len() as u16,
+				template,
+				&registry,
+				options,
+				&mut parser_report
+			)
+		);
+		assert_eq!(report, parser_report);
+	}
+
+	#[test]
+	fn fixed_length_test() {
+		// sourceIPv4Address, octetDeltaCount as unsigned32, flowStartMilliseconds, sourceMacAddress
+		let template = template(&[(8, 4), (1, 4), (152, 8), (56, 6)]);
+		let plan = DecodePlan::compile(&template, &Registry::default(), &DecoderOptions::strict())
+			.unwrap();
+		assert_eq!(plan.record_length(), Some(22));
+
+		let data = (0..44).collect::<Vec<u8>>();
+		assert_same_as_parser(&template, &data, &DecoderOptions::strict());
+		assert_same_as_parser(&template, &data[..40], &DecoderOptions::lenient());
+	}
+
+	#[test]
+	fn variable_length_test() {
+		// interfaceName, dataRecordsReliability, unknown, ipv6 as 3 octets
+		let template = template(&[(82, 0xffff), (276, 1), (32767, 2), (27, 3)]);
+		let plan = DecodePlan::compile(&template, &Registry::default(), &DecoderOptions::lenient())
+			.unwrap();
+		assert_eq!(plan.record_length(), None);
+		assert!(
+			DecodePlan::compile(&template, &Registry::default(), &DecoderOptions::strict())
+				.is_err()
+		);
+
+		let data : &[u8] = &[
+			2, 0xff, b'0', 1, 0xaa, 0xbb, 1, 2, 3, //
+			0xff, 0, 1, b'e', 3, 0xcc, 0xdd, 4, 5, 6, //
+			0xff,
+		];
+		assert_same_as_parser(&template, data, &DecoderOptions::lenient());
+	}
+}
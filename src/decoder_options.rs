@@ -0,0 +1,83 @@
+use std;
+
+/// Handling of template fields whose information element is not in the registry
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UnknownFieldPolicy {
+	/// the template is invalid
+	#[default]
+	Reject,
+	/// decoded as `OctetArray` of the field length,
+	/// keyed with `UNKNOWN_FIELD_PREFIX` in outputs
+	KeepRaw,
+}
+
+/// Strict RFC 7011 decoding by default, each option relaxes one kind of violation.
+/// What was relaxed is counted in a `DecodeReport`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DecoderOptions {
+	pub unknown_fields : UnknownFieldPolicy,
+	/// fields of known information elements with lengths not valid for their type
+	/// are decoded as `OctetArray`
+	pub raw_field_lengths : bool,
+	/// booleans other than 1 (true) and 2 (false) are decoded as `Unsigned8`
+	pub raw_booleans : bool,
+	/// invalid UTF-8 in strings is replaced with U+FFFD
+	pub lossy_strings : bool,
+	/// octets after the last complete data record of a set are skipped
+	pub skip_trailing_octets : bool,
+}
+
+impl DecoderOptions {
+	pub fn strict() -> DecoderOptions {
+		DecoderOptions::default()
+	}
+
+	pub fn lenient() -> DecoderOptions {
+		DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			raw_field_lengths : true,
+			raw_booleans : true,
+			lossy_strings : true,
+			skip_trailing_octets : true,
+		}
+	}
+}
+
+/// Number of fields or octets decoded only because of relaxed `DecoderOptions`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DecodeReport {
+	pub unknown_fields : u64,
+	pub raw_field_lengths : u64,
+	pub raw_booleans : u64,
+	pub lossy_strings : u64,
+	pub trailing_octets : u64,
+}
+
+impl DecodeReport {
+	pub fn is_empty(&self) -> bool {
+		*self == DecodeReport::default()
+	}
+
+	pub fn add(&mut self, other : &DecodeReport) {
+		self.unknown_fields += other.unknown_fields;
+		self.raw_field_lengths += other.raw_field_lengths;
+		self.raw_booleans += other.raw_booleans;
+		self.lossy_strings += other.lossy_strings;
+		self.trailing_octets += other.trailing_octets;
+	}
+}
+
+impl std::fmt::Display for DecodeReport {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		write!(
+			f,
+			"{} unknown fields, {} raw field lengths, {} raw booleans, {} lossy strings, \
+			 {} trailing octets",
+			self.unknown_fields,
+			self.raw_field_lengths,
+			self.raw_booleans,
+			self.lossy_strings,
+			self.trailing_octets
+		)
+	}
+}
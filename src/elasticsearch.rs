@@ -0,0 +1,409 @@
+use flow_time::*;
+use information_element::ie;
+use registry::*;
+use serde_json;
+use serde_json::{Map, Value};
+use sink::*;
+use std;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use structs::*;
+use timestamp::{civil_from_days, format_rfc3339};
+
+/// Keys of the documents written by `BulkSink`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FieldMapping {
+	/// Information element names, e.g. `"sourceIPv4Address"`
+	#[default]
+	Ipfix,
+	/// Elastic Common Schema fields like `source.ip` and `network.bytes` where one exists,
+	/// other information elements below `ipfix`, e.g. `ipfix.flowEndReason`
+	Ecs,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BulkOptions {
+	/// Index name with `%Y`, `%m`, `%d` and `%H` replaced by the UTC flow start time
+	pub index : String,
+	pub mapping : FieldMapping,
+}
+
+impl Default for BulkOptions {
+	fn default() -> BulkOptions {
+		BulkOptions {
+			index : "ipfix-%Y.%m.%d".to_string(),
+			mapping : FieldMapping::Ipfix,
+		}
+	}
+}
+
+// information element, ECS field and its type in the ECS index template
+const ECS_FIELDS : &[(&str, &str, &str)] = &[
+	("sourceIPv4Address", "source.ip", "ip"),
+	("sourceIPv6Address", "source.ip", "ip"),
+	("destinationIPv4Address", "destination.ip", "ip"),
+	("destinationIPv6Address", "destination.ip", "ip"),
+	("sourceTransportPort", "source.port", "long"),
+	("destinationTransportPort", "destination.port", "long"),
+	("sourceMacAddress", "source.mac", "keyword"),
+	("destinationMacAddress", "destination.mac", "keyword"),
+	("postNATSourceIPv4Address", "source.nat.ip", "ip"),
+	("postNATSourceIPv6Address", "source.nat.ip", "ip"),
+	("postNATDestinationIPv4Address", "destination.nat.ip", "ip"),
+	("postNATDestinationIPv6Address", "destination.nat.ip", "ip"),
+	("postNAPTSourceTransportPort", "source.nat.port", "long"),
+	("postNAPTDestinationTransportPort", "destination.nat.port", "long"),
+	("bgpSourceAsNumber", "source.as.number", "long"),
+	("bgpDestinationAsNumber", "destination.as.number", "long"),
+	("protocolIdentifier", "network.iana_number", "keyword"),
+	("octetDeltaCount", "network.bytes", "long"),
+	("packetDeltaCount", "network.packets", "long"),
+	("vlanId", "network.vlan.id", "keyword"),
+	("ingressInterface", "observer.ingress.interface.id", "keyword"),
+	("egressInterface", "observer.egress.interface.id", "keyword"),
+];
+
+/// Writes data records as Elasticsearch and OpenSearch bulk API requests,
+/// an index action line followed by the document.
+/// Documents have an `@timestamp` of the flow start, or the export time if unknown,
+/// addresses as strings for `ip` fields and times in RFC 3339.
+pub struct BulkSink<W : Write> {
+	writer : W,
+	pub options : BulkOptions,
+	times : FlowTimeNormalizer,
+}
+
+impl<W : Write> BulkSink<W> {
+	pub fn new(writer : W, options : BulkOptions) -> BulkSink<W> {
+		BulkSink {
+			writer,
+			options,
+			times : FlowTimeNormalizer::default(),
+		}
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+impl<W : Write> Sink for BulkSink<W> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		let times = self.times.normalize(context, record);
+		let export_time = UNIX_EPOCH + Duration::from_secs(context.header.export_time as u64);
+		let timestamp = times.start.or(times.end).unwrap_or(export_time);
+
+		let action = json!({ "index": { "_index": index_name(&self.options.index, timestamp) } });
+		serde_json::to_writer(&mut self.writer, &action)?;
+		self.writer.write_all(b"\n")?;
+		let document = bulk_document(context, record, &times, timestamp, self.options.mapping);
+		serde_json::to_writer(&mut self.writer, &document)?;
+		self.writer.write_all(b"\n")
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+/// Index name of a pattern like `"ipfix-%Y.%m.%d"` at a time, `%%` is a literal `%`
+pub fn index_name(pattern : &str, time : SystemTime) -> String {
+	let seconds = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+	let (year, month, day) = civil_from_days(seconds / 86400);
+
+	let mut name = String::with_capacity(pattern.len() + 4);
+	let mut chars = pattern.chars();
+	while let Some(c) = chars.next() {
+		if c != '%' {
+			name.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('Y') => name.push_str(&format!("{:04}", year)),
+			Some('m') => name.push_str(&format!("{:02}", month)),
+			Some('d') => name.push_str(&format!("{:02}", day)),
+			Some('H') => name.push_str(&format!("{:02}", seconds % 86400 / 3600)),
+			Some(c) => name.push(c),
+			None => name.push('%'),
+		}
+	}
+	name
+}
+
+fn bulk_document(
+	context : &RecordContext,
+	record : &DataRecord,
+	times : &FlowTimes,
+	timestamp : SystemTime,
+	mapping : FieldMapping,
+) -> Value {
+	let mut document = Map::new();
+	document.insert("@timestamp".to_string(), time_value(timestamp));
+
+	let names = unique_field_names(context.template, context.registry);
+	for (name, value) in names.iter().zip(record.fields.iter()) {
+		let value = match *value {
+			DataValue::OctetArray(_)
+			| DataValue::BasicList
+			| DataValue::SubTemplateList
+			| DataValue::SubTemplateMultiList => Value::String(value.to_string()),
+			_ => match value.to_rfc3339() {
+				Some(rfc3339) => Value::String(rfc3339),
+				None => serde_json::to_value(value).unwrap_or(Value::Null),
+			},
+		};
+		match (mapping, ECS_FIELDS.iter().find(|&&(ie, _, _)| ie == name)) {
+			(FieldMapping::Ipfix, _) => {
+				document.insert(name.clone(), value);
+			}
+			// numeric identifiers are keywords in ECS
+			(FieldMapping::Ecs, Some(&(_, field, "keyword"))) if value.is_number() => {
+				insert_dotted(&mut document, field, Value::String(value.to_string()))
+			}
+			(FieldMapping::Ecs, Some(&(_, field, _))) => insert_dotted(&mut document, field, value),
+			(FieldMapping::Ecs, None) => {
+				insert_dotted(&mut document, &format!("ipfix.{}", name), value)
+			}
+		}
+	}
+
+	if mapping == FieldMapping::Ecs {
+		if let Some(start) = times.start {
+			insert_dotted(&mut document, "event.start", time_value(start));
+		}
+		if let Some(end) = times.end {
+			insert_dotted(&mut document, "event.end", time_value(end));
+		}
+		if let (Some(start), Some(end)) = (times.start, times.end) {
+			if let Ok(duration) = end.duration_since(start) {
+				insert_dotted(&mut document, "event.duration", json!(duration.as_nanos() as u64));
+			}
+		}
+		let protocol = context.typed(record).get(ie::PROTOCOL_IDENTIFIER);
+		let transport = match protocol.and_then(DataValue::as_u64) {
+			Some(1) => Some("icmp"),
+			Some(6) => Some("tcp"),
+			Some(17) => Some("udp"),
+			Some(58) => Some("ipv6-icmp"),
+			Some(132) => Some("sctp"),
+			_ => None,
+		};
+		if let Some(transport) = transport {
+			insert_dotted(&mut document, "network.transport", json!(transport));
+		}
+	}
+	Value::Object(document)
+}
+
+fn time_value(time : SystemTime) -> Value {
+	let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+	Value::String(format_rfc3339(duration, 3))
+}
+
+// "a.b" as {"a": {"b": value}}, kept dotted if "a" is no object
+fn insert_dotted(map : &mut Map<String, Value>, key : &str, value : Value) {
+	match key.find('.') {
+		None => {
+			map.insert(key.to_string(), value);
+		}
+		Some(dot) => {
+			let object = map
+				.entry(key[..dot].to_string())
+				.or_insert_with(|| Value::Object(Map::new()));
+			match *object {
+				Value::Object(ref mut object) => insert_dotted(object, &key[dot + 1..], value),
+				_ => {
+					map.insert(key.to_string(), value);
+				}
+			}
+		}
+	}
+}
+
+/// Composable index template for the indices of `options`
+/// with a mapping of every information element of the registry
+pub fn index_template(registry : &Registry, options : &BulkOptions) -> Value {
+	let mut properties = Map::new();
+	insert_dotted(&mut properties, "@timestamp", json!({ "type": "date" }));
+	let prefix = match options.mapping {
+		FieldMapping::Ipfix => "",
+		FieldMapping::Ecs => "ipfix.",
+	};
+	for information_element in registry.elements() {
+		let name = &information_element.name[..];
+		if let Some(field_type) = field_type(information_element.abstract_data_type) {
+			let path = format!("{}{}", prefix, name);
+			insert_property(&mut properties, &path, field_type);
+		}
+	}
+	if options.mapping == FieldMapping::Ecs {
+		for &(_, field, field_type) in ECS_FIELDS {
+			insert_property(&mut properties, field, field_type);
+		}
+		insert_property(&mut properties, "event.start", "date");
+		insert_property(&mut properties, "event.end", "date");
+		insert_property(&mut properties, "event.duration", "long");
+		insert_property(&mut properties, "network.transport", "keyword");
+	}
+
+	let pattern = match options.index.find('%') {
+		Some(i) => format!("{}*", &options.index[..i]),
+		None => options.index.clone(),
+	};
+	json!({
+		"index_patterns": [pattern],
+		"template": {
+			"mappings": {
+				"properties": properties,
+			},
+		},
+	})
+}
+
+// "a.b" as {"a": {"properties": {"b": {"type": field_type}}}}
+fn insert_property(properties : &mut Map<String, Value>, path : &str, field_type : &str) {
+	match path.find('.') {
+		None => {
+			properties.insert(path.to_string(), json!({ "type": field_type }));
+		}
+		Some(dot) => {
+			let object = properties
+				.entry(path[..dot].to_string())
+				.or_insert_with(|| json!({ "properties": {} }));
+			if let Some(&mut Value::Object(ref mut nested)) = object.get_mut("properties") {
+				insert_property(nested, &path[dot + 1..], field_type);
+			}
+		}
+	}
+}
+
+fn field_type(abstract_data_type : AbstractDataType) -> Option<&'static str> {
+	use structs::AbstractDataType::*;
+
+	Some(match abstract_data_type {
+		Unsigned8 | Signed16 => "short",
+		Unsigned16 | Signed32 => "integer",
+		Unsigned32 | Signed64 => "long",
+		Unsigned64 => "unsigned_long",
+		Signed8 => "byte",
+		Float32 => "float",
+		Float64 => "double",
+		Boolean => "boolean",
+		MacAddress | OctetArray | String => "keyword",
+		DateTimeSeconds | DateTimeMilliseconds => "date",
+		DateTimeMicroseconds | DateTimeNanoseconds => "date_nanos",
+		Ipv4Address | Ipv6Address => "ip",
+		BasicList | SubTemplateList | SubTemplateMultiList => return None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::Ipv4Addr;
+	use template_builder::*;
+
+	#[test]
+	fn index_name_test() {
+		let time = UNIX_EPOCH + Duration::from_secs(1_518_864_590);
+		assert_eq!(index_name("ipfix-%Y.%m.%d", time), "ipfix-2018.02.17");
+		assert_eq!(index_name("flows-%Y%m%d%H-100%%", time), "flows-2018021710-100%");
+	}
+
+	#[test]
+	fn bulk_test() {
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::PROTOCOL_IDENTIFIER)
+			.field(ie::OCTET_DELTA_COUNT)
+			.field(ie::FLOW_START_MILLISECONDS)
+			.field(ie::FLOW_END_MILLISECONDS)
+			.field(ie::FLOW_END_REASON)
+			.build()
+			.unwrap();
+		let header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 1_518_900_000,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let record = DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::Unsigned8(6),
+				DataValue::Unsigned64(1500),
+				DataValue::DateTimeMilliseconds(1_518_864_590_123),
+				DataValue::DateTimeMilliseconds(1_518_864_591_123),
+				DataValue::Unsigned8(3),
+			],
+		};
+		let context = RecordContext {
+			header : &header,
+			template : &template,
+			registry : &registry,
+		};
+		let bulk = |mapping| {
+			let options = BulkOptions {
+				mapping,
+				..BulkOptions::default()
+			};
+			let mut sink = BulkSink::new(Vec::new(), options);
+			sink.record(&context, &record).unwrap();
+			let lines = String::from_utf8(sink.into_inner()).unwrap();
+			lines
+				.lines()
+				.map(|line| serde_json::from_str::<Value>(line).unwrap())
+				.collect::<Vec<_>>()
+		};
+
+		let lines = bulk(FieldMapping::Ipfix);
+		assert_eq!(lines[0], json!({ "index": { "_index": "ipfix-2018.02.17" } }));
+		assert_eq!(lines[1]["@timestamp"], "2018-02-17T10:49:50.123Z");
+		assert_eq!(lines[1]["sourceIPv4Address"], "192.0.2.1");
+		assert_eq!(lines[1]["flowEndMilliseconds"], "2018-02-17T10:49:51.123Z");
+
+		let lines = bulk(FieldMapping::Ecs);
+		assert_eq!(
+			lines[1],
+			json!({
+				"@timestamp": "2018-02-17T10:49:50.123Z",
+				"source": { "ip": "192.0.2.1" },
+				"network": { "iana_number": "6", "bytes": 1500, "transport": "tcp" },
+				"event": {
+					"start": "2018-02-17T10:49:50.123Z",
+					"end": "2018-02-17T10:49:51.123Z",
+					"duration": 1_000_000_000u64,
+				},
+				"ipfix": {
+					"flowStartMilliseconds": "2018-02-17T10:49:50.123Z",
+					"flowEndMilliseconds": "2018-02-17T10:49:51.123Z",
+					"flowEndReason": 3,
+				},
+			})
+		);
+	}
+
+	#[test]
+	fn index_template_test() {
+		let registry = Registry::default();
+		let template = index_template(&registry, &BulkOptions::default());
+		assert_eq!(template["index_patterns"], json!(["ipfix-*"]));
+		let properties = &template["template"]["mappings"]["properties"];
+		assert_eq!(properties["sourceIPv6Address"]["type"], "ip");
+		assert_eq!(properties["octetDeltaCount"]["type"], "unsigned_long");
+		assert_eq!(properties["flowStartNanoseconds"]["type"], "date_nanos");
+
+		let options = BulkOptions {
+			index : "flows-%Y".to_string(),
+			mapping : FieldMapping::Ecs,
+		};
+		let template = index_template(&registry, &options);
+		assert_eq!(template["index_patterns"], json!(["flows-*"]));
+		let properties = &template["template"]["mappings"]["properties"];
+		assert_eq!(properties["source"]["properties"]["ip"]["type"], "ip");
+		assert_eq!(properties["source"]["properties"]["nat"]["properties"]["port"]["type"], "long");
+		assert_eq!(properties["ipfix"]["properties"]["flowEndReason"]["type"], "short");
+	}
+}
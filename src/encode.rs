@@ -0,0 +1,394 @@
+use std;
+use structs::*;
+
+// Encodes messages for replaying stored templates and records.
+// Values are written in the field length of the template,
+// fixed length fields must match the value exactly.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncodeError {
+	FieldCount { expected : usize, actual : usize },
+	FieldLength { index : usize, field_length : u16, value : DataValue },
+	NotImplemented(DataValue),
+	TooLong,
+	/// templates with and without scope fields belong in different sets
+	MixedTemplates,
+}
+
+impl std::fmt::Display for EncodeError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use EncodeError::*;
+
+		match *self {
+			FieldCount { expected, actual } => write!(
+				f,
+				"template has {} fields, record has {}",
+				expected, actual
+			),
+			FieldLength {
+				index,
+				field_length,
+				ref value,
+			} => write!(
+				f,
+				"field {} of length {} can't hold {:?}",
+				index, field_length, value
+			),
+			NotImplemented(ref value) => write!(f, "encoding {:?} not implemented", value),
+			TooLong => write!(f, "message longer than 65535 octets"),
+			MixedTemplates => write!(f, "options templates and templates in one set"),
+		}
+	}
+}
+
+/// Message with the given sets, the length of the header is overwritten
+pub fn encode_message(header : &MessageHeader, sets : &[Vec<u8>]) -> Result<Vec<u8>, EncodeError> {
+	let length = sets
+		.iter()
+		.fold(MESSAGE_HEADER_LENGTH as usize, |length, set| length + set.len());
+	if length > 0xffff {
+		return Err(EncodeError::TooLong);
+	}
+
+	let mut message = Vec::with_capacity(length);
+	push_u16(&mut message, IPFIX_VERSION_NUMBER);
+	push_u16(&mut message, length as u16);
+	push_u32(&mut message, header.export_time);
+	push_u32(&mut message, header.sequence_number);
+	push_u32(&mut message, header.observation_domain_id);
+	for set in sets {
+		message.extend_from_slice(set);
+	}
+	Ok(message)
+}
+
+/// Template set, or options template set if the templates have scope fields
+pub fn encode_template_set(templates : &[TemplateRecord]) -> Result<Vec<u8>, EncodeError> {
+	let is_options = templates
+		.iter()
+		.any(|template| !template.scope_fields.is_empty());
+	// RFC 7011 3.4.2.2, options templates have at least one scope field
+	if is_options && templates.iter().any(|template| template.scope_fields.is_empty()) {
+		return Err(EncodeError::MixedTemplates);
+	}
+	let mut set = set_header(if is_options {
+		OPTIONS_TEMPLATE_SET_ID
+	} else {
+		TEMPLATE_SET_ID
+	});
+	for template in templates {
+		push_u16(&mut set, template.header.template_id);
+		push_u16(
+			&mut set,
+			(template.scope_fields.len() + template.fields.len()) as u16,
+		);
+		if is_options {
+			push_u16(&mut set, template.scope_fields.len() as u16);
+		}
+		for field in template.scope_fields.iter().chain(template.fields.iter()) {
+			match field.enterprise_number {
+				Some(enterprise_number) => {
+					push_u16(&mut set, field.information_element_id | 0x8000);
+					push_u16(&mut set, field.field_length);
+					push_u32(&mut set, enterprise_number);
+				}
+				None => {
+					push_u16(&mut set, field.information_element_id);
+					push_u16(&mut set, field.field_length);
+				}
+			}
+		}
+	}
+	finish_set(set)
+}
+
+pub fn encode_data_set(
+	template : &TemplateRecord,
+	records : &[DataRecord],
+) -> Result<Vec<u8>, EncodeError> {
+	let mut set = set_header(template.header.template_id);
+	for record in records {
+		encode_data_record(template, record, &mut set)?;
+	}
+	finish_set(set)
+}
+
+pub fn encode_data_record(
+	template : &TemplateRecord,
+	record : &DataRecord,
+	out : &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+	let fields = template.scope_fields.len() + template.fields.len();
+	if record.fields.len() != fields {
+		return Err(EncodeError::FieldCount {
+			expected : fields,
+			actual : record.fields.len(),
+		});
+	}
+
+	for (index, (field, value)) in template
+		.scope_fields
+		.iter()
+		.chain(template.fields.iter())
+		.zip(record.fields.iter())
+		.enumerate()
+	{
+		encode_data_value(value, field.field_length, out).map_err(|e| match e {
+			EncodeError::FieldLength { .. } => EncodeError::FieldLength {
+				index,
+				field_length : field.field_length,
+				value : value.clone(),
+			},
+			e => e,
+		})?;
+	}
+	Ok(())
+}
+
+fn encode_data_value(
+	value : &DataValue,
+	field_length : u16,
+	out : &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+	use DataValue::*;
+
+	let length_error = || EncodeError::FieldLength {
+		index : 0,
+		field_length,
+		value : value.clone(),
+	};
+	let bytes = match *value {
+		Unsigned8(u) => vec![u],
+		Unsigned16(u) => u.to_be_bytes().to_vec(),
+		Unsigned32(u) | DateTimeSeconds(u) => u.to_be_bytes().to_vec(),
+		Unsigned64(u) | DateTimeMilliseconds(u) => u.to_be_bytes().to_vec(),
+		Signed8(i) => i.to_be_bytes().to_vec(),
+		Signed16(i) => i.to_be_bytes().to_vec(),
+		Signed32(i) => i.to_be_bytes().to_vec(),
+		Signed64(i) => i.to_be_bytes().to_vec(),
+		Float32(f) => f.to_be_bytes().to_vec(),
+		Float64(f) => f.to_be_bytes().to_vec(),
+		Boolean(b) => vec![if b { 1 } else { 2 }],
+		MacAddress(ref bytes) | OctetArray(ref bytes) => bytes.clone(),
+		String(ref s) => s.as_bytes().to_vec(),
+		DateTimeMicroseconds { seconds, fraction } | DateTimeNanoseconds { seconds, fraction } => {
+			let mut bytes = seconds.to_be_bytes().to_vec();
+			bytes.extend_from_slice(&fraction.to_be_bytes());
+			bytes
+		}
+		Ipv4Address(addr) => addr.octets().to_vec(),
+		Ipv6Address(addr) => addr.octets().to_vec(),
+		BasicList | SubTemplateList | SubTemplateMultiList => {
+			return Err(EncodeError::NotImplemented(value.clone()))
+		}
+	};
+
+	if field_length == 0xffff {
+		match bytes.len() {
+			length if length < VARIABLE_LENGTH_LONG_TAG as usize => out.push(length as u8),
+			length if length <= 0xffff => {
+				out.push(VARIABLE_LENGTH_LONG_TAG);
+				push_u16(out, length as u16);
+			}
+			_ => return Err(length_error()),
+		}
+	} else if bytes.len() != field_length as usize {
+		return Err(length_error());
+	}
+	out.extend_from_slice(&bytes);
+	Ok(())
+}
+
+fn set_header(set_id : u16) -> Vec<u8> {
+	let mut set = Vec::new();
+	push_u16(&mut set, set_id);
+	push_u16(&mut set, 0); // length, written by finish_set
+	set
+}
+
+fn finish_set(mut set : Vec<u8>) -> Result<Vec<u8>, EncodeError> {
+	if set.len() > 0xffff {
+		return Err(EncodeError::TooLong);
+	}
+	let length = (set.len() as u16).to_be_bytes();
+	set[2..4].copy_from_slice(&length);
+	Ok(set)
+}
+
+fn push_u16(out : &mut Vec<u8>, u : u16) {
+	out.extend_from_slice(&u.to_be_bytes());
+}
+
+fn push_u32(out : &mut Vec<u8>, u : u32) {
+	out.extend_from_slice(&u.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use decoder_options::*;
+	use parser::*;
+	use registry::*;
+
+	#[test]
+	fn round_trip_test() {
+		let registry = Registry::default();
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 3,
+				scope_field_count : 0,
+			},
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 8,
+					field_length : 4,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 2,
+					field_length : 4,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 82,
+					field_length : 0xffff,
+					enterprise_number : None,
+				},
+			],
+			scope_fields : vec![],
+		};
+		let records = vec![
+			DataRecord {
+				fields : vec![
+					DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+					DataValue::Unsigned32(10),
+					DataValue::String("eth0".to_string()),
+				],
+			},
+			DataRecord {
+				fields : vec![
+					DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 2)),
+					DataValue::Unsigned32(20),
+					DataValue::String("x".repeat(300)),
+				],
+			},
+		];
+		let header = MessageHeader {
+			version_number : IPFIX_VERSION_NUMBER,
+			length : 0,
+			export_time : 1,
+			sequence_number : 2,
+			observation_domain_id : 3,
+		};
+		let bytes = encode_message(
+			&header,
+			&[
+				encode_template_set(std::slice::from_ref(&template)).unwrap(),
+				encode_data_set(&template, &records).unwrap(),
+			],
+		).unwrap();
+
+		let (rest, message) = message_parser(&bytes).unwrap();
+		assert!(rest.is_empty());
+		assert_eq!(message.header.length as usize, bytes.len());
+		assert_eq!(message.sets.len(), 2);
+
+		let (_, templates) = template_records_parser(message.sets[0].1, message.sets[0].0).unwrap();
+		assert_eq!(templates, vec![template.clone()]);
+
+		let (set_header, data) = message.sets[1];
+		assert_eq!(set_header.set_id, 256);
+		let (_, parsed) = data_records_parser(
+			data,
+			data.len() as u16,
+			&template,
+			&registry,
+			&DecoderOptions::strict(),
+			&mut DecodeReport::default(),
+		)
+		.unwrap();
+		assert_eq!(parsed, records);
+	}
+
+	#[test]
+	fn field_length_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 1,
+				scope_field_count : 0,
+			},
+			fields : vec![FieldSpecifier {
+				information_element_id : 2,
+				field_length : 8,
+				enterprise_number : None,
+			}],
+			scope_fields : vec![],
+		};
+		let record = DataRecord {
+			fields : vec![DataValue::Unsigned32(10)],
+		};
+		assert_eq!(
+			encode_data_set(&template, &[record]),
+			Err(EncodeError::FieldLength {
+				index : 0,
+				field_length : 8,
+				value : DataValue::Unsigned32(10),
+			})
+		);
+		assert_eq!(
+			encode_data_set(&template, &[DataRecord { fields : vec![] }]),
+			Err(EncodeError::FieldCount {
+				expected : 1,
+				actual : 0,
+			})
+		);
+	}
+	#[test]
+	fn template_set_test() {
+		let field = |information_element_id| FieldSpecifier {
+			information_element_id,
+			field_length : 4,
+			enterprise_number : None,
+		};
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 1,
+				scope_field_count : 0,
+			},
+			fields : vec![field(8)],
+			scope_fields : vec![],
+		};
+		let options_template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 257,
+				field_count : 2,
+				scope_field_count : 1,
+			},
+			fields : vec![field(41)],
+			scope_fields : vec![field(149)],
+		};
+
+		let header = MessageHeader {
+			version_number : IPFIX_VERSION_NUMBER,
+			length : 0,
+			export_time : 1,
+			sequence_number : 2,
+			observation_domain_id : 3,
+		};
+		let set = encode_template_set(std::slice::from_ref(&options_template)).unwrap();
+		let bytes = encode_message(&header, &[set]).unwrap();
+		let (_, message) = message_parser(&bytes).unwrap();
+		let (set_header, data) = message.sets[0];
+		assert_eq!(set_header.set_id, OPTIONS_TEMPLATE_SET_ID);
+		let (_, templates) = template_records_parser(data, set_header).unwrap();
+		assert_eq!(templates, vec![options_template.clone()]);
+
+		assert_eq!(
+			encode_template_set(&[template, options_template]),
+			Err(EncodeError::MixedTemplates)
+		);
+	}
+}
@@ -0,0 +1,661 @@
+use registry::*;
+use sink::*;
+use std;
+use std::cmp::Ordering;
+use std::net::IpAddr;
+use structs::*;
+
+/// Predicate over data records like
+/// `protocolIdentifier == 6 and destinationTransportPort in (22, 3389)`.
+///
+/// Fields are information element names, compared with `==`, `!=`, `<`, `<=`, `>`, `>=`
+/// or tested for membership with `in` and `not in`, a name alone tests for presence.
+/// Values are integers, floats, `true` and `false`, quoted strings, IPv4 and IPv6
+/// addresses, CIDR networks like `10.0.0.0/8` which match by containment and can't be
+/// ordered, and MAC addresses like `00-1b-21-3c-4d-5e`. `dateTime*` fields compare with seconds
+/// since the Unix epoch. Predicates combine with `and`, `or`, `not` and parentheses,
+/// `&&`, `||` and `!` are accepted as well.
+///
+/// Comparisons and membership tests, `!=` and `not in` included, of fields missing from a
+/// record or holding a value of another type, like raw values of lenient decoding, are false.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+	expression : Expression,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterError {
+	/// byte offset in the expression
+	Syntax {
+		position : usize,
+		expected : &'static str,
+	},
+	UnknownName(String),
+	/// value that can't be compared with the abstract data type of the field
+	Type {
+		field : String,
+		value : String,
+	},
+}
+
+impl std::fmt::Display for FilterError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use FilterError::*;
+
+		match *self {
+			Syntax { position, expected } => write!(f, "expected {} at {}", expected, position),
+			UnknownName(ref name) => write!(f, "information element {} not found", name),
+			Type { ref field, ref value } => {
+				write!(f, "{} can't be compared with {}", field, value)
+			}
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+	And(Box<Expression>, Box<Expression>),
+	Or(Box<Expression>, Box<Expression>),
+	Not(Box<Expression>),
+	Exists(Field),
+	Compare(Field, Operator, Literal),
+	In(Field, Vec<Literal>),
+	NotIn(Field, Vec<Literal>),
+}
+
+type Field = (Option<u32>, u16);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+	Eq,
+	Ne,
+	Lt,
+	Le,
+	Gt,
+	Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+	Integer(i128),
+	Float(f64),
+	Boolean(bool),
+	String(String),
+	Address(IpAddr),
+	Network(IpAddr, u8),
+	MacAddress([u8; 6]),
+}
+
+impl Filter {
+	/// Parses an expression, resolving names with the registry
+	pub fn parse(expression : &str, registry : &Registry) -> Result<Filter, FilterError> {
+		let tokens = tokenize(expression)?;
+		let mut parser = Parser {
+			tokens,
+			next : 0,
+			end : expression.len(),
+			registry,
+		};
+		let expression = parser.or()?;
+		if parser.next < parser.tokens.len() {
+			return Err(parser.error("and, or or the end"));
+		}
+		Ok(Filter { expression })
+	}
+
+	pub fn matches(&self, record : &TypedDataRecord) -> bool {
+		self.expression.evaluate(record)
+	}
+}
+
+impl Expression {
+	fn evaluate(&self, record : &TypedDataRecord) -> bool {
+		match *self {
+			Expression::And(ref a, ref b) => a.evaluate(record) && b.evaluate(record),
+			Expression::Or(ref a, ref b) => a.evaluate(record) || b.evaluate(record),
+			Expression::Not(ref a) => !a.evaluate(record),
+			Expression::Exists(field) => value(record, field).is_some(),
+			Expression::Compare(field, operator, ref literal) => match value(record, field) {
+				Some(value) if operator == Operator::Eq => equals(value, literal),
+				Some(value) if operator == Operator::Ne => differs(value, literal),
+				Some(value) => match compare(value, literal) {
+					Some(ordering) => match operator {
+						Operator::Lt => ordering == Ordering::Less,
+						Operator::Le => ordering != Ordering::Greater,
+						Operator::Gt => ordering == Ordering::Greater,
+						_ => ordering != Ordering::Less,
+					},
+					None => false,
+				},
+				None => false,
+			},
+			Expression::In(field, ref literals) => match value(record, field) {
+				Some(value) => literals.iter().any(|literal| equals(value, literal)),
+				None => false,
+			},
+			Expression::NotIn(field, ref literals) => match value(record, field) {
+				Some(value) => literals.iter().all(|literal| differs(value, literal)),
+				None => false,
+			},
+		}
+	}
+}
+
+// first value of the information element, scope fields included
+fn value<'a>(record : &TypedDataRecord<'a>, field : Field) -> Option<&'a DataValue> {
+	record
+		.template
+		.scope_fields
+		.iter()
+		.chain(record.template.fields.iter())
+		.zip(record.data.fields.iter())
+		.find(|&(specifier, _)| {
+			(specifier.enterprise_number, specifier.information_element_id) == field
+		})
+		.map(|(_, value)| value)
+}
+
+fn equals(value : &DataValue, literal : &Literal) -> bool {
+	match *literal {
+		Literal::Network(network, prefix_length) => match value.as_ip_addr() {
+			Some(address) => contains(network, prefix_length, address),
+			None => false,
+		},
+		_ => compare(value, literal) == Some(Ordering::Equal),
+	}
+}
+
+// comparable but not equal
+fn differs(value : &DataValue, literal : &Literal) -> bool {
+	match *literal {
+		Literal::Network(network, prefix_length) => match value.as_ip_addr() {
+			Some(address) => {
+				network.is_ipv4() == address.is_ipv4() && !contains(network, prefix_length, address)
+			}
+			None => false,
+		},
+		_ => compare(value, literal).is_some() && !equals(value, literal),
+	}
+}
+
+fn compare(value : &DataValue, literal : &Literal) -> Option<Ordering> {
+	let number = || {
+		value
+			.as_f64()
+			.or_else(|| value.as_u64().map(|u| u as f64))
+			.or_else(|| value.as_i64().map(|i| i as f64))
+			.or_else(|| value.as_unix_duration().map(|duration| duration.as_secs_f64()))
+	};
+	match *literal {
+		Literal::Integer(i) => match (value.as_u64(), value.as_i64()) {
+			(Some(u), _) => Some(i128::from(u).cmp(&i)),
+			(_, Some(v)) => Some(i128::from(v).cmp(&i)),
+			_ => number()?.partial_cmp(&(i as f64)),
+		},
+		Literal::Float(f) => number()?.partial_cmp(&f),
+		Literal::Boolean(b) => match *value {
+			DataValue::Boolean(v) => Some(v.cmp(&b)),
+			_ => None,
+		},
+		Literal::String(ref s) => Some(value.as_str()?.cmp(s)),
+		Literal::Address(address) => match (value.as_ip_addr()?, address) {
+			(IpAddr::V4(a), IpAddr::V4(b)) => Some(a.cmp(&b)),
+			(IpAddr::V6(a), IpAddr::V6(b)) => Some(a.cmp(&b)),
+			_ => None,
+		},
+		Literal::Network(..) => None,
+		Literal::MacAddress(ref mac) => match *value {
+			DataValue::MacAddress(ref v) => Some(v[..].cmp(&mac[..])),
+			_ => None,
+		},
+	}
+}
+
+fn contains(network : IpAddr, prefix_length : u8, address : IpAddr) -> bool {
+	match (network, address) {
+		(IpAddr::V4(network), IpAddr::V4(address)) => {
+			let mask = u32::MAX.checked_shl(32 - prefix_length as u32).unwrap_or(0);
+			u32::from(network) & mask == u32::from(address) & mask
+		}
+		(IpAddr::V6(network), IpAddr::V6(address)) => {
+			let mask = u128::MAX.checked_shl(128 - prefix_length as u32).unwrap_or(0);
+			u128::from(network) & mask == u128::from(address) & mask
+		}
+		_ => false,
+	}
+}
+
+// whether values of the type can equal the literal
+fn comparable(abstract_data_type : AbstractDataType, literal : &Literal) -> bool {
+	use structs::AbstractDataType::*;
+
+	match *literal {
+		Literal::Integer(_) | Literal::Float(_) => matches!(
+			abstract_data_type,
+			Unsigned8 | Unsigned16 | Unsigned32 | Unsigned64 | Signed8 | Signed16 | Signed32
+				| Signed64 | Float32 | Float64 | DateTimeSeconds | DateTimeMilliseconds
+				| DateTimeMicroseconds | DateTimeNanoseconds
+		),
+		Literal::Boolean(_) => abstract_data_type == Boolean,
+		Literal::String(_) => abstract_data_type == String,
+		Literal::Address(IpAddr::V4(_)) | Literal::Network(IpAddr::V4(_), _) => {
+			abstract_data_type == Ipv4Address
+		}
+		Literal::Address(IpAddr::V6(_)) | Literal::Network(IpAddr::V6(_), _) => {
+			abstract_data_type == Ipv6Address
+		}
+		Literal::MacAddress(_) => abstract_data_type == MacAddress,
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+	// names, keywords and unquoted values
+	Word(&'a str),
+	Quoted(String),
+	Open,
+	Close,
+	Comma,
+	Operator(Operator),
+	And,
+	Or,
+	Not,
+}
+
+fn tokenize(input : &str) -> Result<Vec<(usize, Token<'_>)>, FilterError> {
+	let is_word = |c : char| c.is_alphanumeric() || "._:/-+".contains(c);
+	let mut tokens = Vec::new();
+	let mut chars = input.char_indices().peekable();
+	while let Some((position, c)) = chars.next() {
+		let mut followed_by = |next : char| match chars.peek() {
+			Some(&(_, c)) if c == next => {
+				chars.next();
+				true
+			}
+			_ => false,
+		};
+		let token = match c {
+			_ if c.is_whitespace() => continue,
+			'(' => Token::Open,
+			')' => Token::Close,
+			',' => Token::Comma,
+			'=' if followed_by('=') => Token::Operator(Operator::Eq),
+			'!' if followed_by('=') => Token::Operator(Operator::Ne),
+			'!' => Token::Not,
+			'<' if followed_by('=') => Token::Operator(Operator::Le),
+			'<' => Token::Operator(Operator::Lt),
+			'>' if followed_by('=') => Token::Operator(Operator::Ge),
+			'>' => Token::Operator(Operator::Gt),
+			'&' if followed_by('&') => Token::And,
+			'|' if followed_by('|') => Token::Or,
+			'"' => {
+				let mut s = String::new();
+				loop {
+					match chars.next() {
+						Some((_, '"')) => break,
+						Some((_, '\\')) => match chars.next() {
+							Some((_, c)) => s.push(c),
+							None => break,
+						},
+						Some((_, c)) => s.push(c),
+						None => {
+							return Err(FilterError::Syntax {
+								position : input.len(),
+								expected : "closing quote",
+							})
+						}
+					}
+				}
+				Token::Quoted(s)
+			}
+			_ if is_word(c) => {
+				let mut end = position + c.len_utf8();
+				while let Some(&(i, c)) = chars.peek() {
+					if !is_word(c) {
+						break;
+					}
+					end = i + c.len_utf8();
+					chars.next();
+				}
+				match &input[position..end] {
+					"and" => Token::And,
+					"or" => Token::Or,
+					"not" => Token::Not,
+					word => Token::Word(word),
+				}
+			}
+			_ => {
+				return Err(FilterError::Syntax {
+					position,
+					expected : "a name, value, operator or parenthesis",
+				})
+			}
+		};
+		tokens.push((position, token));
+	}
+	Ok(tokens)
+}
+
+struct Parser<'a, 'r> {
+	tokens : Vec<(usize, Token<'a>)>,
+	next : usize,
+	end : usize,
+	registry : &'r Registry,
+}
+
+impl<'a, 'r> Parser<'a, 'r> {
+	fn peek(&self) -> Option<&Token<'a>> {
+		self.tokens.get(self.next).map(|(_, token)| token)
+	}
+
+	fn error(&self, expected : &'static str) -> FilterError {
+		FilterError::Syntax {
+			position : self.tokens.get(self.next).map_or(self.end, |&(position, _)| position),
+			expected,
+		}
+	}
+
+	fn expect(&mut self, token : Token, expected : &'static str) -> Result<(), FilterError> {
+		if self.peek() != Some(&token) {
+			return Err(self.error(expected));
+		}
+		self.next += 1;
+		Ok(())
+	}
+
+	fn or(&mut self) -> Result<Expression, FilterError> {
+		let mut expression = self.and()?;
+		while self.peek() == Some(&Token::Or) {
+			self.next += 1;
+			expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+		}
+		Ok(expression)
+	}
+
+	fn and(&mut self) -> Result<Expression, FilterError> {
+		let mut expression = self.not()?;
+		while self.peek() == Some(&Token::And) {
+			self.next += 1;
+			expression = Expression::And(Box::new(expression), Box::new(self.not()?));
+		}
+		Ok(expression)
+	}
+
+	fn not(&mut self) -> Result<Expression, FilterError> {
+		if self.peek() == Some(&Token::Not) {
+			self.next += 1;
+			return Ok(Expression::Not(Box::new(self.not()?)));
+		}
+		if self.peek() == Some(&Token::Open) {
+			self.next += 1;
+			let expression = self.or()?;
+			self.expect(Token::Close, "closing parenthesis")?;
+			return Ok(expression);
+		}
+		self.predicate()
+	}
+
+	fn predicate(&mut self) -> Result<Expression, FilterError> {
+		let name = match self.peek() {
+			Some(&Token::Word(name)) => name,
+			_ => return Err(self.error("an information element name")),
+		};
+		let information_element = self
+			.registry
+			.lookup_by_name(name)
+			.ok_or_else(|| FilterError::UnknownName(name.to_string()))?;
+		let field = (information_element.enterprise_number, information_element.id);
+		let abstract_data_type = information_element.abstract_data_type;
+		self.next += 1;
+
+		let negated = match (self.peek(), self.tokens.get(self.next + 1)) {
+			(Some(&Token::Not), Some(&(_, Token::Word("in")))) => {
+				self.next += 1;
+				true
+			}
+			_ => false,
+		};
+		let expression = match self.peek() {
+			Some(&Token::Operator(operator)) => {
+				self.next += 1;
+				let ordered = operator != Operator::Eq && operator != Operator::Ne;
+				let literal = self.literal(name, abstract_data_type, ordered)?;
+				Expression::Compare(field, operator, literal)
+			}
+			Some(&Token::Word("in")) => {
+				self.next += 1;
+				let mut literals = Vec::new();
+				if self.peek() == Some(&Token::Open) {
+					self.next += 1;
+					loop {
+						literals.push(self.literal(name, abstract_data_type, false)?);
+						if self.peek() != Some(&Token::Comma) {
+							break;
+						}
+						self.next += 1;
+					}
+					self.expect(Token::Close, "comma or closing parenthesis")?;
+				} else {
+					literals.push(self.literal(name, abstract_data_type, false)?);
+				}
+				Expression::In(field, literals)
+			}
+			_ => Expression::Exists(field),
+		};
+		Ok(match expression {
+			Expression::In(field, literals) if negated => Expression::NotIn(field, literals),
+			expression => expression,
+		})
+	}
+
+	fn literal(
+		&mut self,
+		name : &str,
+		abstract_data_type : AbstractDataType,
+		// for `<`, `<=`, `>` and `>=`, which networks don't support
+		ordered : bool,
+	) -> Result<Literal, FilterError> {
+		let (literal, text) = match self.peek() {
+			Some(Token::Quoted(s)) => (Literal::String(s.clone()), format!("{:?}", s)),
+			Some(&Token::Word(word)) => match parse_literal(word) {
+				Some(literal) => (literal, word.to_string()),
+				None => return Err(self.error("a value")),
+			},
+			_ => return Err(self.error("a value")),
+		};
+		let network = matches!(literal, Literal::Network(..));
+		if !comparable(abstract_data_type, &literal) || (ordered && network) {
+			return Err(FilterError::Type {
+				field : name.to_string(),
+				value : text,
+			});
+		}
+		self.next += 1;
+		Ok(literal)
+	}
+}
+
+fn parse_literal(word : &str) -> Option<Literal> {
+	if let Ok(i) = word.parse() {
+		return Some(Literal::Integer(i));
+	}
+	if let Ok(address) = word.parse() {
+		return Some(Literal::Address(address));
+	}
+	if let Some((address, prefix_length)) = word.split_once('/') {
+		let address : IpAddr = address.parse().ok()?;
+		let prefix_length : u8 = prefix_length.parse().ok()?;
+		let bits = if address.is_ipv4() { 32 } else { 128 };
+		if prefix_length > bits {
+			return None;
+		}
+		return Some(Literal::Network(address, prefix_length));
+	}
+	let octets = word
+		.split(['-', ':'])
+		.map(|octet| match octet.len() {
+			2 => u8::from_str_radix(octet, 16).ok(),
+			_ => None,
+		})
+		.collect::<Option<Vec<u8>>>();
+	if let Some(octets) = octets {
+		if octets.len() == 6 {
+			let mut mac = [0; 6];
+			mac.copy_from_slice(&octets);
+			return Some(Literal::MacAddress(mac));
+		}
+	}
+	match word {
+		"true" => Some(Literal::Boolean(true)),
+		"false" => Some(Literal::Boolean(false)),
+		_ if word.starts_with(|c : char| c.is_ascii_digit() || c == '-' || c == '.') => {
+			word.parse().ok().map(Literal::Float)
+		}
+		_ => None,
+	}
+}
+
+/// Passes the data records matching a filter to another sink and drops the rest
+pub struct FilterSink<S : Sink> {
+	pub filter : Filter,
+	sink : S,
+}
+
+impl<S : Sink> FilterSink<S> {
+	pub fn new(filter : Filter, sink : S) -> FilterSink<S> {
+		FilterSink { filter, sink }
+	}
+
+	pub fn into_inner(self) -> S {
+		self.sink
+	}
+}
+
+impl<S : Sink> Sink for FilterSink<S> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		if self.filter.matches(&context.typed(record)) {
+			self.sink.record(context, record)
+		} else {
+			Ok(())
+		}
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.sink.flush()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use information_element::*;
+	use std::net::{Ipv4Addr, Ipv6Addr};
+	use template_builder::*;
+
+	#[test]
+	fn filter_test() {
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::DESTINATION_TRANSPORT_PORT)
+			.field(ie::PROTOCOL_IDENTIFIER)
+			.field(ie::INTERFACE_NAME)
+			.field(ie::FLOW_START_SECONDS)
+			.build()
+			.unwrap();
+		let record = |address : [u8; 4], port : u16, protocol : u8| DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::from(address)),
+				DataValue::Unsigned16(port),
+				DataValue::Unsigned8(protocol),
+				DataValue::String("eth0".to_string()),
+				DataValue::DateTimeSeconds(1_518_864_590),
+			],
+		};
+		let matches = |expression : &str, record : &DataRecord| {
+			let filter = Filter::parse(expression, &registry).unwrap();
+			filter.matches(&TypedDataRecord {
+				data : record,
+				template : &template,
+			})
+		};
+
+		let ssh = record([10, 1, 2, 3], 22, 6);
+		let dns = record([192, 0, 2, 1], 53, 17);
+		let expression = "protocolIdentifier == 6 and destinationTransportPort in (22, 3389) \
+		                  and sourceIPv4Address in 10.0.0.0/8";
+		assert!(matches(expression, &ssh));
+		assert!(!matches(expression, &dns));
+		assert!(matches("not (protocolIdentifier == 6) && sourceIPv4Address >= 192.0.2.0", &dns));
+		assert!(matches("destinationTransportPort not in (22, 3389)", &dns));
+		assert!(matches("destinationTransportPort < 53 or interfaceName == \"eth1\"", &ssh));
+		assert!(!matches("destinationTransportPort != 22", &ssh));
+		assert!(matches("flowStartSeconds > 1518864589.5 and interfaceName", &ssh));
+		// comparisons of missing fields are false
+		assert!(!matches("sourceIPv6Address == ::1", &ssh));
+		assert!(!matches("sourceIPv6Address != ::1", &ssh));
+		assert!(!matches("sourceIPv6Address not in ::/0", &ssh));
+		assert!(matches("!sourceIPv6Address in ::/0", &ssh));
+		// networks match by containment
+		assert!(matches("sourceIPv4Address != 10.0.0.0/8", &dns));
+		assert!(!matches("sourceIPv4Address != 10.0.0.0/8", &ssh));
+		assert!(matches("sourceIPv4Address not in (10.0.0.0/8, 198.51.100.0/24)", &dns));
+		assert!(!matches("sourceIPv4Address not in (10.0.0.0/8, 192.0.2.1)", &dns));
+
+		let v6 = TemplateBuilder::new(257, &registry)
+			.field(ie::SOURCE_IPV6_ADDRESS)
+			.build()
+			.unwrap();
+		let filter = Filter::parse("sourceIPv6Address in (2001:db8::/32, ::1)", &registry).unwrap();
+		let address = "2001:db8::1".parse::<Ipv6Addr>().unwrap();
+		assert!(filter.matches(&TypedDataRecord {
+			data : &DataRecord {
+				fields : vec![DataValue::Ipv6Address(address)],
+			},
+			template : &v6,
+		}));
+	}
+
+	#[test]
+	fn filter_error_test() {
+		let registry = Registry::default();
+		let parse = |expression| Filter::parse(expression, &registry).map(|_| ());
+
+		assert_eq!(
+			parse("protocolIdentifier == 6 and"),
+			Err(FilterError::Syntax {
+				position : 27,
+				expected : "an information element name",
+			})
+		);
+		assert_eq!(
+			parse("(protocolIdentifier == 6"),
+			Err(FilterError::Syntax {
+				position : 24,
+				expected : "closing parenthesis",
+			})
+		);
+		assert_eq!(parse("protocol == 6"), Err(FilterError::UnknownName("protocol".to_string())));
+		assert_eq!(
+			parse("sourceIPv4Address == 2001:db8::1"),
+			Err(FilterError::Type {
+				field : "sourceIPv4Address".to_string(),
+				value : "2001:db8::1".to_string(),
+			})
+		);
+		assert_eq!(
+			parse("sourceIPv4Address < 10.0.0.0/8"),
+			Err(FilterError::Type {
+				field : "sourceIPv4Address".to_string(),
+				value : "10.0.0.0/8".to_string(),
+			})
+		);
+		assert_eq!(
+			parse("sourceIPv4Address in 10.0.0.0/33"),
+			Err(FilterError::Syntax {
+				position : 21,
+				expected : "a value",
+			})
+		);
+	}
+}
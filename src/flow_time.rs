@@ -0,0 +1,273 @@
+use information_element::ie;
+use sink::*;
+use std;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use structs::*;
+
+/// Absolute start and end of a flow
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FlowTimes {
+	pub start : Option<SystemTime>,
+	pub end : Option<SystemTime>,
+}
+
+/// Derives absolute flow start and end times from whichever time information elements
+/// a record carries, in order of preference:
+/// absolute `flowStart*`/`flowEnd*` seconds to nanoseconds, the most precise first,
+/// `flow*DeltaMicroseconds` before the export time of the message,
+/// and `flow*SysUpTime` after `systemInitTimeMilliseconds`.
+/// A missing start or end is completed with `flowDurationMilliseconds`
+/// or `flowDurationMicroseconds`.
+///
+/// `systemInitTimeMilliseconds` is taken from the record itself or from the last record,
+/// usually an options record, that carried it in the same observation domain.
+#[derive(Clone, Debug, Default)]
+pub struct FlowTimeNormalizer {
+	system_init_times : std::collections::HashMap<u32, SystemTime>,
+}
+
+struct Bound {
+	nanoseconds : u16,
+	microseconds : u16,
+	milliseconds : u16,
+	seconds : u16,
+	delta_microseconds : u16,
+	sys_up_time : u16,
+}
+
+const START : Bound = Bound {
+	nanoseconds : ie::FLOW_START_NANOSECONDS,
+	microseconds : ie::FLOW_START_MICROSECONDS,
+	milliseconds : ie::FLOW_START_MILLISECONDS,
+	seconds : ie::FLOW_START_SECONDS,
+	delta_microseconds : ie::FLOW_START_DELTA_MICROSECONDS,
+	sys_up_time : ie::FLOW_START_SYS_UP_TIME,
+};
+
+const END : Bound = Bound {
+	nanoseconds : ie::FLOW_END_NANOSECONDS,
+	microseconds : ie::FLOW_END_MICROSECONDS,
+	milliseconds : ie::FLOW_END_MILLISECONDS,
+	seconds : ie::FLOW_END_SECONDS,
+	delta_microseconds : ie::FLOW_END_DELTA_MICROSECONDS,
+	sys_up_time : ie::FLOW_END_SYS_UP_TIME,
+};
+
+impl FlowTimeNormalizer {
+	/// Remembers the system init time of records carrying `systemInitTimeMilliseconds`
+	pub fn observe(&mut self, context : &RecordContext, record : &DataRecord) {
+		let record = context.typed(record);
+		if let Some(system_init_time) = record
+			.get(ie::SYSTEM_INIT_TIME_MILLISECONDS)
+			.and_then(DataValue::as_system_time)
+		{
+			self.system_init_times
+				.insert(context.header.observation_domain_id, system_init_time);
+		}
+	}
+
+	pub fn system_init_time(&self, observation_domain_id : u32) -> Option<SystemTime> {
+		self.system_init_times.get(&observation_domain_id).cloned()
+	}
+
+	pub fn flow_times(&self, context : &RecordContext, record : &DataRecord) -> FlowTimes {
+		let record = context.typed(record);
+		let export_time = UNIX_EPOCH + Duration::from_secs(context.header.export_time as u64);
+		let system_init_time = record
+			.get(ie::SYSTEM_INIT_TIME_MILLISECONDS)
+			.and_then(DataValue::as_system_time)
+			.or_else(|| self.system_init_time(context.header.observation_domain_id));
+		resolve(&record, Some(export_time), system_init_time)
+	}
+
+	/// `observe` followed by `flow_times`
+	pub fn normalize(&mut self, context : &RecordContext, record : &DataRecord) -> FlowTimes {
+		self.observe(context, record);
+		self.flow_times(context, record)
+	}
+}
+
+// relative times are only resolved if the time they are relative to is given
+pub(crate) fn resolve(
+	record : &TypedDataRecord,
+	export_time : Option<SystemTime>,
+	system_init_time : Option<SystemTime>,
+) -> FlowTimes {
+	let time = |bound : &Bound| {
+		[
+			bound.nanoseconds,
+			bound.microseconds,
+			bound.milliseconds,
+			bound.seconds,
+		].iter()
+			.filter_map(|&id| record.get(id))
+			.filter_map(DataValue::as_system_time)
+			.next()
+			.or_else(|| {
+				let delta = record.get(bound.delta_microseconds).and_then(DataValue::as_u64)?;
+				export_time?.checked_sub(Duration::from_micros(delta))
+			})
+			.or_else(|| {
+				let sys_up_time = record.get(bound.sys_up_time).and_then(DataValue::as_u64)?;
+				system_init_time?.checked_add(Duration::from_millis(sys_up_time))
+			})
+	};
+	let mut times = FlowTimes {
+		start : time(&START),
+		end : time(&END),
+	};
+
+	let duration = record
+		.get(ie::FLOW_DURATION_MICROSECONDS)
+		.and_then(DataValue::as_u64)
+		.map(Duration::from_micros)
+		.or_else(|| {
+			record
+				.get(ie::FLOW_DURATION_MILLISECONDS)
+				.and_then(DataValue::as_u64)
+				.map(Duration::from_millis)
+		});
+	if let Some(duration) = duration {
+		match times {
+			FlowTimes {
+				start : Some(start),
+				end : None,
+			} => times.end = start.checked_add(duration),
+			FlowTimes {
+				start : None,
+				end : Some(end),
+			} => times.start = end.checked_sub(duration),
+			_ => {}
+		}
+	}
+	times
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use registry::*;
+	use test_util::*;
+	use timestamp::NTP_UNIX_EPOCH_OFFSET;
+
+	fn header(observation_domain_id : u32) -> MessageHeader {
+		MessageHeader {
+			version_number : IPFIX_VERSION_NUMBER,
+			length : 0,
+			export_time : 1_500_000_000,
+			sequence_number : 0,
+			observation_domain_id,
+		}
+	}
+
+	fn unix(milliseconds : u64) -> Option<SystemTime> {
+		Some(UNIX_EPOCH + Duration::from_millis(milliseconds))
+	}
+
+	#[test]
+	fn absolute_test() {
+		let registry = Registry::default();
+		let header = header(1);
+		let template = template(&[(150, 4), (153, 8), (156, 8)]);
+		let context = RecordContext {
+			header : &header,
+			template : &template,
+			registry : &registry,
+		};
+		let record = DataRecord {
+			fields : vec![
+				DataValue::DateTimeSeconds(1_499_999_000),
+				DataValue::DateTimeMilliseconds(1_499_999_500_250),
+				DataValue::DateTimeNanoseconds {
+					seconds : (1_499_999_000 + NTP_UNIX_EPOCH_OFFSET) as u32,
+					fraction : 0x8000_0000,
+				},
+			],
+		};
+		assert_eq!(
+			FlowTimeNormalizer::default().flow_times(&context, &record),
+			FlowTimes {
+				start : unix(1_499_999_000_500),
+				end : unix(1_499_999_500_250),
+			}
+		);
+	}
+
+	#[test]
+	fn delta_test() {
+		let registry = Registry::default();
+		let header = header(1);
+		let template = template(&[(158, 4), (161, 4)]);
+		let context = RecordContext {
+			header : &header,
+			template : &template,
+			registry : &registry,
+		};
+		let record = DataRecord {
+			fields : vec![DataValue::Unsigned32(2_000_000), DataValue::Unsigned32(1500)],
+		};
+		assert_eq!(
+			FlowTimeNormalizer::default().flow_times(&context, &record),
+			FlowTimes {
+				start : unix(1_499_999_998_000),
+				end : unix(1_499_999_999_500),
+			}
+		);
+	}
+
+	#[test]
+	fn sys_up_time_test() {
+		let registry = Registry::default();
+		let mut normalizer = FlowTimeNormalizer::default();
+
+		let flow_template = template(&[(22, 4), (21, 4)]);
+		let flow = DataRecord {
+			fields : vec![DataValue::Unsigned32(1000), DataValue::Unsigned32(3000)],
+		};
+		let domain_1 = header(1);
+		let domain_2 = header(2);
+		let flow_context = |header| RecordContext {
+			header,
+			template : &flow_template,
+			registry : &registry,
+		};
+		assert_eq!(
+			normalizer.normalize(&flow_context(&domain_1), &flow),
+			FlowTimes::default()
+		);
+
+		let mut options_template = template(&[(160, 8)]);
+		options_template.scope_fields = vec![FieldSpecifier {
+			information_element_id : 149,
+			field_length : 4,
+			enterprise_number : None,
+		}];
+		let options = DataRecord {
+			fields : vec![
+				DataValue::Unsigned32(1),
+				DataValue::DateTimeMilliseconds(1_400_000_000_000),
+			],
+		};
+		normalizer.observe(
+			&RecordContext {
+				header : &domain_1,
+				template : &options_template,
+				registry : &registry,
+			},
+			&options,
+		);
+		assert_eq!(normalizer.system_init_time(1), unix(1_400_000_000_000));
+
+		assert_eq!(
+			normalizer.normalize(&flow_context(&domain_1), &flow),
+			FlowTimes {
+				start : unix(1_400_000_001_000),
+				end : unix(1_400_000_003_000),
+			}
+		);
+		assert_eq!(
+			normalizer.normalize(&flow_context(&domain_2), &flow),
+			FlowTimes::default()
+		);
+	}
+}
@@ -0,0 +1,198 @@
+use flow_time::*;
+use information_element::ie;
+use std::convert::TryFrom;
+use std::net::IpAddr;
+use std::time::SystemTime;
+use structs::*;
+
+/// Common flow properties of a data record,
+/// resolved from whichever information elements its template carries
+pub struct FlowView<'a> {
+	record : TypedDataRecord<'a>,
+	times : FlowTimes,
+}
+
+impl<'a> FlowView<'a> {
+	/// Start and end are taken from absolute time information elements only,
+	/// use `with_times` for times resolved by a `FlowTimeNormalizer`
+	pub fn new(record : TypedDataRecord<'a>) -> FlowView<'a> {
+		let times = resolve(&record, None, None);
+		FlowView { record, times }
+	}
+
+	pub fn with_times(record : TypedDataRecord<'a>, times : FlowTimes) -> FlowView<'a> {
+		FlowView { record, times }
+	}
+
+	pub fn record(&self) -> &TypedDataRecord<'a> {
+		&self.record
+	}
+
+	/// `sourceIPv4Address` or `sourceIPv6Address`
+	pub fn src_addr(&self) -> Option<IpAddr> {
+		self.addr(ie::SOURCE_IPV4_ADDRESS, ie::SOURCE_IPV6_ADDRESS)
+	}
+
+	/// `destinationIPv4Address` or `destinationIPv6Address`
+	pub fn dst_addr(&self) -> Option<IpAddr> {
+		self.addr(ie::DESTINATION_IPV4_ADDRESS, ie::DESTINATION_IPV6_ADDRESS)
+	}
+
+	/// `sourceTransportPort`, `tcpSourcePort` or `udpSourcePort`
+	pub fn src_port(&self) -> Option<u16> {
+		self.first(&[
+			ie::SOURCE_TRANSPORT_PORT,
+			ie::TCP_SOURCE_PORT,
+			ie::UDP_SOURCE_PORT,
+		])
+	}
+
+	/// `destinationTransportPort`, `tcpDestinationPort` or `udpDestinationPort`
+	pub fn dst_port(&self) -> Option<u16> {
+		self.first(&[
+			ie::DESTINATION_TRANSPORT_PORT,
+			ie::TCP_DESTINATION_PORT,
+			ie::UDP_DESTINATION_PORT,
+		])
+	}
+
+	/// `protocolIdentifier`
+	pub fn protocol(&self) -> Option<u8> {
+		self.first(&[ie::PROTOCOL_IDENTIFIER])
+	}
+
+	/// `octetDeltaCount` or `octetTotalCount`
+	pub fn octets(&self) -> Option<u64> {
+		self.first(&[ie::OCTET_DELTA_COUNT, ie::OCTET_TOTAL_COUNT])
+	}
+
+	/// `packetDeltaCount` or `packetTotalCount`
+	pub fn packets(&self) -> Option<u64> {
+		self.first(&[ie::PACKET_DELTA_COUNT, ie::PACKET_TOTAL_COUNT])
+	}
+
+	pub fn start(&self) -> Option<SystemTime> {
+		self.times.start
+	}
+
+	pub fn end(&self) -> Option<SystemTime> {
+		self.times.end
+	}
+
+	/// `ingressInterface`
+	pub fn ingress_interface(&self) -> Option<u32> {
+		self.first(&[ie::INGRESS_INTERFACE])
+	}
+
+	/// `egressInterface`
+	pub fn egress_interface(&self) -> Option<u32> {
+		self.first(&[ie::EGRESS_INTERFACE])
+	}
+
+	fn addr(&self, ipv4 : u16, ipv6 : u16) -> Option<IpAddr> {
+		self.record
+			.get(ipv4)
+			.or_else(|| self.record.get(ipv6))
+			.and_then(DataValue::as_ip_addr)
+	}
+
+	fn first<T>(&self, ids : &[u16]) -> Option<T>
+	where
+		T : for<'v> TryFrom<&'v DataValue>,
+	{
+		ids.iter()
+			.filter_map(|&id| self.record.get(id))
+			.filter_map(|value| T::try_from(value).ok())
+			.next()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, UNIX_EPOCH};
+	use test_util::*;
+
+	#[test]
+	fn ipv4_test() {
+		let template = template(&[
+			(8, 4),
+			(12, 4),
+			(7, 2),
+			(11, 2),
+			(4, 1),
+			(1, 4),
+			(86, 8),
+			(10, 2),
+			(152, 8),
+		]);
+		let record = DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::Ipv4Address(Ipv4Addr::new(198, 51, 100, 1)),
+				DataValue::Unsigned16(49152),
+				DataValue::Unsigned16(443),
+				DataValue::Unsigned8(6),
+				DataValue::Unsigned32(1500),
+				DataValue::Unsigned64(3),
+				DataValue::Unsigned16(7),
+				DataValue::DateTimeMilliseconds(1_500_000_000_000),
+			],
+		};
+		let flow = FlowView::new(TypedDataRecord {
+			data : &record,
+			template : &template,
+		});
+
+		assert_eq!(flow.src_addr(), Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+		assert_eq!(
+			flow.dst_addr(),
+			Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)))
+		);
+		assert_eq!(flow.src_port(), Some(49152));
+		assert_eq!(flow.dst_port(), Some(443));
+		assert_eq!(flow.protocol(), Some(6));
+		assert_eq!(flow.octets(), Some(1500));
+		assert_eq!(flow.packets(), Some(3));
+		assert_eq!(flow.ingress_interface(), Some(7));
+		assert_eq!(flow.egress_interface(), None);
+		assert_eq!(
+			flow.start(),
+			Some(UNIX_EPOCH + Duration::from_secs(1_500_000_000))
+		);
+		assert_eq!(flow.end(), None);
+	}
+
+	#[test]
+	fn ipv6_test() {
+		let template = template(&[(27, 16), (28, 16), (182, 2), (183, 2)]);
+		let record = DataRecord {
+			fields : vec![
+				DataValue::Ipv6Address(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+				DataValue::Ipv6Address(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2)),
+				DataValue::Unsigned16(49152),
+				DataValue::Unsigned16(22),
+			],
+		};
+		let times = FlowTimes {
+			start : Some(UNIX_EPOCH),
+			end : None,
+		};
+		let flow = FlowView::with_times(
+			TypedDataRecord {
+				data : &record,
+				template : &template,
+			},
+			times,
+		);
+
+		assert_eq!(
+			flow.src_addr(),
+			Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+		);
+		assert_eq!(flow.src_port(), Some(49152));
+		assert_eq!(flow.dst_port(), Some(22));
+		assert_eq!(flow.protocol(), None);
+		assert_eq!(flow.start(), Some(UNIX_EPOCH));
+	}
+}
@@ -0,0 +1,99 @@
+// Reader for the IANA IPFIX registry XML.
+// Shared between the build script and `Registry::from_xml`,
+// so it may only depend on std and roxmltree.
+
+use roxmltree;
+
+pub struct IanaRecord {
+	pub id : u16,
+	pub name : String,
+	pub data_type : String,
+	pub data_type_semantics : Option<String>,
+	pub units : Option<String>,
+	pub range : Option<(u64, u64)>,
+	pub status : String,
+	pub description : String,
+}
+
+pub fn parse(xml : &str) -> Result<Vec<IanaRecord>, String> {
+	let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+	let registry = document
+		.descendants()
+		.find(|node| {
+			node.tag_name().name() == "registry"
+				&& node.attribute("id") == Some("ipfix-information-elements")
+		})
+		.ok_or_else(|| "registry ipfix-information-elements not found".to_string())?;
+
+	let mut records = Vec::new();
+	for record in registry
+		.children()
+		.filter(|node| node.tag_name().name() == "record")
+	{
+		// skips reserved and unassigned ranges like "105-127"
+		let id = match child_text(&record, "elementId").and_then(|id| id.parse::<u16>().ok()) {
+			Some(id) => id,
+			None => continue,
+		};
+		let data_type = match child_text(&record, "dataType") {
+			Some(data_type) => data_type,
+			None => continue,
+		};
+		let name = child_text(&record, "name")
+			.ok_or_else(|| format!("information element {} has no name", id))?;
+		let range = match child_text(&record, "range") {
+			Some(range) => Some(parse_range(&range)
+				.ok_or_else(|| format!("information element {} has invalid range {}", id, range))?),
+			None => None,
+		};
+		let description = record
+			.children()
+			.find(|node| node.tag_name().name() == "description")
+			.map(|node| {
+				node.descendants()
+					.filter(|node| node.is_text())
+					.filter_map(|node| node.text())
+					.collect::<Vec<_>>()
+					.join(" ")
+					.split_whitespace()
+					.collect::<Vec<_>>()
+					.join(" ")
+			})
+			.unwrap_or_default();
+
+		records.push(IanaRecord {
+			id,
+			name,
+			data_type,
+			data_type_semantics : child_text(&record, "dataTypeSemantics")
+				.filter(|semantics| semantics != "default"),
+			units : child_text(&record, "units").filter(|units| units != "none"),
+			range,
+			status : child_text(&record, "status").unwrap_or_else(|| "current".to_string()),
+			description,
+		});
+	}
+	Ok(records)
+}
+
+fn child_text(node : &roxmltree::Node, tag : &str) -> Option<String> {
+	node.children()
+		.find(|child| child.tag_name().name() == tag)
+		.and_then(|child| child.text())
+		.map(|text| text.trim().to_string())
+}
+
+fn parse_range(range : &str) -> Option<(u64, u64)> {
+	let mut bounds = range.splitn(2, '-').map(|bound| {
+		let bound = bound.trim();
+		if bound.starts_with("0x") || bound.starts_with("0X") {
+			u64::from_str_radix(&bound[2..], 16).ok()
+		} else {
+			bound.parse().ok()
+		}
+	});
+	match (bounds.next(), bounds.next()) {
+		(Some(Some(begin)), Some(Some(end))) => Some((begin, end)),
+		_ => None,
+	}
+}
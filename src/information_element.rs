@@ -1,483 +1,163 @@
+use std;
+use std::borrow::Cow;
 use structs::*;
-use structs::AbstractDataType::*;
+use self::DataTypeSemantics::*;
+use self::Units::*;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct InformationElement {
 	pub id : u16,
-	pub name : &'static str,
+	pub enterprise_number : Option<u32>,
+	pub name : Cow<'static, str>,
 	pub abstract_data_type : AbstractDataType,
+	pub data_type_semantics : Option<DataTypeSemantics>,
+	pub units : Option<Units>,
+	/// inclusive range of valid values
+	pub range : Option<(u64, u64)>,
+	pub status : Status,
+	pub description : Cow<'static, str>,
 }
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
-pub fn lookup(id : u16) -> Option<InformationElement> {
-	let tuple = match id {
-		// 0 Reserved
-		// 65-69 Assigned for NetFlow v9 compatibility
-		// 97 Assigned for NetFlow v9 compatibility
-		// 105-127 Assigned for NetFlow v9 compatibility
-		// 416 deprecated
-		// 419 deprecated
-		// 482-32767 Unassigned
-		1 => (id, "octetDeltaCount", Unsigned64),
-		2 => (id, "packetDeltaCount", Unsigned64),
-		3 => (id, "deltaFlowCount", Unsigned64),
-		4 => (id, "protocolIdentifier", Unsigned8),
-		5 => (id, "ipClassOfService", Unsigned8),
-		6 => (id, "tcpControlBits", Unsigned16),
-		7 => (id, "sourceTransportPort", Unsigned16),
-		8 => (id, "sourceIPv4Address", Ipv4Address),
-		9 => (id, "sourceIPv4PrefixLength", Unsigned8),
-		10 => (id, "ingressInterface", Unsigned32),
-		11 => (id, "destinationTransportPort", Unsigned16),
-		12 => (id, "destinationIPv4Address", Ipv4Address),
-		13 => (id, "destinationIPv4PrefixLength", Unsigned8),
-		14 => (id, "egressInterface", Unsigned32),
-		15 => (id, "ipNextHopIPv4Address", Ipv4Address),
-		16 => (id, "bgpSourceAsNumber", Unsigned32),
-		17 => (id, "bgpDestinationAsNumber", Unsigned32),
-		18 => (id, "bgpNextHopIPv4Address", Ipv4Address),
-		19 => (id, "postMCastPacketDeltaCount", Unsigned64),
-		20 => (id, "postMCastOctetDeltaCount", Unsigned64),
-		21 => (id, "flowEndSysUpTime", Unsigned32),
-		22 => (id, "flowStartSysUpTime", Unsigned32),
-		23 => (id, "postOctetDeltaCount", Unsigned64),
-		24 => (id, "postPacketDeltaCount", Unsigned64),
-		25 => (id, "minimumIpTotalLength", Unsigned64),
-		26 => (id, "maximumIpTotalLength", Unsigned64),
-		27 => (id, "sourceIPv6Address", Ipv6Address),
-		28 => (id, "destinationIPv6Address", Ipv6Address),
-		29 => (id, "sourceIPv6PrefixLength", Unsigned8),
-		30 => (id, "destinationIPv6PrefixLength", Unsigned8),
-		31 => (id, "flowLabelIPv6", Unsigned32),
-		32 => (id, "icmpTypeCodeIPv4", Unsigned16),
-		33 => (id, "igmpType", Unsigned8),
-		34 => (id, "samplingInterval", Unsigned32),
-		35 => (id, "samplingAlgorithm", Unsigned8),
-		36 => (id, "flowActiveTimeout", Unsigned16),
-		37 => (id, "flowIdleTimeout", Unsigned16),
-		38 => (id, "engineType", Unsigned8),
-		39 => (id, "engineId", Unsigned8),
-		40 => (id, "exportedOctetTotalCount", Unsigned64),
-		41 => (id, "exportedMessageTotalCount", Unsigned64),
-		42 => (id, "exportedFlowRecordTotalCount", Unsigned64),
-		43 => (id, "ipv4RouterSc", Ipv4Address),
-		44 => (id, "sourceIPv4Prefix", Ipv4Address),
-		45 => (id, "destinationIPv4Prefix", Ipv4Address),
-		46 => (id, "mplsTopLabelType", Unsigned8),
-		47 => (id, "mplsTopLabelIPv4Address", Ipv4Address),
-		48 => (id, "samplerId", Unsigned8),
-		49 => (id, "samplerMode", Unsigned8),
-		50 => (id, "samplerRandomInterval", Unsigned32),
-		51 => (id, "classId", Unsigned8),
-		52 => (id, "minimumTTL", Unsigned8),
-		53 => (id, "maximumTTL", Unsigned8),
-		54 => (id, "fragmentIdentification", Unsigned32),
-		55 => (id, "postIpClassOfService", Unsigned8),
-		56 => (id, "sourceMacAddress", MacAddress),
-		57 => (id, "postDestinationMacAddress", MacAddress),
-		58 => (id, "vlanId", Unsigned16),
-		59 => (id, "postVlanId", Unsigned16),
-		60 => (id, "ipVersion", Unsigned8),
-		61 => (id, "flowDirection", Unsigned8),
-		62 => (id, "ipNextHopIPv6Address", Ipv6Address),
-		63 => (id, "bgpNextHopIPv6Address", Ipv6Address),
-		64 => (id, "ipv6ExtensionHeaders", Unsigned32),
+impl InformationElement {
+	pub fn is_counter(&self) -> bool {
+		matches!(
+			self.data_type_semantics,
+			Some(TotalCounter) | Some(DeltaCounter) | Some(SnmpCounter)
+		)
+	}
 
-		70 => (id, "mplsTopLabelStackSection", OctetArray),
-		71 => (id, "mplsLabelStackSection2", OctetArray),
-		72 => (id, "mplsLabelStackSection3", OctetArray),
-		73 => (id, "mplsLabelStackSection4", OctetArray),
-		74 => (id, "mplsLabelStackSection5", OctetArray),
-		75 => (id, "mplsLabelStackSection6", OctetArray),
-		76 => (id, "mplsLabelStackSection7", OctetArray),
-		77 => (id, "mplsLabelStackSection8", OctetArray),
-		78 => (id, "mplsLabelStackSection9", OctetArray),
-		79 => (id, "mplsLabelStackSection10", OctetArray),
-		80 => (id, "destinationMacAddress", MacAddress),
-		81 => (id, "postSourceMacAddress", MacAddress),
-		82 => (id, "interfaceName", String),
-		83 => (id, "interfaceDescription", String),
-		84 => (id, "samplerName", String),
-		85 => (id, "octetTotalCount", Unsigned64),
-		86 => (id, "packetTotalCount", Unsigned64),
-		87 => (id, "flagsAndSamplerId", Unsigned32),
-		88 => (id, "fragmentOffset", Unsigned16),
-		89 => (id, "forwardingStatus", Unsigned8),
-		90 => (id, "mplsVpnRouteDistinguisher", OctetArray),
-		91 => (id, "mplsTopLabelPrefixLength", Unsigned8),
-		92 => (id, "srcTrafficIndex", Unsigned32),
-		93 => (id, "dstTrafficIndex", Unsigned32),
-		94 => (id, "applicationDescription", String),
-		95 => (id, "applicationId", OctetArray),
-		96 => (id, "applicationName", String),
+	pub fn in_range(&self, value : u64) -> bool {
+		match self.range {
+			Some((begin, end)) => begin <= value && value <= end,
+			None => true,
+		}
+	}
+}
+
+/// RFC 7012 section 3.2, `None` is the default semantics
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataTypeSemantics {
+	Quantity,
+	TotalCounter,
+	DeltaCounter,
+	Identifier,
+	Flags,
+	List,
+	SnmpCounter,
+	SnmpGauge,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Units {
+	Bits,
+	Octets,
+	Packets,
+	Flows,
+	Seconds,
+	Milliseconds,
+	Microseconds,
+	Nanoseconds,
+	FourOctetWords,
+	Messages,
+	Hops,
+	Entries,
+	Frames,
+	Ports,
+	Inferred,
+}
+
+impl std::fmt::Display for Units {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		let name = match *self {
+			Bits => "bits",
+			Octets => "octets",
+			Packets => "packets",
+			Flows => "flows",
+			Seconds => "seconds",
+			Milliseconds => "milliseconds",
+			Microseconds => "microseconds",
+			Nanoseconds => "nanoseconds",
+			FourOctetWords => "4-octet words",
+			Messages => "messages",
+			Hops => "hops",
+			Entries => "entries",
+			Frames => "frames",
+			Ports => "ports",
+			Inferred => "inferred",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+	Current,
+	Deprecated,
+}
+
+include!(concat!(env!("OUT_DIR"), "/information_elements.rs"));
+
+/// Information element ids by name, e.g. `ie::SOURCE_IPV4_ADDRESS`
+pub mod ie {
+	include!(concat!(env!("OUT_DIR"), "/ie.rs"));
+}
+
+pub fn lookup(id : u16) -> Option<&'static InformationElement> {
+	INFORMATION_ELEMENTS
+		.binary_search_by_key(&id, |information_element| information_element.id)
+		.ok()
+		.map(|index| &INFORMATION_ELEMENTS[index])
+}
+
+/// IANA names are case-sensitive
+pub fn lookup_by_name(name : &str) -> Option<&'static InformationElement> {
+	INFORMATION_ELEMENTS_BY_NAME
+		.binary_search_by_key(&name, |&index| &INFORMATION_ELEMENTS[index].name)
+		.ok()
+		.map(|index| &INFORMATION_ELEMENTS[INFORMATION_ELEMENTS_BY_NAME[index]])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn metadata_test() {
+		let octet_delta_count = lookup(1).unwrap();
+		assert_eq!(octet_delta_count.name, "octetDeltaCount");
+		assert_eq!(octet_delta_count.data_type_semantics, Some(DeltaCounter));
+		assert_eq!(octet_delta_count.units, Some(Octets));
+		assert!(octet_delta_count.is_counter());
 
-		98 => (id, "postIpDiffServCodePoint", Unsigned8),
-		99 => (id, "multicastReplicationFactor", Unsigned32),
-		100 => (id, "className", String),
-		101 => (id, "classificationEngineId", Unsigned8),
-		102 => (id, "layer2packetSectionOffset", Unsigned16),
-		103 => (id, "layer2packetSectionSize", Unsigned16),
-		104 => (id, "layer2packetSectionData", OctetArray),
+		let source_port = lookup(7).unwrap();
+		assert_eq!(source_port.data_type_semantics, Some(Identifier));
+		assert!(!source_port.is_counter());
 
-		128 => (id, "bgpNextAdjacentAsNumber", Unsigned32),
-		129 => (id, "bgpPrevAdjacentAsNumber", Unsigned32),
-		130 => (id, "exporterIPv4Address", Ipv4Address),
-		131 => (id, "exporterIPv6Address", Ipv6Address),
-		132 => (id, "droppedOctetDeltaCount", Unsigned64),
-		133 => (id, "droppedPacketDeltaCount", Unsigned64),
-		134 => (id, "droppedOctetTotalCount", Unsigned64),
-		135 => (id, "droppedPacketTotalCount", Unsigned64),
-		136 => (id, "flowEndReason", Unsigned8),
-		137 => (id, "commonPropertiesId", Unsigned64),
-		138 => (id, "observationPointId", Unsigned64),
-		139 => (id, "icmpTypeCodeIPv6", Unsigned16),
-		140 => (id, "mplsTopLabelIPv6Address", Ipv6Address),
-		141 => (id, "lineCardId", Unsigned32),
-		142 => (id, "portId", Unsigned32),
-		143 => (id, "meteringProcessId", Unsigned32),
-		144 => (id, "exportingProcessId", Unsigned32),
-		145 => (id, "templateId", Unsigned16),
-		146 => (id, "wlanChannelId", Unsigned8),
-		147 => (id, "wlanSSID", String),
-		148 => (id, "flowId", Unsigned64),
-		149 => (id, "observationDomainId", Unsigned32),
-		150 => (id, "flowStartSeconds", DateTimeSeconds),
-		151 => (id, "flowEndSeconds", DateTimeSeconds),
-		152 => (id, "flowStartMilliseconds", DateTimeMilliseconds),
-		153 => (id, "flowEndMilliseconds", DateTimeMilliseconds),
-		154 => (id, "flowStartMicroseconds", DateTimeMicroseconds),
-		155 => (id, "flowEndMicroseconds", DateTimeMicroseconds),
-		156 => (id, "flowStartNanoseconds", DateTimeNanoseconds),
-		157 => (id, "flowEndNanoseconds", DateTimeNanoseconds),
-		158 => (id, "flowStartDeltaMicroseconds", Unsigned32),
-		159 => (id, "flowEndDeltaMicroseconds", Unsigned32),
-		160 => (id, "systemInitTimeMilliseconds", DateTimeMilliseconds),
-		161 => (id, "flowDurationMilliseconds", Unsigned32),
-		162 => (id, "flowDurationMicroseconds", Unsigned32),
-		163 => (id, "observedFlowTotalCount", Unsigned64),
-		164 => (id, "ignoredPacketTotalCount", Unsigned64),
-		165 => (id, "ignoredOctetTotalCount", Unsigned64),
-		166 => (id, "notSentFlowTotalCount", Unsigned64),
-		167 => (id, "notSentPacketTotalCount", Unsigned64),
-		168 => (id, "notSentOctetTotalCount", Unsigned64),
-		169 => (id, "destinationIPv6Prefix", Ipv6Address),
-		170 => (id, "sourceIPv6Prefix", Ipv6Address),
-		171 => (id, "postOctetTotalCount", Unsigned64),
-		172 => (id, "postPacketTotalCount", Unsigned64),
-		173 => (id, "flowKeyIndicator", Unsigned64),
-		174 => (id, "postMCastPacketTotalCount", Unsigned64),
-		175 => (id, "postMCastOctetTotalCount", Unsigned64),
-		176 => (id, "icmpTypeIPv4", Unsigned8),
-		177 => (id, "icmpCodeIPv4", Unsigned8),
-		178 => (id, "icmpTypeIPv6", Unsigned8),
-		179 => (id, "icmpCodeIPv6", Unsigned8),
-		180 => (id, "udpSourcePort", Unsigned16),
-		181 => (id, "udpDestinationPort", Unsigned16),
-		182 => (id, "tcpSourcePort", Unsigned16),
-		183 => (id, "tcpDestinationPort", Unsigned16),
-		184 => (id, "tcpSequenceNumber", Unsigned32),
-		185 => (id, "tcpAcknowledgementNumber", Unsigned32),
-		186 => (id, "tcpWindowSize", Unsigned16),
-		187 => (id, "tcpUrgentPointer", Unsigned16),
-		188 => (id, "tcpHeaderLength", Unsigned8),
-		189 => (id, "ipHeaderLength", Unsigned8),
-		190 => (id, "totalLengthIPv4", Unsigned16),
-		191 => (id, "payloadLengthIPv6", Unsigned16),
-		192 => (id, "ipTTL", Unsigned8),
-		193 => (id, "nextHeaderIPv6", Unsigned8),
-		194 => (id, "mplsPayloadLength", Unsigned32),
-		195 => (id, "ipDiffServCodePoint", Unsigned8),
-		196 => (id, "ipPrecedence", Unsigned8),
-		197 => (id, "fragmentFlags", Unsigned8),
-		198 => (id, "octetDeltaSumOfSquares", Unsigned64),
-		199 => (id, "octetTotalSumOfSquares", Unsigned64),
-		200 => (id, "mplsTopLabelTTL", Unsigned8),
-		201 => (id, "mplsLabelStackLength", Unsigned32),
-		202 => (id, "mplsLabelStackDepth", Unsigned32),
-		203 => (id, "mplsTopLabelExp", Unsigned8),
-		204 => (id, "ipPayloadLength", Unsigned32),
-		205 => (id, "udpMessageLength", Unsigned16),
-		206 => (id, "isMulticast", Unsigned8),
-		207 => (id, "ipv4IHL", Unsigned8),
-		208 => (id, "ipv4Options", Unsigned32),
-		209 => (id, "tcpOptions", Unsigned64),
-		210 => (id, "paddingOctets", OctetArray),
-		211 => (id, "collectorIPv4Address", Ipv4Address),
-		212 => (id, "collectorIPv6Address", Ipv6Address),
-		213 => (id, "exportInterface", Unsigned32),
-		214 => (id, "exportProtocolVersion", Unsigned8),
-		215 => (id, "exportTransportProtocol", Unsigned8),
-		216 => (id, "collectorTransportPort", Unsigned16),
-		217 => (id, "exporterTransportPort", Unsigned16),
-		218 => (id, "tcpSynTotalCount", Unsigned64),
-		219 => (id, "tcpFinTotalCount", Unsigned64),
-		220 => (id, "tcpRstTotalCount", Unsigned64),
-		221 => (id, "tcpPshTotalCount", Unsigned64),
-		222 => (id, "tcpAckTotalCount", Unsigned64),
-		223 => (id, "tcpUrgTotalCount", Unsigned64),
-		224 => (id, "ipTotalLength", Unsigned64),
-		225 => (id, "postNATSourceIPv4Address", Ipv4Address),
-		226 => (id, "postNATDestinationIPv4Address", Ipv4Address),
-		227 => (id, "postNAPTSourceTransportPort", Unsigned16),
-		228 => (id, "postNAPTDestinationTransportPort", Unsigned16),
-		229 => (id, "natOriginatingAddressRealm", Unsigned8),
-		230 => (id, "natEvent", Unsigned8),
-		231 => (id, "initiatorOctets", Unsigned64),
-		232 => (id, "responderOctets", Unsigned64),
-		233 => (id, "firewallEvent", Unsigned8),
-		234 => (id, "ingressVRFID", Unsigned32),
-		235 => (id, "egressVRFID", Unsigned32),
-		236 => (id, "VRFname", String),
-		237 => (id, "postMplsTopLabelExp", Unsigned8),
-		238 => (id, "tcpWindowScale", Unsigned16),
-		239 => (id, "biflowDirection", Unsigned8),
-		240 => (id, "ethernetHeaderLength", Unsigned8),
-		241 => (id, "ethernetPayloadLength", Unsigned16),
-		242 => (id, "ethernetTotalLength", Unsigned16),
-		243 => (id, "dot1qVlanId", Unsigned16),
-		244 => (id, "dot1qPriority", Unsigned8),
-		245 => (id, "dot1qCustomerVlanId", Unsigned16),
-		246 => (id, "dot1qCustomerPriority", Unsigned8),
-		247 => (id, "metroEvcId", String),
-		248 => (id, "metroEvcType", Unsigned8),
-		249 => (id, "pseudoWireId", Unsigned32),
-		250 => (id, "pseudoWireType", Unsigned16),
-		251 => (id, "pseudoWireControlWord", Unsigned32),
-		252 => (id, "ingressPhysicalInterface", Unsigned32),
-		253 => (id, "egressPhysicalInterface", Unsigned32),
-		254 => (id, "postDot1qVlanId", Unsigned16),
-		255 => (id, "postDot1qCustomerVlanId", Unsigned16),
-		256 => (id, "ethernetType", Unsigned16),
-		257 => (id, "postIpPrecedence", Unsigned8),
-		258 => (id, "collectionTimeMilliseconds", DateTimeMilliseconds),
-		259 => (id, "exportSctpStreamId", Unsigned16),
-		260 => (id, "maxExportSeconds", DateTimeSeconds),
-		261 => (id, "maxFlowEndSeconds", DateTimeSeconds),
-		262 => (id, "messageMD5Checksum", OctetArray),
-		263 => (id, "messageScope", Unsigned8),
-		264 => (id, "minExportSeconds", DateTimeSeconds),
-		265 => (id, "minFlowStartSeconds", DateTimeSeconds),
-		266 => (id, "opaqueOctets", OctetArray),
-		267 => (id, "sessionScope", Unsigned8),
-		268 => (id, "maxFlowEndMicroseconds", DateTimeMicroseconds),
-		269 => (id, "maxFlowEndMilliseconds", DateTimeMilliseconds),
-		270 => (id, "maxFlowEndNanoseconds", DateTimeNanoseconds),
-		271 => (id, "minFlowStartMicroseconds", DateTimeMicroseconds),
-		272 => (id, "minFlowStartMilliseconds", DateTimeMilliseconds),
-		273 => (id, "minFlowStartNanoseconds", DateTimeNanoseconds),
-		274 => (id, "collectorCertificate", OctetArray),
-		275 => (id, "exporterCertificate", OctetArray),
-		276 => (id, "dataRecordsReliability", Boolean),
-		277 => (id, "observationPointType", Unsigned8),
-		278 => (id, "newConnectionDeltaCount", Unsigned32),
-		279 => (id, "connectionSumDurationSeconds", Unsigned64),
-		280 => (id, "connectionTransactionId", Unsigned64),
-		281 => (id, "postNATSourceIPv6Address", Ipv6Address),
-		282 => (id, "postNATDestinationIPv6Address", Ipv6Address),
-		283 => (id, "natPoolId", Unsigned32),
-		284 => (id, "natPoolName", String),
-		285 => (id, "anonymizationFlags", Unsigned16),
-		286 => (id, "anonymizationTechnique", Unsigned16),
-		287 => (id, "informationElementIndex", Unsigned16),
-		288 => (id, "p2pTechnology", String),
-		289 => (id, "tunnelTechnology", String),
-		290 => (id, "encryptedTechnology", String),
-		291 => (id, "basicList", BasicList),
-		292 => (id, "subTemplateList", SubTemplateList),
-		293 => (id, "subTemplateMultiList", SubTemplateMultiList),
-		294 => (id, "bgpValidityState", Unsigned8),
-		295 => (id, "IPSecSPI", Unsigned32),
-		296 => (id, "greKey", Unsigned32),
-		297 => (id, "natType", Unsigned8),
-		298 => (id, "initiatorPackets", Unsigned64),
-		299 => (id, "responderPackets", Unsigned64),
-		300 => (id, "observationDomainName", String),
-		301 => (id, "selectionSequenceId", Unsigned64),
-		302 => (id, "selectorId", Unsigned64),
-		303 => (id, "informationElementId", Unsigned16),
-		304 => (id, "selectorAlgorithm", Unsigned16),
-		305 => (id, "samplingPacketInterval", Unsigned32),
-		306 => (id, "samplingPacketSpace", Unsigned32),
-		307 => (id, "samplingTimeInterval", Unsigned32),
-		308 => (id, "samplingTimeSpace", Unsigned32),
-		309 => (id, "samplingSize", Unsigned32),
-		310 => (id, "samplingPopulation", Unsigned32),
-		311 => (id, "samplingProbability", Float64),
-		312 => (id, "dataLinkFrameSize", Unsigned16),
-		313 => (id, "ipHeaderPacketSection", OctetArray),
-		314 => (id, "ipPayloadPacketSection", OctetArray),
-		315 => (id, "dataLinkFrameSection", OctetArray),
-		316 => (id, "mplsLabelStackSection", OctetArray),
-		317 => (id, "mplsPayloadPacketSection", OctetArray),
-		318 => (id, "selectorIdTotalPktsObserved", Unsigned64),
-		319 => (id, "selectorIdTotalPktsSelected", Unsigned64),
-		320 => (id, "absoluteError", Float64),
-		321 => (id, "relativeError", Float64),
-		322 => (id, "observationTimeSeconds", DateTimeSeconds),
-		323 => (id, "observationTimeMilliseconds", DateTimeMilliseconds),
-		324 => (id, "observationTimeMicroseconds", DateTimeMicroseconds),
-		325 => (id, "observationTimeNanoseconds", DateTimeNanoseconds),
-		326 => (id, "digestHashValue", Unsigned64),
-		327 => (id, "hashIPPayloadOffset", Unsigned64),
-		328 => (id, "hashIPPayloadSize", Unsigned64),
-		329 => (id, "hashOutputRangeMin", Unsigned64),
-		330 => (id, "hashOutputRangeMax", Unsigned64),
-		331 => (id, "hashSelectedRangeMin", Unsigned64),
-		332 => (id, "hashSelectedRangeMax", Unsigned64),
-		333 => (id, "hashDigestOutput", Boolean),
-		334 => (id, "hashInitialiserValue", Unsigned64),
-		335 => (id, "selectorName", String),
-		336 => (id, "upperCILimit", Float64),
-		337 => (id, "lowerCILimit", Float64),
-		338 => (id, "confidenceLevel", Float64),
-		339 => (id, "informationElementDataType", Unsigned8),
-		340 => (id, "informationElementDescription", String),
-		341 => (id, "informationElementName", String),
-		342 => (id, "informationElementRangeBegin", Unsigned64),
-		343 => (id, "informationElementRangeEnd", Unsigned64),
-		344 => (id, "informationElementSemantics", Unsigned8),
-		345 => (id, "informationElementUnits", Unsigned16),
-		346 => (id, "privateEnterpriseNumber", Unsigned32),
-		347 => (id, "virtualStationInterfaceId", OctetArray),
-		348 => (id, "virtualStationInterfaceName", String),
-		349 => (id, "virtualStationUUID", OctetArray),
-		350 => (id, "virtualStationName", String),
-		351 => (id, "layer2SegmentId", Unsigned64),
-		352 => (id, "layer2OctetDeltaCount", Unsigned64),
-		353 => (id, "layer2OctetTotalCount", Unsigned64),
-		354 => (id, "ingressUnicastPacketTotalCount", Unsigned64),
-		355 => (id, "ingressMulticastPacketTotalCount", Unsigned64),
-		356 => (id, "ingressBroadcastPacketTotalCount", Unsigned64),
-		357 => (id, "egressUnicastPacketTotalCount", Unsigned64),
-		358 => (id, "egressBroadcastPacketTotalCount", Unsigned64),
-		359 => (id, "monitoringIntervalStartMilliSeconds", DateTimeMilliseconds),
-		360 => (id, "monitoringIntervalEndMilliSeconds", DateTimeMilliseconds),
-		361 => (id, "portRangeStart", Unsigned16),
-		362 => (id, "portRangeEnd", Unsigned16),
-		363 => (id, "portRangeStepSize", Unsigned16),
-		364 => (id, "portRangeNumPorts", Unsigned16),
-		365 => (id, "staMacAddress", MacAddress),
-		366 => (id, "staIPv4Address", Ipv4Address),
-		367 => (id, "wtpMacAddress", MacAddress),
-		368 => (id, "ingressInterfaceType", Unsigned32),
-		369 => (id, "egressInterfaceType", Unsigned32),
-		370 => (id, "rtpSequenceNumber", Unsigned16),
-		371 => (id, "userName", String),
-		372 => (id, "applicationCategoryName", String),
-		373 => (id, "applicationSubCategoryName", String),
-		374 => (id, "applicationGroupName", String),
-		375 => (id, "originalFlowsPresent", Unsigned64),
-		376 => (id, "originalFlowsInitiated", Unsigned64),
-		377 => (id, "originalFlowsCompleted", Unsigned64),
-		378 => (id, "distinctCountOfSourceIPAddress", Unsigned64),
-		379 => (id, "distinctCountOfDestinationIPAddress", Unsigned64),
-		380 => (id, "distinctCountOfSourceIPv4Address", Unsigned32),
-		381 => (id, "distinctCountOfDestinationIPv4Address", Unsigned32),
-		382 => (id, "distinctCountOfSourceIPv6Address", Unsigned64),
-		383 => (id, "distinctCountOfDestinationIPv6Address", Unsigned64),
-		384 => (id, "valueDistributionMethod", Unsigned8),
-		385 => (id, "rfc3550JitterMilliseconds", Unsigned32),
-		386 => (id, "rfc3550JitterMicroseconds", Unsigned32),
-		387 => (id, "rfc3550JitterNanoseconds", Unsigned32),
-		388 => (id, "dot1qDEI", Boolean),
-		389 => (id, "dot1qCustomerDEI", Boolean),
-		390 => (id, "flowSelectorAlgorithm", Unsigned16),
-		391 => (id, "flowSelectedOctetDeltaCount", Unsigned64),
-		392 => (id, "flowSelectedPacketDeltaCount", Unsigned64),
-		393 => (id, "flowSelectedFlowDeltaCount", Unsigned64),
-		394 => (id, "selectorIDTotalFlowsObserved", Unsigned64),
-		395 => (id, "selectorIDTotalFlowsSelected", Unsigned64),
-		396 => (id, "samplingFlowInterval", Unsigned64),
-		397 => (id, "samplingFlowSpacing", Unsigned64),
-		398 => (id, "flowSamplingTimeInterval", Unsigned64),
-		399 => (id, "flowSamplingTimeSpacing", Unsigned64),
-		400 => (id, "hashFlowDomain", Unsigned16),
-		401 => (id, "transportOctetDeltaCount", Unsigned64),
-		402 => (id, "transportPacketDeltaCount", Unsigned64),
-		403 => (id, "originalExporterIPv4Address", Ipv4Address),
-		404 => (id, "originalExporterIPv6Address", Ipv6Address),
-		405 => (id, "originalObservationDomainId", Unsigned32),
-		406 => (id, "intermediateProcessId", Unsigned32),
-		407 => (id, "ignoredDataRecordTotalCount", Unsigned64),
-		408 => (id, "dataLinkFrameType", Unsigned16),
-		409 => (id, "sectionOffset", Unsigned16),
-		410 => (id, "sectionExportedOctets", Unsigned16),
-		411 => (id, "dot1qServiceInstanceTag", OctetArray),
-		412 => (id, "dot1qServiceInstanceId", Unsigned32),
-		413 => (id, "dot1qServiceInstancePriority", Unsigned8),
-		414 => (id, "dot1qCustomerSourceMacAddress", MacAddress),
-		415 => (id, "dot1qCustomerDestinationMacAddress", MacAddress),
+		let vlan_id = lookup(58).unwrap();
+		assert!(vlan_id.in_range(4095));
+		assert!(!vlan_id.in_range(4096));
 
-		417 => (id, "postLayer2OctetDeltaCount", Unsigned64),
-		418 => (id, "postMCastLayer2OctetDeltaCount", Unsigned64),
+		assert_eq!(lookup(84).unwrap().status, Status::Deprecated);
+		assert_eq!(lookup(8).unwrap().enterprise_number, None);
+		assert_eq!(lookup(189).unwrap().units.unwrap().to_string(), "4-octet words");
+	}
 
-		420 => (id, "postLayer2OctetTotalCount", Unsigned64),
-		421 => (id, "postMCastLayer2OctetTotalCount", Unsigned64),
-		422 => (id, "minimumLayer2TotalLength", Unsigned64),
-		423 => (id, "maximumLayer2TotalLength", Unsigned64),
-		424 => (id, "droppedLayer2OctetDeltaCount", Unsigned64),
-		425 => (id, "droppedLayer2OctetTotalCount", Unsigned64),
-		426 => (id, "ignoredLayer2OctetTotalCount", Unsigned64),
-		427 => (id, "notSentLayer2OctetTotalCount", Unsigned64),
-		428 => (id, "layer2OctetDeltaSumOfSquares", Unsigned64),
-		429 => (id, "layer2OctetTotalSumOfSquares", Unsigned64),
-		430 => (id, "layer2FrameDeltaCount", Unsigned64),
-		431 => (id, "layer2FrameTotalCount", Unsigned64),
-		432 => (id, "pseudoWireDestinationIPv4Address", Ipv4Address),
-		433 => (id, "ignoredLayer2FrameTotalCount", Unsigned64),
-		434 => (id, "mibObjectValueInteger", Signed32),
-		435 => (id, "mibObjectValueOctetString", OctetArray),
-		436 => (id, "mibObjectValueOID", OctetArray),
-		437 => (id, "mibObjectValueBits", OctetArray),
-		438 => (id, "mibObjectValueIPAddress", Ipv4Address),
-		439 => (id, "mibObjectValueCounter", Unsigned64),
-		440 => (id, "mibObjectValueGauge", Unsigned32),
-		441 => (id, "mibObjectValueTimeTicks", Unsigned32),
-		442 => (id, "mibObjectValueUnsigned", Unsigned32),
-		443 => (id, "mibObjectValueTable", SubTemplateList),
-		444 => (id, "mibObjectValueRow", SubTemplateList),
-		445 => (id, "mibObjectIdentifier", OctetArray),
-		446 => (id, "mibSubIdentifier", Unsigned32),
-		447 => (id, "mibIndexIndicator", Unsigned64),
-		448 => (id, "mibCaptureTimeSemantics", Unsigned8),
-		449 => (id, "mibContextEngineID", OctetArray),
-		450 => (id, "mibContextName", String),
-		451 => (id, "mibObjectName", String),
-		452 => (id, "mibObjectDescription", String),
-		453 => (id, "mibObjectSyntax", String),
-		454 => (id, "mibModuleName", String),
-		455 => (id, "mobileIMSI", String),
-		456 => (id, "mobileMSISDN", String),
-		457 => (id, "httpStatusCode", Unsigned16),
-		458 => (id, "sourceTransportPortsLimit", Unsigned16),
-		459 => (id, "httpRequestMethod", String),
-		460 => (id, "httpRequestHost", String),
-		461 => (id, "httpRequestTarget", String),
-		462 => (id, "httpMessageVersion", String),
-		463 => (id, "natInstanceID", Unsigned32),
-		464 => (id, "internalAddressRealm", OctetArray),
-		465 => (id, "externalAddressRealm", OctetArray),
-		466 => (id, "natQuotaExceededEvent", Unsigned32),
-		467 => (id, "natThresholdEvent", Unsigned32),
-		468 => (id, "httpUserAgent", String),
-		469 => (id, "httpContentType", String),
-		470 => (id, "httpReasonPhrase", String),
-		471 => (id, "maxSessionEntries", Unsigned32),
-		472 => (id, "maxBIBEntries", Unsigned32),
-		473 => (id, "maxEntriesPerUser", Unsigned32),
-		474 => (id, "maxSubscribers", Unsigned32),
-		475 => (id, "maxFragmentsPendingReassembly", Unsigned32),
-		476 => (id, "addressPoolHighThreshold", Unsigned32),
-		477 => (id, "addressPoolLowThreshold", Unsigned32),
-		478 => (id, "addressPortMappingHighThreshold", Unsigned32),
-		479 => (id, "addressPortMappingLowThreshold", Unsigned32),
-		480 => (id, "addressPortMappingPerUserHighThreshold", Unsigned32),
-		481 => (id, "globalAddressMappingHighThreshold", Unsigned32),
+	#[test]
+	fn lookup_by_name_test() {
+		assert_eq!(lookup_by_name("sourceIPv4Address").unwrap().id, 8);
+		assert_eq!(lookup_by_name("octetDeltaCount").unwrap().id, 1);
+		assert_eq!(lookup_by_name("globalAddressMappingHighThreshold").unwrap().id, 481);
+		assert!(lookup_by_name("sourceipv4address").is_none());
+		assert!(lookup_by_name("").is_none());
+	}
 
-		_ => return None,
-	};
-	Some(InformationElement {
-		id : tuple.0,
-		name : tuple.1,
-		abstract_data_type : tuple.2,
-	})
+	#[test]
+	fn constants_test() {
+		assert_eq!(ie::SOURCE_IPV4_ADDRESS, 8);
+		assert_eq!(ie::POST_NAT_SOURCE_IPV4_ADDRESS, 225);
+		assert_eq!(ie::FLOW_LABEL_IPV6, 31);
+		assert_eq!(ie::DOT1Q_VLAN_ID, 243);
+		assert_eq!(ie::IPV4_IHL, 207);
+		assert_eq!(ie::RFC3550_JITTER_MILLISECONDS, 385);
+	}
 }
@@ -0,0 +1,358 @@
+use registry::*;
+use sink::*;
+use std;
+use structs::*;
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JsonFormat {
+	/// `{"sourceIPv4Address": "10.0.0.1", "unknown:6871:18": 1}`,
+	/// unknown information elements use the id format with `UNKNOWN_FIELD_PREFIX`
+	Names,
+	/// `{"8": "10.0.0.1", "unknown:6871:18": 1}`
+	Ids,
+	/// `[{"ie": "8", "name": "sourceIPv4Address", "value": "10.0.0.1"}]`,
+	/// unknown information elements have `"name": null, "unknown": true`
+	Entries,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeFormat {
+	/// `dateTimeSeconds` and `dateTimeMilliseconds` since the Unix epoch as numbers,
+	/// `dateTimeMicroseconds` and `dateTimeNanoseconds` as NTP `[seconds, fraction]`
+	Raw,
+	/// `"2018-02-17T10:49:50.123Z"` with the precision of the data type
+	Rfc3339,
+}
+
+/// JSON representation of a data record.
+/// In the map formats repeated information elements are numbered from the second occurrence on,
+/// e.g. `"paddingOctets"`, `"paddingOctets#2"`, to keep the keys unique.
+pub struct JsonDataRecord<'a> {
+	pub record : TypedDataRecord<'a>,
+	pub registry : &'a Registry,
+	pub format : JsonFormat,
+	pub time_format : TimeFormat,
+	/// wraps the fields in an object with export time, observation domain and template id
+	pub header : Option<&'a MessageHeader>,
+}
+
+impl<'a> Serialize for JsonDataRecord<'a> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		let fields = JsonFields { record : self };
+		match self.header {
+			None => fields.serialize(s),
+			Some(header) => {
+				let mut map = s.serialize_map(Some(5))?;
+				map.serialize_entry("export_time", &header.export_time)?;
+				map.serialize_entry("sequence_number", &header.sequence_number)?;
+				map.serialize_entry("observation_domain_id", &header.observation_domain_id)?;
+				map.serialize_entry("template_id", &self.record.template.header.template_id)?;
+				map.serialize_entry("fields", &fields)?;
+				map.end()
+			}
+		}
+	}
+}
+
+struct JsonFields<'a, 'b : 'a> {
+	record : &'a JsonDataRecord<'b>,
+}
+
+impl<'a, 'b> Serialize for JsonFields<'a, 'b> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		let template = self.record.record.template;
+		let specifiers = template.scope_fields.iter().chain(template.fields.iter());
+		let fields = specifiers.zip(self.record.record.data.fields.iter());
+
+		match self.record.format {
+			JsonFormat::Names | JsonFormat::Ids => {
+				let mut map = s.serialize_map(Some(self.record.record.data.fields.len()))?;
+				let keys = match self.record.format {
+					JsonFormat::Names => unique_field_names(template, self.record.registry),
+					_ => unique_field_keys(template, self.record.registry),
+				};
+				for (key, (_, value)) in keys.iter().zip(fields) {
+					map.serialize_entry(key, &self.value(value))?;
+				}
+				map.end()
+			}
+			JsonFormat::Entries => {
+				let mut seq = s.serialize_seq(Some(self.record.record.data.fields.len()))?;
+				for (specifier, value) in fields {
+					seq.serialize_element(&JsonEntry {
+						ie : field_key(specifier),
+						name : field_name(specifier, self.record.registry),
+						value : self.value(value),
+					})?;
+				}
+				seq.end()
+			}
+		}
+	}
+}
+
+impl<'a, 'b> JsonFields<'a, 'b> {
+	fn value<'v>(&self, value : &'v DataValue) -> JsonValue<'v> {
+		JsonValue {
+			value,
+			time_format : self.record.time_format,
+		}
+	}
+}
+
+struct JsonValue<'a> {
+	value : &'a DataValue,
+	time_format : TimeFormat,
+}
+
+impl<'a> Serialize for JsonValue<'a> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		match (self.time_format, self.value.to_rfc3339()) {
+			(TimeFormat::Rfc3339, Some(rfc3339)) => s.serialize_str(&rfc3339),
+			_ => self.value.serialize(s),
+		}
+	}
+}
+
+struct JsonEntry<'a> {
+	ie : String,
+	name : Option<&'a str>,
+	value : JsonValue<'a>,
+}
+
+impl<'a> Serialize for JsonEntry<'a> {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		let mut map = s.serialize_map(None)?;
+		map.serialize_entry("ie", &self.ie)?;
+		map.serialize_entry("name", &self.name)?;
+		map.serialize_entry("value", &self.value)?;
+		if self.name.is_none() {
+			// kept as raw octets by `UnknownFieldPolicy::KeepRaw`
+			map.serialize_entry("unknown", &true)?;
+		}
+		map.end()
+	}
+}
+
+/// Writes one JSON document per line
+pub struct JsonSink<W : std::io::Write> {
+	writer : W,
+	pub format : JsonFormat,
+	pub time_format : TimeFormat,
+	pub context : bool,
+}
+
+impl<W : std::io::Write> JsonSink<W> {
+	pub fn new(writer : W, format : JsonFormat) -> JsonSink<W> {
+		JsonSink {
+			writer,
+			format,
+			time_format : TimeFormat::Raw,
+			context : true,
+		}
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+impl<W : std::io::Write> Sink for JsonSink<W> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		let json = JsonDataRecord {
+			record : context.typed(record),
+			registry : context.registry,
+			format : self.format,
+			time_format : self.time_format,
+			header : if self.context {
+				Some(context.header)
+			} else {
+				None
+			},
+		};
+		serde_json::to_writer(&mut self.writer, &json)?;
+		self.writer.write_all(b"\n")
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde::de::DeserializeSeed;
+
+	fn template() -> TemplateRecord {
+		TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 3,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 210,
+					field_length : 1,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 210,
+					field_length : 1,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 18,
+					field_length : 1,
+					enterprise_number : Some(6871),
+				},
+			],
+		}
+	}
+
+	fn json(format : JsonFormat, header : Option<&MessageHeader>) -> String {
+		let template = template();
+		let record = DataRecord {
+			fields : vec![
+				DataValue::OctetArray(vec![0]),
+				DataValue::OctetArray(vec![1]),
+				DataValue::Unsigned8(2),
+			],
+		};
+		serde_json::to_string(&JsonDataRecord {
+			record : TypedDataRecord {
+				data : &record,
+				template : &template,
+			},
+			registry : &Registry::default(),
+			format,
+			time_format : TimeFormat::Raw,
+			header,
+		}).unwrap()
+	}
+
+	#[test]
+	fn names_test() {
+		assert_eq!(
+			json(JsonFormat::Names, None),
+			"{\"paddingOctets\":[0],\"paddingOctets#2\":[1],\"unknown:6871:18\":2}"
+		);
+	}
+
+	#[test]
+	fn ids_test() {
+		assert_eq!(
+			json(JsonFormat::Ids, None),
+			"{\"210\":[0],\"210#2\":[1],\"unknown:6871:18\":2}"
+		);
+	}
+
+	#[test]
+	fn entries_test() {
+		assert_eq!(
+			json(JsonFormat::Entries, None),
+			"[{\"ie\":\"210\",\"name\":\"paddingOctets\",\"value\":[0]},\
+			 {\"ie\":\"210\",\"name\":\"paddingOctets\",\"value\":[1]},\
+			 {\"ie\":\"6871:18\",\"name\":null,\"value\":2,\"unknown\":true}]"
+		);
+	}
+
+	#[test]
+	fn context_test() {
+		let header = MessageHeader {
+			version_number : IPFIX_VERSION_NUMBER,
+			length : 0,
+			export_time : 1_500_000_000,
+			sequence_number : 7,
+			observation_domain_id : 1,
+		};
+		assert_eq!(
+			json(JsonFormat::Ids, Some(&header)),
+			"{\"export_time\":1500000000,\"sequence_number\":7,\"observation_domain_id\":1,\
+			 \"template_id\":256,\"fields\":{\"210\":[0],\"210#2\":[1],\"unknown:6871:18\":2}}"
+		);
+	}
+
+	#[test]
+	fn rfc3339_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 2,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 152,
+					field_length : 8,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 156,
+					field_length : 8,
+					enterprise_number : None,
+				},
+			],
+		};
+		let record = DataRecord {
+			fields : vec![
+				DataValue::DateTimeMilliseconds(1_518_864_590_123),
+				DataValue::DateTimeNanoseconds {
+					seconds : 3_727_853_390,
+					fraction : 0x8000_0000,
+				},
+			],
+		};
+		let json = |time_format| {
+			serde_json::to_string(&JsonDataRecord {
+				record : TypedDataRecord {
+					data : &record,
+					template : &template,
+				},
+				registry : &Registry::default(),
+				format : JsonFormat::Names,
+				time_format,
+				header : None,
+			}).unwrap()
+		};
+		assert_eq!(
+			json(TimeFormat::Rfc3339),
+			"{\"flowStartMilliseconds\":\"2018-02-17T10:49:50.123Z\",\
+			 \"flowStartNanoseconds\":\"2018-02-17T10:49:50.500000000Z\"}"
+		);
+		assert_eq!(
+			json(TimeFormat::Raw),
+			"{\"flowStartMilliseconds\":1518864590123,\
+			 \"flowStartNanoseconds\":[3727853390,2147483648]}"
+		);
+
+		// both are read back
+		let seed = TypedDataRecordSeed {
+			template : &template,
+			registry : &Registry::default(),
+		};
+		for &time_format in &[TimeFormat::Rfc3339, TimeFormat::Raw] {
+			let json = json(time_format);
+			let mut deserializer = serde_json::Deserializer::from_str(&json);
+			assert_eq!(seed.deserialize(&mut deserializer).unwrap(), record);
+		}
+	}
+}
@@ -1,17 +1,96 @@
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "cbor")]
+extern crate ciborium;
+#[cfg(feature = "derive")]
+extern crate libipfix_derive;
 #[macro_use]
 extern crate log;
-#[macro_use]
 extern crate nom;
+#[cfg(feature = "parquet")]
+extern crate parquet;
+#[cfg(feature = "msgpack")]
+extern crate rmp_serde;
+extern crate roxmltree;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
 extern crate serde;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+extern crate serde_content;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "arrow")]
+pub use arrow::*;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub mod binary;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub use binary::*;
 pub mod collect;
 pub use collect::*;
+pub mod convert;
+pub use convert::*;
+pub mod csv;
+pub use csv::*;
+pub mod data_ref;
+pub use data_ref::*;
+pub mod decode_plan;
+pub use decode_plan::*;
+pub mod decoder_options;
+pub use decoder_options::*;
+pub mod elasticsearch;
+pub use elasticsearch::*;
+pub mod encode;
+pub use encode::*;
+pub mod filter;
+pub use filter::*;
+pub mod flow_time;
+pub use flow_time::*;
+pub mod flow_view;
+pub use flow_view::*;
+mod iana_xml;
 pub mod information_element;
 pub use information_element::*;
+pub mod json;
+pub use json::*;
+#[cfg(feature = "parquet")]
+pub mod parquet_sink;
+#[cfg(feature = "parquet")]
+pub use parquet_sink::*;
 pub mod parser;
 pub use parser::*;
+pub mod projection;
+pub use projection::*;
+pub mod record;
+pub use record::*;
+pub mod registry;
+pub use registry::*;
+pub mod sink;
+pub use sink::*;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
 pub mod structs;
 pub use structs::*;
+pub mod template_builder;
+pub use template_builder::*;
 pub mod template_management;
 pub use template_management::*;
+#[cfg(test)]
+mod test_util;
+pub mod timestamp;
+pub use timestamp::*;
+pub mod type_options;
+pub use type_options::*;
+
+#[cfg(feature = "derive")]
+pub use libipfix_derive::IpfixRecord;
@@ -0,0 +1,280 @@
+use arrow::*;
+use data_ref::*;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use sink::*;
+use std;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use structs::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParquetOptions {
+	/// Files are rolled when the export time leaves this window, aligned to the Unix epoch
+	pub window : Duration,
+	/// Files are rolled when about this many bytes are written
+	pub max_file_size : u64,
+	/// Records buffered before they are handed to the writer
+	pub batch_rows : usize,
+	pub addresses : AddressFormat,
+}
+
+impl Default for ParquetOptions {
+	fn default() -> ParquetOptions {
+		ParquetOptions {
+			window : Duration::from_secs(3600),
+			max_file_size : 128 << 20,
+			batch_rows : 8192,
+			addresses : AddressFormat::Binary,
+		}
+	}
+}
+
+/// Writes data records to Parquet files with one schema per template, see `template_schema`.
+/// Records are partitioned like `domain=1/template=256/1700000000-0.parquet`
+/// by observation domain, template and the start of their export time window.
+/// Files are written with a `.tmp` suffix until they are complete,
+/// `flush` completes all open files. Dropping the sink completes them too but only logs errors.
+pub struct ParquetSink {
+	directory : PathBuf,
+	options : ParquetOptions,
+	partitions : HashMap<(u32, u16), Partition>,
+}
+
+struct Partition {
+	template : TemplateRecord,
+	window_start : u64,
+	builder : RecordBatchBuilder,
+	writer : ArrowWriter<File>,
+	path : PathBuf,
+}
+
+impl ParquetSink {
+	pub fn new<P : Into<PathBuf>>(directory : P, options : ParquetOptions) -> ParquetSink {
+		ParquetSink {
+			directory : directory.into(),
+			options,
+			partitions : HashMap::new(),
+		}
+	}
+
+	pub fn directory(&self) -> &Path {
+		&self.directory
+	}
+
+	fn window_start(&self, export_time : u32) -> u64 {
+		let window = std::cmp::max(self.options.window.as_secs(), 1);
+		export_time as u64 / window * window
+	}
+
+	fn open(&self, context : &RecordContext, window_start : u64) -> io::Result<Partition> {
+		let template = context.template;
+		let directory = self.directory.join(format!(
+			"domain={}/template={}",
+			context.header.observation_domain_id, template.header.template_id
+		));
+		std::fs::create_dir_all(&directory)?;
+		// files of earlier runs and rolled files of the same window are kept
+		let path = (0..)
+			.map(|n| directory.join(format!("{}-{}.parquet", window_start, n)))
+			.find(|path| !path.exists() && !tmp_path(path).exists())
+			.unwrap();
+
+		let builder = RecordBatchBuilder::new(template, context.registry, self.options.addresses);
+		let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+		let file = File::create(tmp_path(&path))?;
+		let writer =
+			ArrowWriter::try_new(file, builder.schema(), Some(properties)).map_err(other)?;
+		debug!("writing {}", path.display());
+		Ok(Partition {
+			template : template.clone(),
+			window_start,
+			builder,
+			writer,
+			path,
+		})
+	}
+
+	fn close(&mut self, key : (u32, u16)) -> io::Result<()> {
+		match self.partitions.remove(&key) {
+			Some(partition) => partition.close(),
+			None => Ok(()),
+		}
+	}
+}
+
+impl Sink for ParquetSink {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> io::Result<()> {
+		let key = (context.header.observation_domain_id, context.template.header.template_id);
+		let window_start = self.window_start(context.header.export_time);
+
+		let roll = match self.partitions.get(&key) {
+			Some(partition) => {
+				partition.template != *context.template
+					|| partition.window_start != window_start
+					|| partition.size() >= self.options.max_file_size
+			}
+			None => false,
+		};
+		if roll {
+			self.close(key)?;
+		}
+		if !self.partitions.contains_key(&key) {
+			let partition = self.open(context, window_start)?;
+			self.partitions.insert(key, partition);
+		}
+
+		let batch_rows = self.options.batch_rows;
+		let partition = self.partitions.get_mut(&key).unwrap();
+		partition.builder.append(&DataRecordRef::from(record));
+		if partition.builder.len() >= batch_rows {
+			partition.write()?;
+		}
+		Ok(())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		let keys = self.partitions.keys().cloned().collect::<Vec<_>>();
+		for key in keys {
+			self.close(key)?;
+		}
+		Ok(())
+	}
+}
+
+impl Drop for ParquetSink {
+	fn drop(&mut self) {
+		if let Err(e) = self.flush() {
+			error!("{}", e);
+		}
+	}
+}
+
+impl Partition {
+	// estimate including buffered records
+	fn size(&self) -> u64 {
+		(self.writer.bytes_written() + self.writer.in_progress_size()) as u64
+	}
+
+	fn write(&mut self) -> io::Result<()> {
+		if self.builder.is_empty() {
+			return Ok(());
+		}
+		let batch = self.builder.finish().map_err(other)?;
+		self.writer.write(&batch).map_err(other)
+	}
+
+	fn close(mut self) -> io::Result<()> {
+		self.write()?;
+		self.writer.close().map_err(other)?;
+		std::fs::rename(tmp_path(&self.path), &self.path)?;
+		debug!("wrote {}", self.path.display());
+		Ok(())
+	}
+}
+
+fn tmp_path(path : &Path) -> PathBuf {
+	path.with_extension("parquet.tmp")
+}
+
+fn other<E : std::error::Error + Send + Sync + 'static>(e : E) -> io::Error {
+	io::Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use information_element::*;
+	use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+	use registry::*;
+	use std::net::Ipv4Addr;
+	use template_builder::*;
+
+	fn read(path : &Path) -> (Vec<String>, usize) {
+		let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+			.unwrap()
+			.build()
+			.unwrap();
+		let mut names = Vec::new();
+		let mut rows = 0;
+		for batch in reader {
+			let batch = batch.unwrap();
+			names = batch.schema().fields().iter().map(|f| f.name().clone()).collect();
+			rows += batch.num_rows();
+		}
+		(names, rows)
+	}
+
+	#[test]
+	fn parquet_sink_test() {
+		let directory =
+			std::env::temp_dir().join(format!("libipfix-parquet-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&directory);
+
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::OCTET_DELTA_COUNT)
+			.build()
+			.unwrap();
+		let mut header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 7200,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let record = DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::Unsigned64(1500),
+			],
+		};
+
+		let mut sink = ParquetSink::new(
+			&directory,
+			ParquetOptions {
+				batch_rows : 2,
+				..ParquetOptions::default()
+			},
+		);
+		for export_time in &[7200, 7300, 7400, 10800] {
+			header.export_time = *export_time;
+			let context = RecordContext {
+				header : &header,
+				template : &template,
+				registry : &registry,
+			};
+			sink.record(&context, &record).unwrap();
+		}
+		let partition = directory.join("domain=1/template=256");
+		// the window of the last record is still open
+		assert!(partition.join("7200-0.parquet").exists());
+		assert!(partition.join("10800-0.parquet.tmp").exists());
+		assert!(!partition.join("10800-0.parquet").exists());
+		sink.flush().unwrap();
+
+		assert_eq!(
+			read(&partition.join("7200-0.parquet")),
+			(vec!["sourceIPv4Address".to_string(), "octetDeltaCount".to_string()], 3)
+		);
+		assert_eq!(read(&partition.join("10800-0.parquet")).1, 1);
+
+		// files are never overwritten
+		let context = RecordContext {
+			header : &header,
+			template : &template,
+			registry : &registry,
+		};
+		sink.record(&context, &record).unwrap();
+		// dropping the sink completes the file
+		drop(sink);
+		assert_eq!(read(&partition.join("10800-1.parquet")).1, 1);
+
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
@@ -1,11 +1,15 @@
-use information_element::*;
+use data_ref::*;
+use decoder_options::*;
 use nom::*;
+use registry::*;
 use std;
+use std::borrow::Cow;
 use structs::*;
+use template_management::verify_field_length;
 
 // TODO insert padding
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named!(pub message_parser<Message>,
 	do_parse!(
 		message_header : message_header_parser >>
@@ -23,7 +27,7 @@ named!(pub message_parser<Message>,
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named!(
 	message_header_parser<MessageHeader>,
 	do_parse!(
@@ -42,7 +46,7 @@ named!(
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named!(
 	set_header_parser<SetHeader>,
 	do_parse!(
@@ -52,7 +56,7 @@ named!(
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named!(
 	field_specifier_parser<FieldSpecifier>,
 	do_parse!(
@@ -70,7 +74,7 @@ named!(
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named_args!(
 	pub template_records_parser(set_header : SetHeader)<Vec<TemplateRecord>>,
 	length_value!(
@@ -81,20 +85,54 @@ named_args!(
 	)
 );
 
+/// Relaxations allowed by `options` are added to `report`
 pub fn data_records_parser<'input>(
 	input : &'input [u8],
 	records_length : u16,
 	template : &TemplateRecord,
+	registry : &Registry,
+	options : &DecoderOptions,
+	report : &mut DecodeReport,
 ) -> IResult<&'input [u8], Vec<DataRecord>> {
+	data_records_ref_parser(input, records_length, template, registry, options, report).map(
+		|(rest, records)| {
+			(
+				rest,
+				records.into_iter().map(DataRecordRef::into_owned).collect(),
+			)
+		},
+	)
+}
+
+/// Like `data_records_parser`, but octet arrays, MAC addresses and strings borrow from `input`
+pub fn data_records_ref_parser<'input>(
+	input : &'input [u8],
+	records_length : u16,
+	template : &TemplateRecord,
+	registry : &Registry,
+	options : &DecoderOptions,
+	report : &mut DecodeReport,
+) -> IResult<&'input [u8], Vec<DataRecordRef<'input>>> {
 	let (rest_after_records, mut input) = take!(input, records_length as usize)?;
 
 	// do-while loop
 	// at least 1 data record
-	let mut records = Vec::<DataRecord>::default();
+	let mut records = Vec::<DataRecordRef>::default();
 	loop {
-		let (rest, record) = data_record_parser(input, template)?;
-		input = rest;
-		records.push(record);
+		// relaxations of incomplete records are not reported
+		let mut record_report = DecodeReport::default();
+		match data_record_ref_parser(input, template, registry, options, &mut record_report) {
+			Ok((rest, record)) => {
+				input = rest;
+				records.push(record);
+				report.add(&record_report);
+			}
+			Err(_) if options.skip_trailing_octets && !records.is_empty() => {
+				report.trailing_octets += input.len() as u64;
+				break;
+			}
+			Err(e) => return Err(e),
+		}
 
 		if input.is_empty() {
 			break;
@@ -120,25 +158,48 @@ pub mod error_kind {
 		ErrorKind::Custom(SemanticError::StringNotUtf8 as u32);
 }
 
-fn data_record_parser<'input>(
+fn data_record_ref_parser<'input>(
 	input : &'input [u8],
 	template : &TemplateRecord,
-) -> IResult<&'input [u8], DataRecord> {
+	registry : &Registry,
+	options : &DecoderOptions,
+	report : &mut DecodeReport,
+) -> IResult<&'input [u8], DataRecordRef<'input>> {
 	let mut input = input;
-	let mut fields = Vec::<DataValue>::default();
-
-	for field in &template.fields {
-		let information_element = lookup(field.information_element_id).ok_or_else(|| {
-			Err::Error(error_position!(
-				input,
-				error_kind::INFORMATION_ELEMENT_UNKNOWN
-			))
-		})?; // return if Err
+	let mut fields = Vec::<DataValueRef>::with_capacity(template.header.field_count as usize);
+
+	for field in template.scope_fields.iter().chain(template.fields.iter()) {
+		let abstract_data_type = match registry.lookup_field(field) {
+			Some(information_element)
+				if options.raw_field_lengths
+					&& verify_field_length(
+						information_element.abstract_data_type,
+						field.field_length,
+					)
+					.is_err() =>
+			{
+				report.raw_field_lengths += 1;
+				AbstractDataType::OctetArray
+			}
+			Some(information_element) => information_element.abstract_data_type,
+			None if options.unknown_fields == UnknownFieldPolicy::KeepRaw => {
+				report.unknown_fields += 1;
+				AbstractDataType::OctetArray
+			}
+			None => {
+				return Err(Err::Error(error_position!(
+					input,
+					error_kind::INFORMATION_ELEMENT_UNKNOWN
+				)))
+			}
+		};
 
-		match information_element_parser(
+		match information_element_parser_with(
 			input,
-			information_element.abstract_data_type,
+			abstract_data_type,
 			field.field_length,
+			options,
+			report,
 		) {
 			Err(err) => return Err(err),
 			Ok((rest, field)) => {
@@ -147,64 +208,84 @@ fn data_record_parser<'input>(
 			}
 		}
 	}
-	Ok((input, DataRecord { fields }))
+	Ok((input, DataRecordRef { fields }))
 }
 
+#[cfg(test)]
 fn information_element_parser(
 	input : &[u8],
 	abstract_data_type : AbstractDataType,
 	length : u16,
 ) -> IResult<&[u8], DataValue> {
+	let options = DecoderOptions::strict();
+	let mut report = DecodeReport::default();
+	information_element_parser_with(input, abstract_data_type, length, &options, &mut report)
+		.map(|(rest, value)| (rest, value.into_owned()))
+}
+
+fn information_element_parser_with<'input>(
+	input : &'input [u8],
+	abstract_data_type : AbstractDataType,
+	length : u16,
+	options : &DecoderOptions,
+	report : &mut DecodeReport,
+) -> IResult<&'input [u8], DataValueRef<'input>> {
 	use structs::AbstractDataType::*;
 
 	match abstract_data_type {
 		Unsigned8 | Unsigned16 | Unsigned32 | Unsigned64 => match length {
-			1 => map!(input, be_u8, DataValue::Unsigned8),
-			2 => map!(input, be_u16, DataValue::Unsigned16),
-			4 => map!(input, be_u32, DataValue::Unsigned32),
-			8 => map!(input, be_u64, DataValue::Unsigned64),
+			1 => map!(input, be_u8, DataValueRef::Unsigned8),
+			2 => map!(input, be_u16, DataValueRef::Unsigned16),
+			4 => map!(input, be_u32, DataValueRef::Unsigned32),
+			8 => map!(input, be_u64, DataValueRef::Unsigned64),
 			_ => panic!(),
 		},
 		Signed8 | Signed16 | Signed32 | Signed64 => match length {
-			1 => map!(input, be_i8, DataValue::Signed8),
-			2 => map!(input, be_i16, DataValue::Signed16),
-			4 => map!(input, be_i32, DataValue::Signed32),
-			8 => map!(input, be_i64, DataValue::Signed64),
+			1 => map!(input, be_i8, DataValueRef::Signed8),
+			2 => map!(input, be_i16, DataValueRef::Signed16),
+			4 => map!(input, be_i32, DataValueRef::Signed32),
+			8 => map!(input, be_i64, DataValueRef::Signed64),
 			_ => panic!(),
 		},
 		Float32 | Float64 => match length {
-			4 => map!(input, be_f32, DataValue::Float32),
-			8 => map!(input, be_f64, DataValue::Float64),
+			4 => map!(input, be_f32, DataValueRef::Float32),
+			8 => map!(input, be_f64, DataValueRef::Float64),
 			_ => panic!(),
 		},
 		Boolean => match length {
 			1 => match be_u8(input) {
-				Ok((rest, 1u8)) => Ok((rest, DataValue::Boolean(true))),
-				Ok((rest, 2u8)) => Ok((rest, DataValue::Boolean(false))),
+				Ok((rest, 1u8)) => Ok((rest, DataValueRef::Boolean(true))),
+				Ok((rest, 2u8)) => Ok((rest, DataValueRef::Boolean(false))),
+				Ok((rest, u)) if options.raw_booleans => {
+					report.raw_booleans += 1;
+					Ok((rest, DataValueRef::Unsigned8(u)))
+				}
 				Ok(_) => Err(Err::Error(error_position!(input, error_kind::BOOL_INVALID))),
 				Err(e) => Err(e),
 			},
 			_ => panic!(),
 		},
 		MacAddress => match length {
-			6 => map!(input, take!(6), |slice| DataValue::MacAddress(
-				slice.to_vec()
-			)),
+			6 => map!(input, take!(6), DataValueRef::MacAddress),
 			_ => panic!(),
 		},
 		OctetArray => match length {
 			0xffffu16 => information_element_variable_length_parser(input),
 			_ => take!(input, length),
-		}.map(|(input, slice)| (input, DataValue::OctetArray(slice.to_vec()))),
+		}.map(|(input, slice)| (input, DataValueRef::OctetArray(slice))),
 		String => {
 			// try cast to utf8
 			let string_result =
 				match length {
 					0xffffu16 => information_element_variable_length_parser(input),
 					_ => take!(input, length),
-				}.map(|(input, slice)| (input, std::string::String::from_utf8(slice.to_vec())));
+				}.map(|(input, slice)| (input, std::str::from_utf8(slice).map_err(|_| slice)));
 			match string_result {
-				Ok((input, Ok(s))) => Ok((input, DataValue::String(s))),
+				Ok((input, Ok(s))) => Ok((input, DataValueRef::String(Cow::Borrowed(s)))),
+				Ok((input, Err(slice))) if options.lossy_strings => {
+					report.lossy_strings += 1;
+					Ok((input, DataValueRef::String(std::string::String::from_utf8_lossy(slice))))
+				}
 				// cast fail is semantic error
 				Ok((input, Err(_))) => Err(Err::Error(error_position!(
 					input,
@@ -214,16 +295,16 @@ fn information_element_parser(
 			}
 		}
 		DateTimeSeconds => match length {
-			4 => map!(input, be_u32, DataValue::DateTimeSeconds),
+			4 => map!(input, be_u32, DataValueRef::DateTimeSeconds),
 			_ => panic!(),
 		},
 		DateTimeMilliseconds => match length {
-			8 => map!(input, be_u64, DataValue::DateTimeMilliseconds),
+			8 => map!(input, be_u64, DataValueRef::DateTimeMilliseconds),
 			_ => panic!(),
 		},
 		DateTimeMicroseconds => match length {
 			8 => map!(input, tuple!(be_u32, be_u32), |(seconds, fraction)| {
-				DataValue::DateTimeMicroseconds {
+				DataValueRef::DateTimeMicroseconds {
 					seconds,
 					fraction : fraction & 0xFFFF_F800,
 				} // ignore lower 11 Bit of fraction
@@ -232,12 +313,12 @@ fn information_element_parser(
 		},
 		DateTimeNanoseconds => match length {
 			8 => map!(input, tuple!(be_u32, be_u32), |(seconds, fraction)| {
-				DataValue::DateTimeNanoseconds { seconds, fraction }
+				DataValueRef::DateTimeNanoseconds { seconds, fraction }
 			}),
 			_ => panic!(),
 		},
 		Ipv4Address => match length {
-			4 => map!(input, be_u32, |u| DataValue::Ipv4Address(Ipv4Addr::from(u))),
+			4 => map!(input, be_u32, |u| DataValueRef::Ipv4Address(Ipv4Addr::from(u))),
 			_ => panic!(),
 		},
 		Ipv6Address => match length {
@@ -253,7 +334,7 @@ fn information_element_parser(
 					be_u16,
 					be_u16
 				),
-				|(u0, u1, u2, u3, u4, u5, u6, u7)| DataValue::Ipv6Address(Ipv6Addr::new(
+				|(u0, u1, u2, u3, u4, u5, u6, u7)| DataValueRef::Ipv6Address(Ipv6Addr::new(
 					u0,
 					u1,
 					u2,
@@ -272,7 +353,7 @@ fn information_element_parser(
 	}
 }
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named!(
 	information_element_variable_length_parser<&[u8]>,
 	alt!(
@@ -281,7 +362,7 @@ named!(
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named_args!(
 	template_record_parser(is_options_template : bool)<TemplateRecord>,
 	do_parse!(
@@ -296,7 +377,7 @@ named_args!(
 	)
 );
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 named_args!(
 	template_record_header_parser(is_options_template : bool)<TemplateRecordHeader>,
 	do_parse!(
@@ -316,6 +397,16 @@ named_args!(
 #[cfg(test)]
 mod tests {
 	use super::*;
+
+	fn parse<'a>(
+		data : &'a [u8],
+		length : u16,
+		template : &TemplateRecord,
+		options : &DecoderOptions,
+	) -> IResult<&'a [u8], Vec<DataRecord>> {
+		let mut report = DecodeReport::default();
+		data_records_parser(data, length, template, &Registry::default(), options, &mut report)
+	}
 	use nom;
 
 	#[test]
@@ -488,7 +579,7 @@ mod tests {
 			},
 		];
 		assert_eq!(
-			template_records_parser(&data, set_header),
+			template_records_parser(data, set_header),
 			Ok((&[][..], res))
 		);
 
@@ -536,7 +627,7 @@ mod tests {
 			},
 		];
 		assert_eq!(
-			template_records_parser(&data, set_header),
+			template_records_parser(data, set_header),
 			Ok((&[][..], res))
 		);
 	}
@@ -560,7 +651,7 @@ mod tests {
 		};
 		let data : &[u8] = &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77];
 		assert_eq!(
-			data_records_parser(data, 8, &template),
+			parse(data, 8, &template, &DecoderOptions::strict()),
 			Ok((
 				&[][..],
 				vec![
@@ -575,11 +666,154 @@ mod tests {
 		);
 
 		assert_eq!(
-			data_records_parser(&[][..], 4, &template),
+			parse(&[][..], 4, &template, &DecoderOptions::strict()),
 			Err(Err::Incomplete(Needed::Size(4)))
 		);
 	}
 
+	#[test]
+	fn unknown_fields_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 0,
+				field_count : 3,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 18,
+					field_length : 2,
+					enterprise_number : Some(6871),
+				},
+				FieldSpecifier {
+					information_element_id : 18,
+					field_length : 0xffff,
+					enterprise_number : Some(6871),
+				},
+				FieldSpecifier {
+					information_element_id : 4, // protocolIdentifier
+					field_length : 1,
+					enterprise_number : None,
+				},
+			],
+		};
+		let data : &[u8] = &[0x00, 0x01, 2, 0xaa, 0xbb, 6];
+		assert!(parse(data, 6, &template, &DecoderOptions::strict()).is_err());
+
+		let options = DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			..DecoderOptions::strict()
+		};
+		let mut report = DecodeReport::default();
+		assert_eq!(
+			data_records_parser(data, 6, &template, &Registry::default(), &options, &mut report),
+			Ok((
+				&[][..],
+				vec![DataRecord {
+					fields : vec![
+						DataValue::OctetArray(vec![0x00, 0x01]),
+						DataValue::OctetArray(vec![0xaa, 0xbb]),
+						DataValue::Unsigned8(6),
+					],
+				}]
+			)),
+		);
+		assert_eq!(report.unknown_fields, 2);
+	}
+
+	#[test]
+	fn lenient_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 0,
+				field_count : 3,
+			},
+			scope_fields : vec![],
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 82, // interfaceName
+					field_length : 2,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 276, // dataRecordsReliability
+					field_length : 1,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 8, // sourceIPv4Address
+					field_length : 3,
+					enterprise_number : None,
+				},
+			],
+		};
+		let data : &[u8] = &[0xff, 0x30, 3, 10, 0, 0, 0x00, 0x00];
+		assert!(parse(data, 8, &template, &DecoderOptions::strict()).is_err());
+
+		let mut report = DecodeReport::default();
+		let registry = Registry::default();
+		let options = DecoderOptions::lenient();
+		assert_eq!(
+			data_records_parser(data, 8, &template, &registry, &options, &mut report),
+			Ok((
+				&[][..],
+				vec![DataRecord {
+					fields : vec![
+						DataValue::String("\u{fffd}0".to_string()),
+						DataValue::Unsigned8(3),
+						DataValue::OctetArray(vec![10, 0, 0]),
+					],
+				}]
+			)),
+		);
+		assert_eq!(
+			report,
+			DecodeReport {
+				unknown_fields : 0,
+				raw_field_lengths : 1,
+				raw_booleans : 1,
+				lossy_strings : 1,
+				trailing_octets : 2,
+			}
+		);
+	}
+
+	#[test]
+	fn options_data_records_parser_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				scope_field_count : 1,
+				field_count : 2,
+			},
+			scope_fields : vec![FieldSpecifier {
+				information_element_id : 149, // observationDomainId
+				field_length : 4,
+				enterprise_number : None,
+			}],
+			fields : vec![FieldSpecifier {
+				information_element_id : 160, // systemInitTimeMilliseconds
+				field_length : 8,
+				enterprise_number : None,
+			}],
+		};
+		let data : &[u8] = &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0x03, 0xe8];
+		assert_eq!(
+			parse(data, 12, &template, &DecoderOptions::strict()),
+			Ok((
+				&[][..],
+				vec![DataRecord {
+					fields : vec![
+						DataValue::Unsigned32(1),
+						DataValue::DateTimeMilliseconds(1000),
+					],
+				}]
+			)),
+		);
+	}
+
 	#[test]
 	fn unsigned_integer_parser_test() {
 		use DataValue::*;
@@ -596,7 +830,7 @@ mod tests {
 		].iter()
 		{
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[1..8], res.clone()))
 			);
 		}
@@ -610,7 +844,7 @@ mod tests {
 		].iter()
 		{
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[2..8], res.clone()))
 			);
 		}
@@ -619,7 +853,7 @@ mod tests {
 		let field_length = 4;
 		for type_ in [AbstractDataType::Unsigned32, AbstractDataType::Unsigned64].iter() {
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[4..8], res.clone()))
 			);
 		}
@@ -627,7 +861,7 @@ mod tests {
 		let res = Unsigned64(0x8899aabbccddeeff);
 		let field_length = 8;
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Unsigned64, field_length),
+			information_element_parser(data, AbstractDataType::Unsigned64, field_length),
 			Ok((&[][..], res))
 		);
 	}
@@ -636,7 +870,7 @@ mod tests {
 	#[should_panic(expected = "explicit panic")]
 	fn unsigned_integer_parser_fail() {
 		let data : &[u8] = &[0x01, 0x02, 0x3, 0x04];
-		let _res = information_element_parser(&data, AbstractDataType::Unsigned32, 3);
+		let _res = information_element_parser(data, AbstractDataType::Unsigned32, 3);
 	}
 
 	#[test]
@@ -655,7 +889,7 @@ mod tests {
 		].iter()
 		{
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[1..8], res.clone()))
 			);
 		}
@@ -669,7 +903,7 @@ mod tests {
 		].iter()
 		{
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[2..8], res.clone()))
 			);
 		}
@@ -678,7 +912,7 @@ mod tests {
 		let field_length = 4;
 		for type_ in [AbstractDataType::Signed32, AbstractDataType::Signed64].iter() {
 			assert_eq!(
-				information_element_parser(&data, *type_, field_length),
+				information_element_parser(data, *type_, field_length),
 				Ok((&data[4..8], res.clone()))
 			);
 		}
@@ -686,7 +920,7 @@ mod tests {
 		let res = Signed64(0x778899aabbccddee);
 		let field_length = 8;
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Signed64, field_length),
+			information_element_parser(data, AbstractDataType::Signed64, field_length),
 			Ok((&[][..], res))
 		);
 	}
@@ -695,7 +929,7 @@ mod tests {
 	#[should_panic(expected = "explicit panic")]
 	fn signed_integer_parser_fail() {
 		let data : &[u8] = &[0x01, 0x02, 0x3, 0x04];
-		let _res = information_element_parser(&data, AbstractDataType::Signed32, 3);
+		let _res = information_element_parser(data, AbstractDataType::Signed32, 3);
 	}
 
 	#[test]
@@ -708,7 +942,7 @@ mod tests {
 	#[should_panic(expected = "explicit panic")]
 	fn float_parser_fail() {
 		let data : &[u8] = &[0x01, 0x02, 0x3, 0x04];
-		let _res = information_element_parser(&data, AbstractDataType::Float32, 3);
+		let _res = information_element_parser(data, AbstractDataType::Float32, 3);
 	}
 
 	#[test]
@@ -717,7 +951,7 @@ mod tests {
 
 		let data : &[u8] = &[0x00];
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Boolean, 1),
+			information_element_parser(data, AbstractDataType::Boolean, 1),
 			Err(Err::Error(nom::Context::Code(
 				data,
 				error_kind::BOOL_INVALID
@@ -727,20 +961,20 @@ mod tests {
 		let data : &[u8] = &[0x01];
 		let res = Boolean(true);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Boolean, 1),
+			information_element_parser(data, AbstractDataType::Boolean, 1),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[0x02];
 		let res = Boolean(false);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Boolean, 1),
+			information_element_parser(data, AbstractDataType::Boolean, 1),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[0x03];
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Boolean, 1),
+			information_element_parser(data, AbstractDataType::Boolean, 1),
 			Err(Err::Error(nom::Context::Code(
 				data,
 				error_kind::BOOL_INVALID
@@ -749,7 +983,7 @@ mod tests {
 
 		let data : &[u8] = &[];
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Boolean, 1),
+			information_element_parser(data, AbstractDataType::Boolean, 1),
 			Err(Err::Incomplete(Needed::Size(1)))
 		);
 	}
@@ -758,7 +992,7 @@ mod tests {
 	#[should_panic(expected = "explicit panic")]
 	fn bool_parser_fail() {
 		let data : &[u8] = &[0x01];
-		let _res = information_element_parser(&data, AbstractDataType::Boolean, 0);
+		let _res = information_element_parser(data, AbstractDataType::Boolean, 0);
 	}
 
 	#[test]
@@ -767,7 +1001,7 @@ mod tests {
 
 		let data : &[u8] = &[0x01, 0x02, 0x3, 0x04, 0x05, 0x06];
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::MacAddress, 6),
+			information_element_parser(data, AbstractDataType::MacAddress, 6),
 			Ok((&[][..], MacAddress(data.to_vec())))
 		);
 	}
@@ -776,7 +1010,7 @@ mod tests {
 	#[should_panic(expected = "explicit panic")]
 	fn mac_address_parser_fail() {
 		let data : &[u8] = &[0x01, 0x02, 0x3, 0x04, 0x05, 0x06];
-		let _res = information_element_parser(&data, AbstractDataType::MacAddress, 4);
+		let _res = information_element_parser(data, AbstractDataType::MacAddress, 4);
 	}
 
 	#[test]
@@ -787,7 +1021,7 @@ mod tests {
 		let res = OctetArray(vec![0x00, 0x00, 0x00, 0x00]);
 		let field_length = 4;
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, field_length),
+			information_element_parser(data, AbstractDataType::OctetArray, field_length),
 			Ok((&[][..], res))
 		);
 
@@ -796,28 +1030,28 @@ mod tests {
 		let data : &[u8] = &[0x00];
 		let res = OctetArray(vec![]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[0x04, 0x00, 0x00, 0x00, 0x00];
 		let res = OctetArray(vec![0x00, 0x00, 0x00, 0x00]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[0xff, 0x00, 0x00];
 		let res = OctetArray(vec![]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[0xff, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00];
 		let res = OctetArray(vec![0x00, 0x00, 0x00, 0x00]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 
@@ -826,7 +1060,7 @@ mod tests {
 		let data : &[u8] = &vector[..];
 		let res = OctetArray(vec![0x00; 1025]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 
@@ -835,7 +1069,7 @@ mod tests {
 		let data : &[u8] = &vector[..];
 		let res = OctetArray(vec![0x00; 0xffff]);
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::OctetArray, variable_length),
+			information_element_parser(data, AbstractDataType::OctetArray, variable_length),
 			Ok((&[][..], res))
 		);
 	}
@@ -845,20 +1079,20 @@ mod tests {
 		let data : &[u8] = &[240, 159, 146, 150];
 		let res = DataValue::String("💖".to_string());
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::String, 4),
+			information_element_parser(data, AbstractDataType::String, 4),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[4, 240, 159, 146, 150];
 		let res = DataValue::String("💖".to_string());
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::String, 0xffffu16),
+			information_element_parser(data, AbstractDataType::String, 0xffffu16),
 			Ok((&[][..], res))
 		);
 
 		let data : &[u8] = &[240, 0, 146, 151]; // modified
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::String, 4),
+			information_element_parser(data, AbstractDataType::String, 4),
 			Err(Err::Error(nom::Context::Code(
 				&[][..],
 				error_kind::STRING_NOT_UTF8
@@ -867,7 +1101,7 @@ mod tests {
 
 		let data : &[u8] = &[4, 240, 0, 146, 151]; // modified
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::String, 0xffffu16),
+			information_element_parser(data, AbstractDataType::String, 0xffffu16),
 			Err(Err::Error(nom::Context::Code(
 				&[][..],
 				error_kind::STRING_NOT_UTF8
@@ -880,7 +1114,7 @@ mod tests {
 		let data : &[u8] = &[0x00, 0x01, 0x02, 0x03];
 		let res = DataValue::Ipv4Address(Ipv4Addr::new(0x00, 0x01, 0x02, 0x03));
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Ipv4Address, 4),
+			information_element_parser(data, AbstractDataType::Ipv4Address, 4),
 			Ok((&[][..], res))
 		);
 
@@ -899,7 +1133,7 @@ mod tests {
 			0x0e0f,
 		));
 		assert_eq!(
-			information_element_parser(&data, AbstractDataType::Ipv6Address, 16),
+			information_element_parser(data, AbstractDataType::Ipv6Address, 16),
 			Ok((&[][..], res))
 		);
 	}
@@ -943,7 +1177,7 @@ mod tests {
 				},
 			],
 		};
-		assert_eq!(template_record_parser(&data, false), Ok((&[][..], res)));
+		assert_eq!(template_record_parser(data, false), Ok((&[][..], res)));
 	}
 
 	#[test]
@@ -957,7 +1191,7 @@ mod tests {
 			field_length : 2u16,
 			enterprise_number : None,
 		};
-		assert_eq!(field_specifier_parser(&data), Ok((&[][..], res)));
+		assert_eq!(field_specifier_parser(data), Ok((&[][..], res)));
 
 		let data : &[u8] = &[
 			0x01, 0x37, // id
@@ -968,7 +1202,7 @@ mod tests {
 			field_length : 8u16,
 			enterprise_number : None,
 		};
-		assert_eq!(field_specifier_parser(&data), Ok((&[][..], res)));
+		assert_eq!(field_specifier_parser(data), Ok((&[][..], res)));
 
 		let data : &[u8] = &[
 			0x00, 0xd2, // id
@@ -979,7 +1213,7 @@ mod tests {
 			field_length : 4,
 			enterprise_number : None,
 		};
-		assert_eq!(field_specifier_parser(&data), Ok((&[][..], res)));
+		assert_eq!(field_specifier_parser(data), Ok((&[][..], res)));
 
 		let data : &[u8] = &[
 			0x81, 0x02, // id
@@ -991,6 +1225,6 @@ mod tests {
 			field_length : 4,
 			enterprise_number : Some(0xC33C),
 		};
-		assert_eq!(field_specifier_parser(&data), Ok((&[][..], res)));
+		assert_eq!(field_specifier_parser(data), Ok((&[][..], res)));
 	}
 }
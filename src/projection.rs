@@ -0,0 +1,334 @@
+use data_ref::*;
+use decode_plan::*;
+use decoder_options::*;
+use nom::{Err, IResult, Needed};
+use record::*;
+use registry::*;
+use structs::*;
+use template_management::*;
+
+/// Information elements to decode from every record, in the order of `ProjectedRecord::fields`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Projection {
+	elements : Vec<(Option<u32>, u16)>,
+}
+
+/// Decoding steps of a template for a `Projection`, other fields are skipped by their length
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectionPlan {
+	options : DecoderOptions,
+	steps : Vec<Step>,
+	width : usize,
+	// sum of the field lengths if no field has variable length
+	record_length : Option<usize>,
+	// relaxations of projected fields decided at compile time, counted once per record
+	report : DecodeReport,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+	// adjacent fixed length fields are skipped at once
+	Skip(usize),
+	SkipVariableLength,
+	// positions in the projected record, more than 1 if an element is requested twice
+	Decode(FieldDecoder, Vec<usize>),
+}
+
+/// Values of the projected information elements, `None` if the template has no such field
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectedRecord<'a> {
+	pub fields : Vec<Option<DataValueRef<'a>>>,
+}
+
+impl Projection {
+	/// Enterprise numbers and ids, `None` for IANA information elements
+	pub fn new(elements : Vec<(Option<u32>, u16)>) -> Projection {
+		Projection { elements }
+	}
+
+	pub fn from_names<S : AsRef<str>>(
+		registry : &Registry,
+		names : &[S],
+	) -> Result<Projection, RecordError> {
+		let elements = names
+			.iter()
+			.map(|name| resolve_name(registry, name.as_ref()))
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok(Projection { elements })
+	}
+
+	pub fn elements(&self) -> &[(Option<u32>, u16)] {
+		&self.elements
+	}
+
+	/// Like `DecodePlan::compile`, but only projected fields need to be decodable.
+	/// The first field of a projected information element is decoded.
+	pub fn compile(
+		&self,
+		template : &TemplateRecord,
+		registry : &Registry,
+		options : &DecoderOptions,
+	) -> Result<ProjectionPlan, VerifyTemplateError> {
+		// skipped fields only need a length
+		let skip_options = DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			raw_field_lengths : true,
+			..*options
+		};
+		verify_template_with(template, registry, &skip_options)?;
+
+		let mut report = DecodeReport::default();
+		let mut steps = Vec::new();
+		let mut found = vec![false; self.elements.len()];
+		let mut record_length = Some(0);
+		for field in template.scope_fields.iter().chain(template.fields.iter()) {
+			let key = (field.enterprise_number, field.information_element_id);
+			let slots = (0..self.elements.len())
+				.filter(|&i| !found[i] && self.elements[i] == key)
+				.collect::<Vec<_>>();
+
+			if slots.is_empty() {
+				record_length = match field.field_length {
+					VARIABLE_LENGTH => {
+						steps.push(Step::SkipVariableLength);
+						None
+					}
+					length => {
+						push_skip(&mut steps, length as usize);
+						record_length.map(|record_length| record_length + length as usize)
+					}
+				};
+				continue;
+			}
+
+			verify_field_specifier(field, registry, options)?;
+			let decoder = FieldDecoder::compile(field, registry, &mut report);
+			record_length = match (record_length, decoder.length()) {
+				(Some(record_length), Some(length)) => Some(record_length + length),
+				_ => None,
+			};
+			for &slot in &slots {
+				found[slot] = true;
+			}
+			steps.push(Step::Decode(decoder, slots));
+		}
+
+		Ok(ProjectionPlan {
+			options : *options,
+			steps,
+			width : self.elements.len(),
+			record_length,
+			report,
+		})
+	}
+}
+
+// merges with a preceding fixed length skip
+fn push_skip(steps : &mut Vec<Step>, length : usize) {
+	if let Some(&mut Step::Skip(ref mut skipped)) = steps.last_mut() {
+		*skipped += length;
+		return;
+	}
+	steps.push(Step::Skip(length));
+}
+
+impl ProjectionPlan {
+	pub fn options(&self) -> &DecoderOptions {
+		&self.options
+	}
+
+	/// Length of every record of templates without variable length fields
+	pub fn record_length(&self) -> Option<usize> {
+		self.record_length
+	}
+
+	/// Projected data records of a set body, with the same relaxations as `DecodePlan::decode`
+	pub fn decode<'a>(
+		&self,
+		input : &'a [u8],
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], Vec<ProjectedRecord<'a>>> {
+		let mut records = match self.record_length {
+			Some(record_length) if record_length > 0 => {
+				Vec::with_capacity(input.len() / record_length)
+			}
+			_ => Vec::new(),
+		};
+
+		// at least 1 data record
+		let mut input = input;
+		loop {
+			// relaxations of incomplete records are not reported
+			let mut record_report = self.report;
+			match self.decode_record(input, &mut record_report) {
+				Ok((rest, record)) => {
+					input = rest;
+					records.push(record);
+					report.add(&record_report);
+				}
+				Err(_) if self.options.skip_trailing_octets && !records.is_empty() => {
+					report.trailing_octets += input.len() as u64;
+					input = &input[input.len()..];
+					break;
+				}
+				Err(e) => return Err(e),
+			}
+
+			if input.is_empty() {
+				break;
+			}
+		}
+		Ok((input, records))
+	}
+
+	fn decode_record<'a>(
+		&self,
+		input : &'a [u8],
+		report : &mut DecodeReport,
+	) -> IResult<&'a [u8], ProjectedRecord<'a>> {
+		if let Some(record_length) = self.record_length {
+			if input.len() < record_length {
+				return Err(Err::Incomplete(Needed::Size(record_length)));
+			}
+		}
+
+		let mut input = input;
+		let mut fields = vec![None; self.width];
+		for step in &self.steps {
+			input = match *step {
+				Step::Skip(length) => take(input, length)?.0,
+				Step::SkipVariableLength => take_variable_length(input)?.0,
+				Step::Decode(decoder, ref slots) => {
+					let (rest, value) = decoder.decode(input, &self.options, report)?;
+					for &slot in &slots[1..] {
+						fields[slot] = Some(value.clone());
+					}
+					fields[slots[0]] = Some(value);
+					rest
+				}
+			};
+		}
+		Ok((input, ProjectedRecord { fields }))
+	}
+}
+
+impl<'a> ProjectedRecord<'a> {
+	pub fn get(&self, index : usize) -> Option<&DataValueRef<'a>> {
+		self.fields.get(index).and_then(Option::as_ref)
+	}
+
+	pub fn into_owned(self) -> Vec<Option<DataValue>> {
+		self.fields
+			.into_iter()
+			.map(|field| field.map(DataValueRef::into_owned))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::borrow::Cow;
+	use std::net::Ipv4Addr;
+	use test_util::*;
+
+	#[test]
+	fn projection_test() {
+		let registry = Registry::default();
+		// octetDeltaCount, interfaceName, unknown, sourceIPv4Address, protocolIdentifier
+		let template = template(&[(1, 8), (82, 0xffff), (32767, 3), (8, 4), (4, 1)]);
+		let projection = Projection::from_names(
+			&registry,
+			&["sourceIPv4Address", "protocolIdentifier", "destinationIPv4Address"],
+		)
+		.unwrap();
+		assert_eq!(projection.elements(), &[(None, 8), (None, 4), (None, 12)]);
+
+		// unknown fields are skipped without relaxations
+		let plan = projection.compile(&template, &registry, &DecoderOptions::strict()).unwrap();
+		assert_eq!(
+			plan.steps,
+			vec![
+				Step::Skip(8),
+				Step::SkipVariableLength,
+				Step::Skip(3),
+				Step::Decode(FieldDecoder::Ipv4Address, vec![0]),
+				Step::Decode(FieldDecoder::Unsigned8, vec![1]),
+			]
+		);
+		assert_eq!(plan.record_length(), None);
+
+		let data : &[u8] = &[
+			0, 0, 0, 0, 0, 0, 0, 1, 4, b'e', b't', b'h', b'0', 0, 0, 0, 192, 0, 2, 1, 6, //
+			0, 0, 0, 0, 0, 0, 0, 2, 0xff, 0, 0, 0, 0, 0, 192, 0, 2, 2, 17,
+		];
+		let mut report = DecodeReport::default();
+		let (rest, records) = plan.decode(data, &mut report).unwrap();
+		assert!(rest.is_empty());
+		assert!(report.is_empty());
+		assert_eq!(
+			records,
+			vec![
+				ProjectedRecord {
+					fields : vec![
+						Some(DataValueRef::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1))),
+						Some(DataValueRef::Unsigned8(6)),
+						None,
+					],
+				},
+				ProjectedRecord {
+					fields : vec![
+						Some(DataValueRef::Ipv4Address(Ipv4Addr::new(192, 0, 2, 2))),
+						Some(DataValueRef::Unsigned8(17)),
+						None,
+					],
+				},
+			]
+		);
+		assert_eq!(records[1].get(1), Some(&DataValueRef::Unsigned8(17)));
+		assert_eq!(records[1].clone().into_owned()[1], Some(DataValue::Unsigned8(17)));
+
+		assert_eq!(
+			Projection::from_names(&registry, &["noSuchElement"]),
+			Err(RecordError::UnknownName("noSuchElement".to_string()))
+		);
+	}
+
+	#[test]
+	fn fixed_length_test() {
+		let registry = Registry::default();
+		// interfaceName of fixed length, octetDeltaCount, sourceIPv4Address
+		let template = template(&[(82, 4), (1, 8), (8, 4)]);
+		let projection = Projection::new(vec![(None, 82), (None, 8), (None, 82)]);
+		let plan = projection.compile(&template, &registry, &DecoderOptions::strict()).unwrap();
+		assert_eq!(plan.record_length(), Some(16));
+
+		let data : &[u8] = &[b'e', b't', b'h', b'0', 0, 0, 0, 0, 0, 0, 0, 1, 192, 0, 2, 1, 0xff];
+		assert!(plan.decode(data, &mut DecodeReport::default()).is_err());
+
+		let lenient = DecoderOptions::lenient();
+		let plan = projection.compile(&template, &registry, &lenient).unwrap();
+		let mut report = DecodeReport::default();
+		let (_, records) = plan.decode(data, &mut report).unwrap();
+		assert_eq!(report.trailing_octets, 1);
+		assert_eq!(
+			records[0].fields,
+			vec![
+				Some(DataValueRef::String(Cow::Borrowed("eth0"))),
+				Some(DataValueRef::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1))),
+				Some(DataValueRef::String(Cow::Borrowed("eth0"))),
+			]
+		);
+
+		// projected fields still need to be decodable
+		let template = self::template(&[(32767, 4), (8, 4)]);
+		assert!(Projection::new(vec![(None, 8)])
+			.compile(&template, &registry, &DecoderOptions::strict())
+			.is_ok());
+		assert_eq!(
+			Projection::new(vec![(None, 32767)])
+				.compile(&template, &registry, &DecoderOptions::strict()),
+			Err(VerifyTemplateError::InformationElementIdNotFound(32767))
+		);
+	}
+}
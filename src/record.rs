@@ -0,0 +1,313 @@
+use decoder_options::*;
+use information_element::InformationElement;
+use registry::*;
+use std;
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::SystemTime;
+use structs::*;
+use template_management::*;
+
+/// Struct with one field per information element,
+/// usually implemented with `#[derive(IpfixRecord)]` from the `derive` feature:
+///
+/// ```ignore
+/// #[derive(IpfixRecord)]
+/// struct Flow {
+///     #[ipfix(ie = 8)]
+///     src : Ipv4Addr,
+///     #[ipfix(name = "octetDeltaCount")]
+///     bytes : u64,
+///     #[ipfix(ie = 18, enterprise = 6871)]
+///     flags : Option<u8>,
+/// }
+/// ```
+///
+/// `Option` fields may be missing from decoded records but must be set for encoding.
+/// Fields of information elements known to the registry must fit their data type.
+pub trait IpfixRecord : Sized {
+	fn field_specifiers(registry : &Registry) -> Result<Vec<FieldSpecifier>, RecordError>;
+
+	fn from_record(record : &TypedDataRecord, registry : &Registry) -> Result<Self, RecordError>;
+
+	/// Values in the order of `field_specifiers`
+	fn to_record(&self, registry : &Registry) -> Result<DataRecord, RecordError>;
+
+	/// Template verified like a collector keeping unknown fields raw would
+	fn template(template_id : u16, registry : &Registry) -> Result<TemplateRecord, RecordError> {
+		let fields = Self::field_specifiers(registry)?;
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id,
+				field_count : fields.len() as u16,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields,
+		};
+		let options = DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			..DecoderOptions::strict()
+		};
+		verify_template_with(&template, registry, &options).map_err(RecordError::Template)?;
+		Ok(template)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordError {
+	UnknownName(String),
+	/// struct field without value
+	Missing(&'static str),
+	/// struct field of a different type than the value
+	Type {
+		field : &'static str,
+		value : DataValue,
+	},
+	/// struct field of a type that can't encode the information element
+	FieldType {
+		field : &'static str,
+		abstract_data_type : AbstractDataType,
+	},
+	Template(VerifyTemplateError),
+}
+
+impl std::fmt::Display for RecordError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use RecordError::*;
+
+		match *self {
+			UnknownName(ref name) => write!(f, "information element {} not found", name),
+			Missing(field) => write!(f, "field {} has no value", field),
+			Type { field, ref value } => write!(f, "field {} can't hold {:?}", field, value),
+			FieldType {
+				field,
+				abstract_data_type,
+			} => write!(f, "field {} can't hold {}", field, abstract_data_type),
+			Template(ref e) => write!(f, "invalid template: {}", e),
+		}
+	}
+}
+
+pub trait FromDataValue : Sized {
+	fn from_data_value(value : &DataValue) -> Option<Self>;
+
+	/// Value of a struct field whose information element is not in the record
+	fn from_missing() -> Option<Self> {
+		None
+	}
+}
+
+pub trait ToDataValue {
+	/// Field length in templates, 0xffff for variable length
+	fn field_length() -> u16;
+
+	fn to_data_value(&self) -> Option<DataValue>;
+
+	/// Whether values can be encoded as the type of an information element, true by default
+	fn encodes(_abstract_data_type : AbstractDataType) -> bool {
+		true
+	}
+
+	/// Field length for an information element known to the registry
+	fn field_length_for(_information_element : &InformationElement) -> u16 {
+		Self::field_length()
+	}
+
+	/// Value for an information element known to the registry
+	fn to_data_value_for(&self, _information_element : &InformationElement) -> Option<DataValue> {
+		self.to_data_value()
+	}
+}
+
+macro_rules! from_data_value {
+	($($type:ty),*) => {$(
+		impl FromDataValue for $type {
+			fn from_data_value(value : &DataValue) -> Option<$type> {
+				<$type>::try_from(value).ok()
+			}
+		}
+	)*};
+}
+
+from_data_value!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, bool);
+from_data_value!(String, Vec<u8>, Ipv4Addr, Ipv6Addr, SystemTime);
+
+// encoded as the listed types, wider ones with reduced-size encoding
+macro_rules! to_data_value {
+	($type:ty, $variant:ident, $length:expr, $($encodes:ident)|+) => {
+		impl ToDataValue for $type {
+			fn field_length() -> u16 {
+				$length
+			}
+
+			fn to_data_value(&self) -> Option<DataValue> {
+				Some(DataValue::$variant(self.clone()))
+			}
+
+			fn encodes(abstract_data_type : AbstractDataType) -> bool {
+				matches!(abstract_data_type, $(AbstractDataType::$encodes)|+)
+			}
+		}
+	};
+}
+
+to_data_value!(u8, Unsigned8, 1, Unsigned8 | Unsigned16 | Unsigned32 | Unsigned64);
+to_data_value!(u16, Unsigned16, 2, Unsigned16 | Unsigned32 | Unsigned64);
+to_data_value!(u32, Unsigned32, 4, Unsigned32 | Unsigned64);
+to_data_value!(u64, Unsigned64, 8, Unsigned64);
+to_data_value!(i8, Signed8, 1, Signed8 | Signed16 | Signed32 | Signed64);
+to_data_value!(i16, Signed16, 2, Signed16 | Signed32 | Signed64);
+to_data_value!(i32, Signed32, 4, Signed32 | Signed64);
+to_data_value!(i64, Signed64, 8, Signed64);
+to_data_value!(f32, Float32, 4, Float32 | Float64);
+to_data_value!(f64, Float64, 8, Float64);
+to_data_value!(bool, Boolean, 1, Boolean);
+to_data_value!(String, String, 0xffff, String);
+to_data_value!(Vec<u8>, OctetArray, 0xffff, OctetArray);
+to_data_value!(Ipv4Addr, Ipv4Address, 4, Ipv4Address);
+to_data_value!(Ipv6Addr, Ipv6Address, 16, Ipv6Address);
+
+/// Encoded as the `dateTime*` type of the information element,
+/// `dateTimeMilliseconds` for elements unknown to the registry
+impl ToDataValue for SystemTime {
+	fn field_length() -> u16 {
+		8
+	}
+
+	fn to_data_value(&self) -> Option<DataValue> {
+		DataValue::from_system_time(AbstractDataType::DateTimeMilliseconds, *self)
+	}
+
+	fn encodes(abstract_data_type : AbstractDataType) -> bool {
+		matches!(
+			abstract_data_type,
+			AbstractDataType::DateTimeSeconds
+				| AbstractDataType::DateTimeMilliseconds
+				| AbstractDataType::DateTimeMicroseconds
+				| AbstractDataType::DateTimeNanoseconds
+		)
+	}
+
+	fn field_length_for(information_element : &InformationElement) -> u16 {
+		information_element.abstract_data_type.default_length()
+	}
+
+	fn to_data_value_for(&self, information_element : &InformationElement) -> Option<DataValue> {
+		DataValue::from_system_time(information_element.abstract_data_type, *self)
+	}
+}
+
+impl<T : FromDataValue> FromDataValue for Option<T> {
+	fn from_data_value(value : &DataValue) -> Option<Option<T>> {
+		T::from_data_value(value).map(Some)
+	}
+
+	fn from_missing() -> Option<Option<T>> {
+		Some(None)
+	}
+}
+
+impl<T : ToDataValue> ToDataValue for Option<T> {
+	fn field_length() -> u16 {
+		T::field_length()
+	}
+
+	fn to_data_value(&self) -> Option<DataValue> {
+		self.as_ref().and_then(T::to_data_value)
+	}
+
+	fn encodes(abstract_data_type : AbstractDataType) -> bool {
+		T::encodes(abstract_data_type)
+	}
+
+	fn field_length_for(information_element : &InformationElement) -> u16 {
+		T::field_length_for(information_element)
+	}
+
+	fn to_data_value_for(&self, information_element : &InformationElement) -> Option<DataValue> {
+		self.as_ref()
+			.and_then(|value| value.to_data_value_for(information_element))
+	}
+}
+
+/// Enterprise number and id of an information element name, used by derived implementations
+pub fn resolve_name(registry : &Registry, name : &str) -> Result<(Option<u32>, u16), RecordError> {
+	registry
+		.lookup_by_name(name)
+		.map(|information_element| (information_element.enterprise_number, information_element.id))
+		.ok_or_else(|| RecordError::UnknownName(name.to_string()))
+}
+
+/// Value of the first field with this information element, used by derived implementations
+pub fn decode_field<T : FromDataValue>(
+	record : &TypedDataRecord,
+	(enterprise_number, id) : (Option<u32>, u16),
+	field : &'static str,
+) -> Result<T, RecordError> {
+	let value = record
+		.template
+		.scope_fields
+		.iter()
+		.chain(record.template.fields.iter())
+		.zip(record.data.fields.iter())
+		.find(|&(specifier, _)| {
+			specifier.information_element_id == id && specifier.enterprise_number == enterprise_number
+		})
+		.map(|(_, value)| value);
+	match value {
+		Some(value) => T::from_data_value(value).ok_or_else(|| RecordError::Type {
+			field,
+			value : value.clone(),
+		}),
+		None => T::from_missing().ok_or(RecordError::Missing(field)),
+	}
+}
+
+/// Field specifier of a struct field, used by derived implementations
+pub fn field_specifier<T : ToDataValue>(
+	registry : &Registry,
+	(enterprise_number, id) : (Option<u32>, u16),
+	field : &'static str,
+) -> Result<FieldSpecifier, RecordError> {
+	let field_length = match lookup(registry, enterprise_number, id) {
+		Some(information_element) if !T::encodes(information_element.abstract_data_type) => {
+			return Err(RecordError::FieldType {
+				field,
+				abstract_data_type : information_element.abstract_data_type,
+			})
+		}
+		Some(information_element) => T::field_length_for(information_element),
+		None => T::field_length(),
+	};
+	Ok(FieldSpecifier {
+		information_element_id : id,
+		field_length,
+		enterprise_number,
+	})
+}
+
+/// Value of a struct field, used by derived implementations
+pub fn encode_field<T : ToDataValue>(
+	value : &T,
+	registry : &Registry,
+	(enterprise_number, id) : (Option<u32>, u16),
+	field : &'static str,
+) -> Result<DataValue, RecordError> {
+	let value = match lookup(registry, enterprise_number, id) {
+		Some(information_element) => value.to_data_value_for(information_element),
+		None => value.to_data_value(),
+	};
+	value.ok_or(RecordError::Missing(field))
+}
+
+fn lookup(
+	registry : &Registry,
+	enterprise_number : Option<u32>,
+	id : u16,
+) -> Option<&InformationElement> {
+	match enterprise_number {
+		Some(enterprise_number) => registry.lookup_enterprise(enterprise_number, id),
+		None => registry.lookup(id),
+	}
+}
@@ -0,0 +1,337 @@
+use iana_xml;
+use information_element::*;
+use std;
+use std::borrow::Cow;
+use structs::*;
+
+/// Information elements used for decoding.
+/// The default registry contains the IANA information elements known at build time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registry {
+	// sorted by enterprise number and id, IANA elements first
+	elements : Cow<'static, [InformationElement]>,
+}
+
+impl Default for Registry {
+	fn default() -> Registry {
+		Registry {
+			elements : Cow::Borrowed(INFORMATION_ELEMENTS),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+	Io(std::io::Error),
+	Xml(String),
+	UnknownValue {
+		id : u16,
+		field : &'static str,
+		value : String,
+	},
+}
+
+impl std::fmt::Display for RegistryError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use RegistryError::*;
+
+		match *self {
+			Io(ref e) => write!(f, "registry not readable: {}", e),
+			Xml(ref e) => write!(f, "registry not parseable: {}", e),
+			UnknownValue {
+				id,
+				field,
+				ref value,
+			} => write!(
+				f,
+				"information element {} has unknown {} {}",
+				id, field, value
+			),
+		}
+	}
+}
+
+impl From<std::io::Error> for RegistryError {
+	fn from(e : std::io::Error) -> RegistryError {
+		RegistryError::Io(e)
+	}
+}
+
+impl Registry {
+	/// Registry containing only the information elements of an IANA registry XML file
+	pub fn from_xml(xml : &str) -> Result<Registry, RegistryError> {
+		let mut registry = Registry {
+			elements : Cow::Owned(Vec::new()),
+		};
+		for record in iana_xml::parse(xml).map_err(RegistryError::Xml)? {
+			registry.insert(information_element_from_record(record)?);
+		}
+		Ok(registry)
+	}
+
+	pub fn load<P : AsRef<std::path::Path>>(path : P) -> Result<Registry, RegistryError> {
+		Registry::from_xml(&std::fs::read_to_string(path)?)
+	}
+
+	pub fn lookup(&self, id : u16) -> Option<&InformationElement> {
+		self.find(None, id)
+	}
+
+	pub fn lookup_enterprise(
+		&self,
+		enterprise_number : u32,
+		id : u16,
+	) -> Option<&InformationElement> {
+		self.find(Some(enterprise_number), id)
+	}
+
+	pub fn lookup_field(&self, field : &FieldSpecifier) -> Option<&InformationElement> {
+		self.find(field.enterprise_number, field.information_element_id)
+	}
+
+	/// IANA names are case-sensitive.
+	/// If enterprise elements share a name, the IANA element or the lowest enterprise number wins.
+	pub fn lookup_by_name(&self, name : &str) -> Option<&InformationElement> {
+		self.elements.iter().find(|e| e.name == name)
+	}
+
+	pub fn lookup_by_name_ignore_case(&self, name : &str) -> Option<&InformationElement> {
+		self.elements
+			.iter()
+			.find(|e| e.name.eq_ignore_ascii_case(name))
+	}
+
+	fn find(&self, enterprise_number : Option<u32>, id : u16) -> Option<&InformationElement> {
+		self.elements
+			.binary_search_by_key(&(enterprise_number, id), key)
+			.ok()
+			.map(|index| &self.elements[index])
+	}
+
+	/// Adds or replaces an information element, returns the replaced one
+	pub fn insert(
+		&mut self,
+		information_element : InformationElement,
+	) -> Option<InformationElement> {
+		let elements = self.elements.to_mut();
+		match elements.binary_search_by_key(&key(&information_element), key) {
+			Ok(index) => Some(std::mem::replace(
+				&mut elements[index],
+				information_element,
+			)),
+			Err(index) => {
+				elements.insert(index, information_element);
+				None
+			}
+		}
+	}
+
+	pub fn elements(&self) -> &[InformationElement] {
+		&self.elements
+	}
+}
+
+fn key(information_element : &InformationElement) -> (Option<u32>, u16) {
+	(information_element.enterprise_number, information_element.id)
+}
+
+fn information_element_from_record(
+	record : iana_xml::IanaRecord,
+) -> Result<InformationElement, RegistryError> {
+	use information_element::DataTypeSemantics::*;
+	use information_element::Units::*;
+	use structs::AbstractDataType::*;
+
+	let id = record.id;
+	let unknown = |field, value : &str| RegistryError::UnknownValue {
+		id,
+		field,
+		value : value.to_string(),
+	};
+
+	let abstract_data_type = match &record.data_type[..] {
+		"unsigned8" => Unsigned8,
+		"unsigned16" => Unsigned16,
+		"unsigned32" => Unsigned32,
+		"unsigned64" => Unsigned64,
+		"signed8" => Signed8,
+		"signed16" => Signed16,
+		"signed32" => Signed32,
+		"signed64" => Signed64,
+		"float32" => Float32,
+		"float64" => Float64,
+		"boolean" => Boolean,
+		"macAddress" => MacAddress,
+		"octetArray" => OctetArray,
+		"string" => String,
+		"dateTimeSeconds" => DateTimeSeconds,
+		"dateTimeMilliseconds" => DateTimeMilliseconds,
+		"dateTimeMicroseconds" => DateTimeMicroseconds,
+		"dateTimeNanoseconds" => DateTimeNanoseconds,
+		"ipv4Address" => Ipv4Address,
+		"ipv6Address" => Ipv6Address,
+		"basicList" => BasicList,
+		"subTemplateList" => SubTemplateList,
+		"subTemplateMultiList" => SubTemplateMultiList,
+		data_type => return Err(unknown("data type", data_type)),
+	};
+	let data_type_semantics = match record.data_type_semantics.as_ref().map(|s| &s[..]) {
+		None => None,
+		Some("quantity") => Some(Quantity),
+		Some("totalCounter") => Some(TotalCounter),
+		Some("deltaCounter") => Some(DeltaCounter),
+		Some("identifier") => Some(Identifier),
+		Some("flags") => Some(Flags),
+		Some("list") => Some(List),
+		Some("snmpCounter") => Some(SnmpCounter),
+		Some("snmpGauge") => Some(SnmpGauge),
+		Some(semantics) => return Err(unknown("data type semantics", semantics)),
+	};
+	let units = match record.units.as_ref().map(|s| &s[..]) {
+		None => None,
+		Some("bits") => Some(Bits),
+		Some("octets") => Some(Octets),
+		Some("packets") => Some(Packets),
+		Some("flows") => Some(Flows),
+		Some("seconds") => Some(Seconds),
+		Some("milliseconds") => Some(Milliseconds),
+		Some("microseconds") => Some(Microseconds),
+		Some("nanoseconds") => Some(Nanoseconds),
+		Some("4-octet words") => Some(FourOctetWords),
+		Some("messages") => Some(Messages),
+		Some("hops") => Some(Hops),
+		Some("entries") => Some(Entries),
+		Some("frames") => Some(Frames),
+		Some("ports") => Some(Ports),
+		Some("inferred") => Some(Inferred),
+		Some(units) => return Err(unknown("units", units)),
+	};
+	let status = match &record.status[..] {
+		"current" => Status::Current,
+		"deprecated" => Status::Deprecated,
+		status => return Err(unknown("status", status)),
+	};
+
+	Ok(InformationElement {
+		id,
+		enterprise_number : None,
+		name : Cow::Owned(record.name),
+		abstract_data_type,
+		data_type_semantics,
+		units,
+		range : record.range,
+		status,
+		description : Cow::Owned(record.description),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const XML : &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<registry xmlns="http://www.iana.org/assignments" id="ipfix">
+  <registry id="ipfix-information-elements">
+    <record>
+      <name>Reserved</name>
+      <elementId>0</elementId>
+    </record>
+    <record>
+      <name>octetDeltaCount</name>
+      <dataType>unsigned64</dataType>
+      <dataTypeSemantics>deltaCounter</dataTypeSemantics>
+      <elementId>1</elementId>
+      <status>current</status>
+      <description>
+        <paragraph>The number of octets since the previous report.</paragraph>
+      </description>
+      <units>octets</units>
+    </record>
+    <record>
+      <name>futureElement</name>
+      <dataType>ipv6Address</dataType>
+      <dataTypeSemantics>default</dataTypeSemantics>
+      <elementId>1000</elementId>
+      <status>current</status>
+    </record>
+    <record>
+      <name>Unassigned</name>
+      <elementId>1001-32767</elementId>
+    </record>
+  </registry>
+</registry>"#;
+
+	#[test]
+	fn default_registry_test() {
+		let registry = Registry::default();
+		assert_eq!(registry.lookup(8), lookup(8));
+		assert_eq!(registry.lookup(8).unwrap().name, "sourceIPv4Address");
+		assert!(registry.lookup(0).is_none());
+
+		let segments_left = registry.lookup(498).unwrap();
+		assert_eq!(segments_left.name, "srhSegmentsIPv6Left");
+		assert!(segments_left.description.contains("route segments remaining"));
+		assert!(registry.elements().iter().all(|element| !element.description.is_empty()));
+	}
+
+	#[test]
+	fn from_xml_test() {
+		let registry = Registry::from_xml(XML).unwrap();
+		assert_eq!(registry.elements().len(), 2);
+
+		let octet_delta_count = registry.lookup(1).unwrap();
+		assert_eq!(octet_delta_count.data_type_semantics, Some(DataTypeSemantics::DeltaCounter));
+		assert_eq!(octet_delta_count.units, Some(Units::Octets));
+		assert_eq!(
+			octet_delta_count.description,
+			"The number of octets since the previous report."
+		);
+
+		let future_element = registry.lookup(1000).unwrap();
+		assert_eq!(future_element.abstract_data_type, AbstractDataType::Ipv6Address);
+		assert_eq!(future_element.data_type_semantics, None);
+
+		assert!(Registry::from_xml("<registry/>").is_err());
+		assert!(Registry::from_xml(&XML.replace("unsigned64", "unsigned128")).is_err());
+	}
+
+	#[test]
+	fn insert_test() {
+		let mut registry = Registry::default();
+		let mut element = lookup(8).unwrap().clone();
+		element.id = 1000;
+		assert!(registry.insert(element.clone()).is_none());
+		assert_eq!(registry.lookup(1000), Some(&element));
+		assert_eq!(registry.insert(element.clone()), Some(element));
+		assert_eq!(Registry::default().lookup(1000), None);
+	}
+
+	#[test]
+	fn enterprise_test() {
+		let mut registry = Registry::default();
+		let mut element = lookup(8).unwrap().clone();
+		element.enterprise_number = Some(6871);
+		element.name = "yafSourceAddress".into();
+		registry.insert(element.clone());
+
+		assert_eq!(registry.lookup_enterprise(6871, 8), Some(&element));
+		assert_eq!(registry.lookup(8), lookup(8));
+		assert_eq!(registry.lookup_enterprise(6872, 8), None);
+		assert_eq!(registry.lookup_by_name("yafSourceAddress"), Some(&element));
+	}
+
+	#[test]
+	fn lookup_by_name_test() {
+		let registry = Registry::default();
+		assert_eq!(registry.lookup_by_name("sourceIPv4Address").unwrap().id, 8);
+		assert!(registry.lookup_by_name("SourceIPv4Address").is_none());
+		assert_eq!(
+			registry
+				.lookup_by_name_ignore_case("SOURCEIPV4ADDRESS")
+				.unwrap()
+				.id,
+			8
+		);
+		assert!(registry.lookup_by_name_ignore_case("sourceIPv4").is_none());
+	}
+}
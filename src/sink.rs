@@ -0,0 +1,134 @@
+use data_ref::*;
+use registry::*;
+use std;
+use structs::*;
+
+/// Everything known about a data record besides its values
+#[derive(Clone, Copy, Debug)]
+pub struct RecordContext<'a> {
+	pub header : &'a MessageHeader,
+	pub template : &'a TemplateRecord,
+	pub registry : &'a Registry,
+}
+
+impl<'a> RecordContext<'a> {
+	pub fn typed<'b>(&self, record : &'b DataRecord) -> TypedDataRecord<'b>
+	where
+		'a : 'b,
+	{
+		TypedDataRecord {
+			data : record,
+			template : self.template,
+		}
+	}
+}
+
+/// Destination for decoded data records
+pub trait Sink {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()>;
+
+	/// Data record borrowing from the message, as passed by `Collector`.
+	/// Copied for `record` unless the sink writes borrowed values itself.
+	fn record_ref(
+		&mut self,
+		context : &RecordContext,
+		record : &DataRecordRef,
+	) -> std::io::Result<()> {
+		self.record(context, &DataRecord::from(record))
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+impl<S : Sink + ?Sized> Sink for &mut S {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		(**self).record(context, record)
+	}
+
+	fn record_ref(
+		&mut self,
+		context : &RecordContext,
+		record : &DataRecordRef,
+	) -> std::io::Result<()> {
+		(**self).record_ref(context, record)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		(**self).flush()
+	}
+}
+
+impl<S : Sink + ?Sized> Sink for Box<S> {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> std::io::Result<()> {
+		(**self).record(context, record)
+	}
+
+	fn record_ref(
+		&mut self,
+		context : &RecordContext,
+		record : &DataRecordRef,
+	) -> std::io::Result<()> {
+		(**self).record_ref(context, record)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		(**self).flush()
+	}
+}
+
+/// `"8"` for IANA information elements, `"6871:8"` for enterprise specific ones
+pub fn field_key(field : &FieldSpecifier) -> String {
+	match field.enterprise_number {
+		Some(enterprise_number) => {
+			format!("{}:{}", enterprise_number, field.information_element_id)
+		}
+		None => format!("{}", field.information_element_id),
+	}
+}
+
+pub fn field_name<'r>(field : &FieldSpecifier, registry : &'r Registry) -> Option<&'r str> {
+	registry
+		.lookup_field(field)
+		.map(|information_element| &information_element.name[..])
+}
+
+/// Marks keys of information elements unknown to the registry, e.g. `"unknown:6871:18"`
+pub const UNKNOWN_FIELD_PREFIX : &str = "unknown:";
+
+/// Names of the template fields, marked keys for unknown information elements.
+/// Repeated information elements are numbered like JSON keys, e.g. `"paddingOctets#2"`.
+pub fn unique_field_names(template : &TemplateRecord, registry : &Registry) -> Vec<String> {
+	number_repeated(template, |field| match field_name(field, registry) {
+		Some(name) => name.to_string(),
+		None => format!("{}{}", UNKNOWN_FIELD_PREFIX, field_key(field)),
+	})
+}
+
+/// Keys of the template fields, see `field_key`, marked and numbered like `unique_field_names`
+pub fn unique_field_keys(template : &TemplateRecord, registry : &Registry) -> Vec<String> {
+	number_repeated(template, |field| match registry.lookup_field(field) {
+		Some(_) => field_key(field),
+		None => format!("{}{}", UNKNOWN_FIELD_PREFIX, field_key(field)),
+	})
+}
+
+fn number_repeated<F>(template : &TemplateRecord, key : F) -> Vec<String>
+where
+	F : Fn(&FieldSpecifier) -> String,
+{
+	let mut names = Vec::<String>::new();
+	let mut keys = Vec::<String>::new();
+	for field in template.scope_fields.iter().chain(template.fields.iter()) {
+		let key = key(field);
+		let occurrence = keys.iter().filter(|k| **k == key).count() + 1;
+		if occurrence > 1 {
+			names.push(format!("{}#{}", key, occurrence));
+		} else {
+			names.push(key.clone());
+		}
+		keys.push(key);
+	}
+	names
+}
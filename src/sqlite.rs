@@ -0,0 +1,398 @@
+use registry::*;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde_json;
+use sink::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+use structs::*;
+use template_management::*;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SqliteOptions {
+	/// Records inserted in one transaction
+	pub batch_rows : usize,
+}
+
+impl Default for SqliteOptions {
+	fn default() -> SqliteOptions {
+		SqliteOptions { batch_rows : 1000 }
+	}
+}
+
+/// Writes data records to SQLite with one table per observation domain and template,
+/// named like `template_1_256`, and one column per field named like `unique_field_names`.
+/// Tables are created when a template first arrives and listed in the `templates` table
+/// with the template as JSON. A template id reused for different fields gets a new table
+/// like `template_1_256_1`, tables of earlier runs are reused for equal templates.
+///
+/// Integers and booleans are stored as `INTEGER`, addresses, MAC addresses and times in
+/// RFC 3339 as `TEXT`, octet arrays and unknown fields as `BLOB`.
+/// Unsigned 64-bit values above `i64::MAX` are stored as `TEXT` of 20 zero-padded digits,
+/// their columns are declared `BLOB` so SQLite keeps the text instead of a rounded `REAL`.
+/// Text sorts after integers, so `ORDER BY` and comparisons with numbers hold,
+/// while `SUM` and other arithmetic convert it to `REAL`.
+/// Records are inserted in transactions of `batch_rows` records, `flush` commits.
+/// Dropping the sink commits too but only logs errors.
+pub struct SqliteSink {
+	// taken by `into_inner`
+	connection : Option<Connection>,
+	options : SqliteOptions,
+	tables : HashMap<(u32, u16), Table>,
+	pending : usize,
+}
+
+struct Table {
+	template : TemplateRecord,
+	insert : String,
+}
+
+impl SqliteSink {
+	pub fn open<P>(path : P, options : SqliteOptions) -> rusqlite::Result<SqliteSink>
+	where
+		P : AsRef<Path>,
+	{
+		SqliteSink::new(Connection::open(path)?, options)
+	}
+
+	/// Creates the `templates` table if it doesn't exist
+	pub fn new(connection : Connection, options : SqliteOptions) -> rusqlite::Result<SqliteSink> {
+		connection.execute_batch(
+			"CREATE TABLE IF NOT EXISTS templates (
+				table_name TEXT PRIMARY KEY,
+				observation_domain_id INTEGER NOT NULL,
+				template_id INTEGER NOT NULL,
+				template TEXT NOT NULL,
+				export_time INTEGER NOT NULL
+			)",
+		)?;
+		Ok(SqliteSink {
+			connection : Some(connection),
+			options,
+			tables : HashMap::new(),
+			pending : 0,
+		})
+	}
+
+	pub fn connection(&self) -> &Connection {
+		self.connection.as_ref().unwrap()
+	}
+
+	/// The connection after committing pending records
+	pub fn into_inner(mut self) -> rusqlite::Result<Connection> {
+		self.commit()?;
+		Ok(self.connection.take().unwrap())
+	}
+
+	// table of the template, created unless an earlier one has the same fields
+	fn table(&self, context : &RecordContext) -> rusqlite::Result<Table> {
+		let domain = context.header.observation_domain_id;
+		let template_id = context.template.header.template_id;
+		let template = serde_json::to_string(context.template)
+			.map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+		let existing = self
+			.connection()
+			.query_row(
+				"SELECT table_name FROM templates
+				WHERE observation_domain_id = ?1 AND template_id = ?2 AND template = ?3",
+				params![domain, template_id, template],
+				|row| row.get::<_, String>(0),
+			)
+			.optional()?;
+		let names = unique_field_names(context.template, context.registry);
+		let name = match existing {
+			Some(name) => name,
+			None => {
+				let versions : u32 = self.connection().query_row(
+					"SELECT count(*) FROM templates
+					WHERE observation_domain_id = ?1 AND template_id = ?2",
+					params![domain, template_id],
+					|row| row.get(0),
+				)?;
+				let name = match versions {
+					0 => format!("template_{}_{}", domain, template_id),
+					n => format!("template_{}_{}_{}", domain, template_id, n),
+				};
+				let columns = context
+					.template
+					.scope_fields
+					.iter()
+					.chain(context.template.fields.iter())
+					.zip(&names)
+					.map(|(field, name)| {
+						format!("{} {}", quote(name), column_type(field, context.registry))
+					})
+					.collect::<Vec<_>>();
+				self.connection().execute(
+					&format!("CREATE TABLE {} ({})", quote(&name), columns.join(", ")),
+					[],
+				)?;
+				self.connection().execute(
+					"INSERT INTO templates VALUES (?1, ?2, ?3, ?4, ?5)",
+					params![name, domain, template_id, template, context.header.export_time],
+				)?;
+				debug!("created table {}", name);
+				name
+			}
+		};
+
+		let placeholders = vec!["?"; names.len()].join(", ");
+		Ok(Table {
+			template : context.template.clone(),
+			insert : format!("INSERT INTO {} VALUES ({})", quote(&name), placeholders),
+		})
+	}
+
+	fn commit(&mut self) -> rusqlite::Result<()> {
+		if !self.connection().is_autocommit() {
+			self.connection().execute_batch("COMMIT")?;
+		}
+		self.pending = 0;
+		Ok(())
+	}
+
+	fn insert(&mut self, context : &RecordContext, record : &DataRecord) -> rusqlite::Result<()> {
+		if self.connection().is_autocommit() {
+			self.connection().execute_batch("BEGIN")?;
+		}
+		let key = (context.header.observation_domain_id, context.template.header.template_id);
+		let current = match self.tables.get(&key) {
+			Some(table) => table.template == *context.template,
+			None => false,
+		};
+		if !current {
+			let table = self.table(context)?;
+			self.tables.insert(key, table);
+		}
+
+		self.connection()
+			.prepare_cached(&self.tables[&key].insert)?
+			.execute(params_from_iter(record.fields.iter().map(sql_value)))?;
+		self.pending += 1;
+		if self.pending >= self.options.batch_rows {
+			self.commit()?;
+		}
+		Ok(())
+	}
+}
+
+impl Drop for SqliteSink {
+	fn drop(&mut self) {
+		if self.connection.is_some() {
+			if let Err(e) = self.commit() {
+				error!("{}", e);
+			}
+		}
+	}
+}
+
+impl Sink for SqliteSink {
+	fn record(&mut self, context : &RecordContext, record : &DataRecord) -> io::Result<()> {
+		self.insert(context, record).map_err(io::Error::other)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.commit().map_err(io::Error::other)
+	}
+}
+
+fn quote(identifier : &str) -> String {
+	format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn column_type(field : &FieldSpecifier, registry : &Registry) -> &'static str {
+	use structs::AbstractDataType::*;
+
+	let abstract_data_type = match registry.lookup_field(field) {
+		Some(information_element)
+			if verify_field_length(information_element.abstract_data_type, field.field_length)
+				.is_ok() =>
+		{
+			information_element.abstract_data_type
+		}
+		_ => return "BLOB",
+	};
+	match abstract_data_type {
+		Unsigned8 | Unsigned16 | Unsigned32 | Signed8 | Signed16 | Signed32 | Signed64
+		| Boolean => "INTEGER",
+		Float32 | Float64 => "REAL",
+		// no type affinity for `INTEGER` or `TEXT` values
+		Unsigned64 | OctetArray => "BLOB",
+		MacAddress | String | DateTimeSeconds | DateTimeMilliseconds | DateTimeMicroseconds
+		| DateTimeNanoseconds | Ipv4Address | Ipv6Address | BasicList | SubTemplateList
+		| SubTemplateMultiList => "TEXT",
+	}
+}
+
+fn sql_value(value : &DataValue) -> Value {
+	use structs::DataValue::*;
+
+	match *value {
+		Unsigned8(u) => Value::Integer(u.into()),
+		Unsigned16(u) => Value::Integer(u.into()),
+		Unsigned32(u) => Value::Integer(u.into()),
+		Unsigned64(u) => match i64::try_from(u) {
+			Ok(i) => Value::Integer(i),
+			// padded to sort like numbers
+			Err(_) => Value::Text(format!("{:020}", u)),
+		},
+		Signed8(i) => Value::Integer(i.into()),
+		Signed16(i) => Value::Integer(i.into()),
+		Signed32(i) => Value::Integer(i.into()),
+		Signed64(i) => Value::Integer(i),
+		Float32(f) => Value::Real(f.into()),
+		Float64(f) => Value::Real(f),
+		Boolean(b) => Value::Integer(b.into()),
+		OctetArray(ref octets) => Value::Blob(octets.clone()),
+		String(ref s) => Value::Text(s.clone()),
+		_ => Value::Text(value.to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use information_element::*;
+	use std::net::Ipv4Addr;
+	use template_builder::*;
+
+	#[test]
+	fn sqlite_sink_test() {
+		let registry = Registry::default();
+		let flows = TemplateBuilder::new(256, &registry)
+			.field(ie::SOURCE_IPV4_ADDRESS)
+			.field(ie::OCTET_DELTA_COUNT)
+			.field(ie::FLOW_START_MILLISECONDS)
+			.build()
+			.unwrap();
+		let reused = TemplateBuilder::new(256, &registry)
+			.field(ie::INTERFACE_NAME)
+			.build()
+			.unwrap();
+		let header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 1_518_864_600,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let flow = DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::Unsigned64(u64::MAX),
+				DataValue::DateTimeMilliseconds(1_518_864_590_123),
+			],
+		};
+		let interface = DataRecord {
+			fields : vec![DataValue::String("eth0".to_string())],
+		};
+		let write = |sink : &mut SqliteSink, records : &[(&TemplateRecord, &DataRecord)]| {
+			for &(template, record) in records {
+				let context = RecordContext {
+					header : &header,
+					template,
+					registry : &registry,
+				};
+				sink.record(&context, record).unwrap();
+			}
+		};
+
+		let options = SqliteOptions { batch_rows : 2 };
+		let mut sink = SqliteSink::new(Connection::open_in_memory().unwrap(), options).unwrap();
+		write(&mut sink, &[(&flows, &flow), (&flows, &flow), (&reused, &interface)]);
+		// the first two records are committed
+		assert!(!sink.connection().is_autocommit());
+		sink.flush().unwrap();
+		assert!(sink.connection().is_autocommit());
+
+		// tables are reused for templates known from earlier runs
+		let connection = sink.into_inner().unwrap();
+		let mut sink = SqliteSink::new(connection, options).unwrap();
+		write(&mut sink, &[(&flows, &flow)]);
+		let connection = sink.into_inner().unwrap();
+
+		let tables = connection
+			.prepare("SELECT table_name, template_id FROM templates ORDER BY table_name")
+			.unwrap()
+			.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u16>(1)?)))
+			.unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(
+			tables,
+			vec![("template_1_256".to_string(), 256), ("template_1_256_1".to_string(), 256)]
+		);
+		let (count, address, octets, start) = connection
+			.query_row(
+				"SELECT count(*), sourceIPv4Address, octetDeltaCount, flowStartMilliseconds
+				FROM template_1_256",
+				[],
+				|row| {
+					Ok((
+						row.get::<_, u32>(0)?,
+						row.get::<_, String>(1)?,
+						row.get::<_, String>(2)?,
+						row.get::<_, String>(3)?,
+					))
+				},
+			)
+			.unwrap();
+		assert_eq!(count, 3);
+		assert_eq!(address, "192.0.2.1");
+		assert_eq!(octets.parse::<u64>(), Ok(u64::MAX));
+		assert_eq!(start, "2018-02-17T10:49:50.123Z");
+		let name : String = connection
+			.query_row("SELECT interfaceName FROM template_1_256_1", [], |row| row.get(0))
+			.unwrap();
+		assert_eq!(name, "eth0");
+
+		// unsigned 64-bit values in numeric order
+		let mut sink = SqliteSink::new(Connection::open_in_memory().unwrap(), options).unwrap();
+		let counts = [u64::MAX, 1500, 1 << 63];
+		let records = counts
+			.iter()
+			.map(|&octets| DataRecord {
+				fields : vec![
+					DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+					DataValue::Unsigned64(octets),
+					DataValue::DateTimeMilliseconds(1_518_864_590_123),
+				],
+			})
+			.collect::<Vec<_>>();
+		write(&mut sink, &records.iter().map(|record| (&flows, record)).collect::<Vec<_>>());
+		let connection = sink.into_inner().unwrap();
+		let sorted = connection
+			.prepare(
+				"SELECT octetDeltaCount FROM template_1_256 WHERE octetDeltaCount > 1000
+				ORDER BY octetDeltaCount",
+			)
+			.unwrap()
+			.query_map([], |row| row.get::<_, Value>(0))
+			.unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(
+			sorted,
+			vec![
+				Value::Integer(1500),
+				Value::Text("09223372036854775808".to_string()),
+				Value::Text("18446744073709551615".to_string()),
+			]
+		);
+
+		// dropping the sink commits
+		let path = std::env::temp_dir().join(format!("libipfix-{}.sqlite", std::process::id()));
+		let mut sink = SqliteSink::open(&path, SqliteOptions::default()).unwrap();
+		write(&mut sink, &[(&flows, &flow)]);
+		drop(sink);
+		let count : u32 = Connection::open(&path)
+			.unwrap()
+			.query_row("SELECT count(*) FROM template_1_256", [], |row| row.get(0))
+			.unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(count, 1);
+	}
+}
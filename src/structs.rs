@@ -1,6 +1,11 @@
+use data_ref::DataValueRef;
+use registry::*;
+use sink::{field_key, UNKNOWN_FIELD_PREFIX};
+use template_management::verify_field_length;
 use std;
 pub use std::net::{Ipv4Addr, Ipv6Addr};
 
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
 use serde::ser::{Serialize, SerializeMap, SerializeTupleStruct, Serializer};
 
 pub const MESSAGE_HEADER_LENGTH : u16 = 16;
@@ -8,6 +13,7 @@ pub const SET_HEADER_LENGTH : u16 = 4;
 pub const IPFIX_VERSION_NUMBER : u16 = 0x000a;
 pub const IPFIX_VERSION_TAG : &[u8] = &[0x00, 0x0a];
 pub const VARIABLE_LENGTH_LONG_TAG : u8 = 0xff;
+pub const VARIABLE_LENGTH : u16 = 0xffff;
 
 pub const TEMPLATE_SET_ID : u16 = 2;
 pub const OPTIONS_TEMPLATE_SET_ID : u16 = 3;
@@ -20,7 +26,7 @@ pub struct Message<'a> {
 	pub sets : Vec<(SetHeader, &'a [u8])>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct MessageHeader {
 	pub version_number : u16,
 	pub length : u16,
@@ -29,39 +35,39 @@ pub struct MessageHeader {
 	pub observation_domain_id : u32,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct FieldSpecifier {
 	pub information_element_id : u16,
 	pub field_length : u16,
 	pub enterprise_number : Option<u32>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct SetHeader {
 	pub set_id : u16,
 	pub length : u16,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TemplateRecord {
 	pub header : TemplateRecordHeader,
 	pub scope_fields : Vec<FieldSpecifier>,
 	pub fields : Vec<FieldSpecifier>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TemplateRecordHeader {
 	pub template_id : u16,
 	pub field_count : u16,
 	pub scope_field_count : u16,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct DataRecord {
 	pub fields : Vec<DataValue>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum AbstractDataType {
 	Unsigned8,
 	Unsigned16,
@@ -89,6 +95,26 @@ pub enum AbstractDataType {
 	SubTemplateMultiList,
 }
 
+impl AbstractDataType {
+	/// Field length without reduced-size encoding, `0xffff` for variable length types
+	pub fn default_length(self) -> u16 {
+		use structs::AbstractDataType::*;
+
+		match self {
+			Unsigned8 | Signed8 | Boolean => 1,
+			Unsigned16 | Signed16 => 2,
+			Unsigned32 | Signed32 | Float32 | DateTimeSeconds | Ipv4Address => 4,
+			Unsigned64 | Signed64 | Float64 => 8,
+			DateTimeMilliseconds | DateTimeMicroseconds | DateTimeNanoseconds => 8,
+			MacAddress => 6,
+			Ipv6Address => 16,
+			OctetArray | String | BasicList | SubTemplateList | SubTemplateMultiList => {
+				VARIABLE_LENGTH
+			}
+		}
+	}
+}
+
 impl std::fmt::Display for AbstractDataType {
 	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
 		write!(f, "{:?}", self)
@@ -163,11 +189,244 @@ impl Serialize for DataValue {
 	}
 }
 
+/// Text of a value as written by `CsvSink`: MAC addresses like JSON, octet arrays in hex
+/// and `dateTime*` values as RFC 3339 timestamps
+impl std::fmt::Display for DataValue {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		DataValueRef::from(self).fmt(f)
+	}
+}
+
+/// Without a template the type is guessed from the serialized form:
+/// integers become `Unsigned64` or `Signed64`, numbers `Float64`, strings `String`
+/// and sequences `OctetArray`. Use `TypedDataRecordSeed` to restore the exact types.
+impl<'de> Deserialize<'de> for DataValue {
+	fn deserialize<D>(d : D) -> Result<DataValue, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		d.deserialize_any(UntypedDataValueVisitor)
+	}
+}
+
+struct UntypedDataValueVisitor;
+
+impl<'de> Visitor<'de> for UntypedDataValueVisitor {
+	type Value = DataValue;
+
+	fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "a data value")
+	}
+
+	fn visit_bool<E : de::Error>(self, v : bool) -> Result<DataValue, E> {
+		Ok(DataValue::Boolean(v))
+	}
+
+	fn visit_u64<E : de::Error>(self, v : u64) -> Result<DataValue, E> {
+		Ok(DataValue::Unsigned64(v))
+	}
+
+	fn visit_i64<E : de::Error>(self, v : i64) -> Result<DataValue, E> {
+		Ok(DataValue::Signed64(v))
+	}
+
+	fn visit_f64<E : de::Error>(self, v : f64) -> Result<DataValue, E> {
+		Ok(DataValue::Float64(v))
+	}
+
+	fn visit_str<E : de::Error>(self, v : &str) -> Result<DataValue, E> {
+		Ok(DataValue::String(v.to_string()))
+	}
+
+	fn visit_bytes<E : de::Error>(self, v : &[u8]) -> Result<DataValue, E> {
+		Ok(DataValue::OctetArray(v.to_vec()))
+	}
+
+	fn visit_seq<A : SeqAccess<'de>>(self, seq : A) -> Result<DataValue, A::Error> {
+		OctetsVisitor.visit_seq(seq).map(DataValue::OctetArray)
+	}
+}
+
+// octet arrays are sequences in JSON but byte strings in binary formats
+struct OctetsVisitor;
+
+impl<'de> Visitor<'de> for OctetsVisitor {
+	type Value = Vec<u8>;
+
+	fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "bytes")
+	}
+
+	fn visit_bytes<E : de::Error>(self, v : &[u8]) -> Result<Vec<u8>, E> {
+		Ok(v.to_vec())
+	}
+
+	fn visit_byte_buf<E : de::Error>(self, v : Vec<u8>) -> Result<Vec<u8>, E> {
+		Ok(v)
+	}
+
+	fn visit_seq<A : SeqAccess<'de>>(self, mut seq : A) -> Result<Vec<u8>, A::Error> {
+		let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+		while let Some(byte) = seq.next_element::<u8>()? {
+			bytes.push(byte);
+		}
+		Ok(bytes)
+	}
+}
+
+// dateTime values as written with `TimeFormat::Raw` or `TimeFormat::Rfc3339`
+struct DateTimeVisitor(AbstractDataType);
+
+impl<'de> Visitor<'de> for DateTimeVisitor {
+	type Value = DataValue;
+
+	fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{} as raw value or RFC 3339 string", self.0)
+	}
+
+	fn visit_u64<E : de::Error>(self, v : u64) -> Result<DataValue, E> {
+		match self.0 {
+			AbstractDataType::DateTimeSeconds if v <= u32::MAX as u64 => {
+				Ok(DataValue::DateTimeSeconds(v as u32))
+			}
+			AbstractDataType::DateTimeMilliseconds => Ok(DataValue::DateTimeMilliseconds(v)),
+			_ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self)),
+		}
+	}
+
+	fn visit_str<E : de::Error>(self, v : &str) -> Result<DataValue, E> {
+		DataValue::from_rfc3339(self.0, v)
+			.ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(v), &self))
+	}
+
+	fn visit_seq<A : SeqAccess<'de>>(self, mut seq : A) -> Result<DataValue, A::Error> {
+		let mut next = |index| {
+			seq.next_element::<u32>()?
+				.ok_or_else(|| de::Error::invalid_length(index, &"[seconds, fraction]"))
+		};
+		let (seconds, fraction) = (next(0)?, next(1)?);
+		match self.0 {
+			AbstractDataType::DateTimeMicroseconds => {
+				Ok(DataValue::DateTimeMicroseconds { seconds, fraction })
+			}
+			AbstractDataType::DateTimeNanoseconds => {
+				Ok(DataValue::DateTimeNanoseconds { seconds, fraction })
+			}
+			_ => Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+		}
+	}
+}
+
+/// Deserializes a `DataValue` into the variant the parser produces
+/// for a field of this type and length
+/// `dateTime*` values are accepted raw or as RFC 3339 strings
+#[derive(Clone, Copy, Debug)]
+pub struct DataValueSeed {
+	pub abstract_data_type : AbstractDataType,
+	pub field_length : u16,
+}
+
+impl<'de> DeserializeSeed<'de> for DataValueSeed {
+	type Value = DataValue;
+
+	fn deserialize<D>(self, d : D) -> Result<DataValue, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		use structs::AbstractDataType::*;
+
+		let unsupported = || {
+			de::Error::custom(format_args!(
+				"{} of length {} not supported",
+				self.abstract_data_type, self.field_length
+			))
+		};
+		Ok(match (self.abstract_data_type, self.field_length) {
+			(Unsigned8, 1) | (Unsigned16, 1) | (Unsigned32, 1) | (Unsigned64, 1) => {
+				DataValue::Unsigned8(u8::deserialize(d)?)
+			}
+			(Unsigned16, 2) | (Unsigned32, 2) | (Unsigned64, 2) => {
+				DataValue::Unsigned16(u16::deserialize(d)?)
+			}
+			(Unsigned32, 4) | (Unsigned64, 4) => DataValue::Unsigned32(u32::deserialize(d)?),
+			(Unsigned64, 8) => DataValue::Unsigned64(u64::deserialize(d)?),
+			(Signed8, 1) | (Signed16, 1) | (Signed32, 1) | (Signed64, 1) => {
+				DataValue::Signed8(i8::deserialize(d)?)
+			}
+			(Signed16, 2) | (Signed32, 2) | (Signed64, 2) => {
+				DataValue::Signed16(i16::deserialize(d)?)
+			}
+			(Signed32, 4) | (Signed64, 4) => DataValue::Signed32(i32::deserialize(d)?),
+			(Signed64, 8) => DataValue::Signed64(i64::deserialize(d)?),
+			(Float32, 4) | (Float64, 4) => DataValue::Float32(f32::deserialize(d)?),
+			(Float64, 8) => DataValue::Float64(f64::deserialize(d)?),
+			(Boolean, 1) => DataValue::Boolean(bool::deserialize(d)?),
+			(MacAddress, 6) => {
+				let string = std::string::String::deserialize(d)?;
+				let bytes = string
+					.split(['-', ':'])
+					.map(|byte| u8::from_str_radix(byte, 16))
+					.collect::<Result<Vec<u8>, _>>()
+					.ok()
+					.filter(|bytes| bytes.len() == 6)
+					.ok_or_else(|| de::Error::custom(format_args!("invalid MAC address {}", string)))?;
+				DataValue::MacAddress(bytes)
+			}
+			(OctetArray, length) => {
+				let bytes = d.deserialize_bytes(OctetsVisitor)?;
+				if length != 0xffff && bytes.len() != length as usize {
+					return Err(de::Error::invalid_length(bytes.len(), &"field length"));
+				}
+				DataValue::OctetArray(bytes)
+			}
+			(String, length) => {
+				let string = std::string::String::deserialize(d)?;
+				if length != 0xffff && string.len() != length as usize {
+					return Err(de::Error::invalid_length(string.len(), &"field length"));
+				}
+				DataValue::String(string)
+			}
+			(DateTimeSeconds, 4)
+			| (DateTimeMilliseconds, 8)
+			| (DateTimeMicroseconds, 8)
+			| (DateTimeNanoseconds, 8) => {
+				d.deserialize_any(DateTimeVisitor(self.abstract_data_type))?
+			}
+			(Ipv4Address, 4) => DataValue::Ipv4Address(
+				std::string::String::deserialize(d)?
+					.parse()
+					.map_err(de::Error::custom)?,
+			),
+			(Ipv6Address, 16) => DataValue::Ipv6Address(
+				std::string::String::deserialize(d)?
+					.parse()
+					.map_err(de::Error::custom)?,
+			),
+			_ => return Err(unsupported()),
+		})
+	}
+}
+
 pub struct TypedDataRecord<'a> {
 	pub data : &'a DataRecord,
 	pub template : &'a TemplateRecord,
 }
 
+impl<'a> TypedDataRecord<'a> {
+	/// First value of an IANA information element, scope fields included
+	pub fn get(&self, id : u16) -> Option<&'a DataValue> {
+		self.template
+			.scope_fields
+			.iter()
+			.chain(self.template.fields.iter())
+			.zip(self.data.fields.iter())
+			.find(|&(specifier, _)| {
+				specifier.information_element_id == id && specifier.enterprise_number.is_none()
+			})
+			.map(|(_, value)| value)
+	}
+}
+
 impl<'a> Serialize for TypedDataRecord<'a> {
 	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
 	where
@@ -188,6 +447,115 @@ impl<'a> Serialize for TypedDataRecord<'a> {
 	}
 }
 
+/// Reads back a serialized `TypedDataRecord`.
+/// Values are typed by the template and registry, unknown fields are octet arrays,
+/// keys may be ids as written by `TypedDataRecord` or names, optionally suffixed with `#n`
+/// and prefixed with `UNKNOWN_FIELD_PREFIX`.
+#[derive(Clone, Copy, Debug)]
+pub struct TypedDataRecordSeed<'a> {
+	pub template : &'a TemplateRecord,
+	pub registry : &'a Registry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for TypedDataRecordSeed<'a> {
+	type Value = DataRecord;
+
+	fn deserialize<D>(self, d : D) -> Result<DataRecord, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		d.deserialize_map(self)
+	}
+}
+
+impl<'a, 'de> Visitor<'de> for TypedDataRecordSeed<'a> {
+	type Value = DataRecord;
+
+	fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "a map of template {}", self.template.header.template_id)
+	}
+
+	fn visit_map<A : MapAccess<'de>>(self, mut map : A) -> Result<DataRecord, A::Error> {
+		let specifiers = self.template
+			.scope_fields
+			.iter()
+			.chain(self.template.fields.iter())
+			.collect::<Vec<_>>();
+		let mut fields = Vec::with_capacity(specifiers.len());
+
+		while let Some(key) = map.next_key::<FieldKey>()? {
+			let specifier = specifiers
+				.get(fields.len())
+				.ok_or_else(|| de::Error::invalid_length(fields.len() + 1, &self))?;
+			let information_element = self.registry.lookup_field(specifier);
+
+			let key = key.0.split('#').next().unwrap_or_default();
+			let key = key.strip_prefix(UNKNOWN_FIELD_PREFIX).unwrap_or(key);
+			let name = information_element.map(|information_element| &information_element.name[..]);
+			if key != field_key(specifier) && Some(key) != name {
+				return Err(de::Error::custom(format_args!(
+					"expected field {} at position {}, found {}",
+					field_key(specifier),
+					fields.len(),
+					key
+				)));
+			}
+
+			// the parser keeps unknown fields and invalid lengths as raw octets
+			let abstract_data_type = match information_element {
+				Some(information_element)
+					if verify_field_length(
+						information_element.abstract_data_type,
+						specifier.field_length,
+					)
+					.is_ok() =>
+				{
+					information_element.abstract_data_type
+				}
+				_ => AbstractDataType::OctetArray,
+			};
+			fields.push(map.next_value_seed(DataValueSeed {
+				abstract_data_type,
+				field_length : specifier.field_length,
+			})?);
+		}
+		if fields.len() != specifiers.len() {
+			return Err(de::Error::invalid_length(fields.len(), &self));
+		}
+		Ok(DataRecord { fields })
+	}
+}
+
+// map keys are strings in JSON but may be integers in other formats
+struct FieldKey(std::string::String);
+
+impl<'de> Deserialize<'de> for FieldKey {
+	fn deserialize<D>(d : D) -> Result<FieldKey, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		struct FieldKeyVisitor;
+
+		impl<'de> Visitor<'de> for FieldKeyVisitor {
+			type Value = FieldKey;
+
+			fn expecting(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+				write!(f, "an information element id or name")
+			}
+
+			fn visit_u64<E : de::Error>(self, v : u64) -> Result<FieldKey, E> {
+				Ok(FieldKey(v.to_string()))
+			}
+
+			fn visit_str<E : de::Error>(self, v : &str) -> Result<FieldKey, E> {
+				Ok(FieldKey(v.to_string()))
+			}
+		}
+
+		d.deserialize_any(FieldKeyVisitor)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	extern crate serde_json;
@@ -277,6 +645,19 @@ mod tests {
 		*/
 	}
 
+	#[test]
+	pub fn data_value_display_test() {
+		use DataValue::*;
+
+		assert_eq!(Signed16(-500).to_string(), "-500");
+		assert_eq!(Float64(0.5).to_string(), "0.5");
+		assert_eq!(Boolean(true).to_string(), "true");
+		assert_eq!(MacAddress(vec![0, 0x1b, 0x21, 0xaa, 0, 1]).to_string(), "00-1B-21-AA-00-01");
+		assert_eq!(OctetArray(vec![0xca, 0xfe, 1]).to_string(), "cafe01");
+		assert_eq!(DateTimeMilliseconds(1_518_864_590_123).to_string(), "2018-02-17T10:49:50.123Z");
+		assert_eq!(Ipv6Address("2001:db8::1".parse().unwrap()).to_string(), "2001:db8::1");
+	}
+
 	#[test]
 	pub fn data_record_json_test() {
 		let template = TemplateRecord {
@@ -314,4 +695,123 @@ mod tests {
 			"{\"210\":[0,0,0,0],\"210\":[0,0,0,0]}"
 		);
 	}
+
+	#[test]
+	pub fn data_value_deserialize_test() {
+		use DataValue::*;
+
+		let untyped = |json : &str| serde_json::from_str::<DataValue>(json).unwrap();
+		assert_eq!(untyped("255"), Unsigned64(255));
+		assert_eq!(untyped("-5"), Signed64(-5));
+		assert_eq!(untyped("32.5"), Float64(32.5));
+		assert_eq!(untyped("\"::1\""), String("::1".to_string()));
+		assert_eq!(untyped("[0,1,2]"), OctetArray(vec![0, 1, 2]));
+
+		let typed = |abstract_data_type, field_length, json : &str| {
+			DataValueSeed {
+				abstract_data_type,
+				field_length,
+			}.deserialize(&mut serde_json::Deserializer::from_str(json))
+		};
+		assert_eq!(
+			typed(AbstractDataType::Unsigned64, 2, "443").unwrap(),
+			Unsigned16(443)
+		);
+		assert!(typed(AbstractDataType::Unsigned64, 1, "443").is_err());
+		assert_eq!(
+			typed(AbstractDataType::MacAddress, 6, "\"00-01-02-03-04-FF\"").unwrap(),
+			MacAddress(vec![0, 1, 2, 3, 4, 0xff])
+		);
+		assert_eq!(
+			typed(AbstractDataType::DateTimeNanoseconds, 8, "[3600,1]").unwrap(),
+			DateTimeNanoseconds {
+				seconds : 3600,
+				fraction : 1,
+			}
+		);
+		assert_eq!(
+			typed(AbstractDataType::Ipv4Address, 4, "\"127.0.0.1\"").unwrap(),
+			Ipv4Address(Ipv4Addr::new(127, 0, 0, 1))
+		);
+		assert!(typed(AbstractDataType::OctetArray, 4, "[0,1]").is_err());
+		assert_eq!(
+			typed(AbstractDataType::OctetArray, 0xffff, "[0,1]").unwrap(),
+			OctetArray(vec![0, 1])
+		);
+	}
+
+	#[test]
+	pub fn typed_data_record_deserialize_test() {
+		let registry = Registry::default();
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 3,
+				scope_field_count : 0,
+			},
+			fields : vec![
+				FieldSpecifier {
+					information_element_id : 8,
+					field_length : 4,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 7,
+					field_length : 2,
+					enterprise_number : None,
+				},
+				FieldSpecifier {
+					information_element_id : 7,
+					field_length : 2,
+					enterprise_number : None,
+				},
+			],
+			scope_fields : vec![],
+		};
+		let record = DataRecord {
+			fields : vec![
+				DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1)),
+				DataValue::Unsigned16(80),
+				DataValue::Unsigned16(443),
+			],
+		};
+		let seed = TypedDataRecordSeed {
+			template : &template,
+			registry : &registry,
+		};
+		let json = serde_json::to_string(&TypedDataRecord {
+			template : &template,
+			data : &record,
+		}).unwrap();
+		let deserialize = |json : &str| seed.deserialize(&mut serde_json::Deserializer::from_str(json));
+
+		assert_eq!(deserialize(&json).unwrap(), record);
+		assert_eq!(
+			deserialize(
+				r#"{"sourceIPv4Address":"192.0.2.1","sourceTransportPort":80,"sourceTransportPort#2":443}"#
+			).unwrap(),
+			record
+		);
+		assert!(deserialize(r#"{"8":"192.0.2.1","7":80}"#).is_err());
+		assert!(deserialize(r#"{"7":80,"8":"192.0.2.1","7":443}"#).is_err());
+	}
+
+	#[test]
+	pub fn template_record_json_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 1,
+				scope_field_count : 0,
+			},
+			fields : vec![FieldSpecifier {
+				information_element_id : 8,
+				field_length : 4,
+				enterprise_number : Some(6871),
+			}],
+			scope_fields : vec![],
+		};
+		let json = serde_json::to_string(&template).unwrap();
+		assert_eq!(serde_json::from_str::<TemplateRecord>(&json).unwrap(), template);
+	}
 }
@@ -0,0 +1,303 @@
+use registry::*;
+use std;
+use structs::*;
+use template_management::*;
+
+/// Information element of a template field
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FieldRef {
+	Id(u16),
+	/// enterprise number and id
+	Enterprise(u32, u16),
+	Name(String),
+}
+
+impl From<u16> for FieldRef {
+	fn from(id : u16) -> FieldRef {
+		FieldRef::Id(id)
+	}
+}
+
+impl From<(u32, u16)> for FieldRef {
+	fn from((enterprise_number, id) : (u32, u16)) -> FieldRef {
+		FieldRef::Enterprise(enterprise_number, id)
+	}
+}
+
+impl<'a> From<&'a str> for FieldRef {
+	fn from(name : &'a str) -> FieldRef {
+		FieldRef::Name(name.to_string())
+	}
+}
+
+impl std::fmt::Display for FieldRef {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		match *self {
+			FieldRef::Id(id) => write!(f, "{}", id),
+			FieldRef::Enterprise(enterprise_number, id) => {
+				write!(f, "{}:{}", enterprise_number, id)
+			}
+			FieldRef::Name(ref name) => write!(f, "{}", name),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TemplateBuildError {
+	TemplateIdInvalid(u16),
+	InformationElementUnknown(FieldRef),
+	Verify(VerifyTemplateError),
+}
+
+impl std::fmt::Display for TemplateBuildError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use TemplateBuildError::*;
+
+		match *self {
+			TemplateIdInvalid(id) => write!(
+				f,
+				"template id {} is invalid, template ids start at {}",
+				id, FIRST_TEMPLATE_ID
+			),
+			InformationElementUnknown(ref field) => {
+				write!(f, "information element {} not found", field)
+			}
+			Verify(ref e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl From<VerifyTemplateError> for TemplateBuildError {
+	fn from(e : VerifyTemplateError) -> TemplateBuildError {
+		TemplateBuildError::Verify(e)
+	}
+}
+
+/// Builds templates from information element ids, enterprise ids or names.
+/// Field lengths default to the length of the abstract data type,
+/// or variable length for octet arrays and strings.
+///
+/// ```
+/// # use libipfix::*;
+/// let registry = Registry::default();
+/// let template = TemplateBuilder::new(256, &registry)
+///     .field("sourceIPv4Address")
+///     .field(ie::DESTINATION_IPV4_ADDRESS)
+///     .field_with_length("octetDeltaCount", 4)
+///     .build()
+///     .unwrap();
+/// assert_eq!(template.header.field_count, 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TemplateBuilder<'r> {
+	registry : &'r Registry,
+	template_id : u16,
+	scope_fields : Vec<FieldSpecifier>,
+	fields : Vec<FieldSpecifier>,
+	// reported by build to keep the calls chainable
+	error : Option<TemplateBuildError>,
+}
+
+impl<'r> TemplateBuilder<'r> {
+	pub fn new(template_id : u16, registry : &'r Registry) -> TemplateBuilder<'r> {
+		TemplateBuilder {
+			registry,
+			template_id,
+			scope_fields : Vec::new(),
+			fields : Vec::new(),
+			error : None,
+		}
+	}
+
+	pub fn field<F : Into<FieldRef>>(mut self, field : F) -> TemplateBuilder<'r> {
+		if let Some(field) = self.specifier(field.into(), None) {
+			self.fields.push(field);
+		}
+		self
+	}
+
+	/// Reduced-size encoding or `VARIABLE_LENGTH`
+	pub fn field_with_length<F : Into<FieldRef>>(
+		mut self,
+		field : F,
+		length : u16,
+	) -> TemplateBuilder<'r> {
+		if let Some(field) = self.specifier(field.into(), Some(length)) {
+			self.fields.push(field);
+		}
+		self
+	}
+
+	/// Scope fields make an options template
+	pub fn scope_field<F : Into<FieldRef>>(mut self, field : F) -> TemplateBuilder<'r> {
+		if let Some(field) = self.specifier(field.into(), None) {
+			self.scope_fields.push(field);
+		}
+		self
+	}
+
+	pub fn scope_field_with_length<F : Into<FieldRef>>(
+		mut self,
+		field : F,
+		length : u16,
+	) -> TemplateBuilder<'r> {
+		if let Some(field) = self.specifier(field.into(), Some(length)) {
+			self.scope_fields.push(field);
+		}
+		self
+	}
+
+	pub fn build(self) -> Result<TemplateRecord, TemplateBuildError> {
+		if let Some(e) = self.error {
+			return Err(e);
+		}
+		if self.template_id < FIRST_TEMPLATE_ID {
+			return Err(TemplateBuildError::TemplateIdInvalid(self.template_id));
+		}
+
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : self.template_id,
+				field_count : (self.scope_fields.len() + self.fields.len()) as u16,
+				scope_field_count : self.scope_fields.len() as u16,
+			},
+			scope_fields : self.scope_fields,
+			fields : self.fields,
+		};
+		verify_template(&template, self.registry)?;
+		Ok(template)
+	}
+
+	fn specifier(&mut self, field : FieldRef, length : Option<u16>) -> Option<FieldSpecifier> {
+		if self.error.is_some() {
+			return None;
+		}
+
+		let information_element = match field {
+			FieldRef::Id(id) => self.registry.lookup(id),
+			FieldRef::Enterprise(enterprise_number, id) => {
+				self.registry.lookup_enterprise(enterprise_number, id)
+			}
+			FieldRef::Name(ref name) => self.registry.lookup_by_name(name),
+		};
+		match information_element {
+			Some(information_element) => Some(FieldSpecifier {
+				information_element_id : information_element.id,
+				field_length : length
+					.unwrap_or_else(|| information_element.abstract_data_type.default_length()),
+				enterprise_number : information_element.enterprise_number,
+			}),
+			None => {
+				self.error = Some(TemplateBuildError::InformationElementUnknown(field));
+				None
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use information_element::*;
+
+	#[test]
+	fn build_test() {
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.scope_field(ie::OBSERVATION_DOMAIN_ID)
+			.field("interfaceName")
+			.field_with_length(ie::OCTET_DELTA_COUNT, 4)
+			.build()
+			.unwrap();
+
+		assert_eq!(
+			template,
+			TemplateRecord {
+				header : TemplateRecordHeader {
+					template_id : 256,
+					field_count : 3,
+					scope_field_count : 1,
+				},
+				scope_fields : vec![FieldSpecifier {
+					information_element_id : 149,
+					field_length : 4,
+					enterprise_number : None,
+				}],
+				fields : vec![
+					FieldSpecifier {
+						information_element_id : 82,
+						field_length : VARIABLE_LENGTH,
+						enterprise_number : None,
+					},
+					FieldSpecifier {
+						information_element_id : 1,
+						field_length : 4,
+						enterprise_number : None,
+					},
+				],
+			}
+		);
+	}
+
+	#[test]
+	fn enterprise_test() {
+		let mut registry = Registry::default();
+		let mut element = lookup(ie::SOURCE_IPV4_ADDRESS).unwrap().clone();
+		element.enterprise_number = Some(6871);
+		element.id = 18;
+		element.name = "yafSourceAddress".into();
+		registry.insert(element);
+
+		let template = TemplateBuilder::new(256, &registry)
+			.field((6871, 18))
+			.field("yafSourceAddress")
+			.build()
+			.unwrap();
+		assert_eq!(template.fields[0], template.fields[1]);
+		assert_eq!(
+			template.fields[0],
+			FieldSpecifier {
+				information_element_id : 18,
+				field_length : 4,
+				enterprise_number : Some(6871),
+			}
+		);
+	}
+
+	#[test]
+	fn error_test() {
+		let registry = Registry::default();
+		assert_eq!(
+			TemplateBuilder::new(256, &registry)
+				.field("sourceIPv4")
+				.field(ie::SOURCE_IPV4_ADDRESS)
+				.build(),
+			Err(TemplateBuildError::InformationElementUnknown(FieldRef::Name(
+				"sourceIPv4".to_string()
+			)))
+		);
+		assert_eq!(
+			TemplateBuilder::new(2, &registry)
+				.field(ie::SOURCE_IPV4_ADDRESS)
+				.build(),
+			Err(TemplateBuildError::TemplateIdInvalid(2))
+		);
+		assert_eq!(
+			TemplateBuilder::new(256, &registry)
+				.field_with_length(ie::SOURCE_IPV4_ADDRESS, 2)
+				.build(),
+			Err(TemplateBuildError::Verify(
+				VerifyTemplateError::FieldLengthMismatch {
+					length : 2,
+					type_ : AbstractDataType::Ipv4Address,
+				}
+			))
+		);
+		assert_eq!(
+			TemplateBuilder::new(256, &registry).build(),
+			Err(TemplateBuildError::Verify(
+				VerifyTemplateError::FieldCountInvalid(0)
+			))
+		);
+	}
+}
@@ -1,10 +1,30 @@
-use information_element;
+use decode_plan::*;
+use decoder_options::*;
+use registry::*;
 use std;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
 use structs::*;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json;
+
+/// Templates by observation domain and template id
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TemplateCache {
-	templates : std::collections::HashMap<u16, TemplateRecord>,
+	templates : std::collections::HashMap<(u32, u16), CachedTemplate>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CachedTemplate {
+	pub observation_domain_id : u32,
+	pub template : TemplateRecord,
+	/// last addition or redefinition
+	pub received : SystemTime,
+	/// compiled on first use by `lookup_planned`, failures are kept until `clear_plans`
+	#[serde(skip)]
+	pub plan : Option<Result<DecodePlan, VerifyTemplateError>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -21,7 +41,11 @@ pub enum UpdateErr {
 }
 
 impl TemplateCache {
-	pub fn update_with(&mut self, template : TemplateRecord) -> Result<UpdateOk, UpdateErr> {
+	pub fn update_with(
+		&mut self,
+		observation_domain_id : u32,
+		template : TemplateRecord,
+	) -> Result<UpdateOk, UpdateErr> {
 		use std::collections::hash_map::Entry::*;
 
 		assert!(template.header.template_id >= FIRST_TEMPLATE_ID);
@@ -31,30 +55,132 @@ impl TemplateCache {
 				== template.fields.len()
 		);
 
+		let key = (observation_domain_id, template.header.template_id);
 		if template.header.field_count == 0 {
 			// template withdrawal
 			self.templates
-				.remove(&template.header.template_id)
+				.remove(&key)
 				.map(|_| UpdateOk::Withdrawal)
 				.ok_or(UpdateErr::WithdrawalUnknown)
 		} else {
-			match self.templates.entry(template.header.template_id) {
-				Occupied(ref entry) if &template == entry.get() => Ok(UpdateOk::Redefinition),
-				Occupied(entry) => {
-					// template != entry
-					entry.remove();
-					Err(UpdateErr::RedefinitionDifferent)
+			match self.templates.entry(key) {
+				Occupied(mut entry) => {
+					if template == entry.get().template {
+						entry.get_mut().received = SystemTime::now();
+						Ok(UpdateOk::Redefinition)
+					} else {
+						entry.remove();
+						Err(UpdateErr::RedefinitionDifferent)
+					}
 				}
 				Vacant(entry) => {
-					entry.insert(template);
+					entry.insert(CachedTemplate {
+						observation_domain_id,
+						template,
+						received : SystemTime::now(),
+						plan : None,
+					});
 					Ok(UpdateOk::Addition)
 				}
 			}
 		}
 	}
 
-	pub fn lookup(&self, id : u16) -> Option<&TemplateRecord> {
-		self.templates.get(&id)
+	pub fn lookup(&self, observation_domain_id : u32, id : u16) -> Option<&TemplateRecord> {
+		self.lookup_cached(observation_domain_id, id)
+			.map(|cached| &cached.template)
+	}
+
+	pub fn lookup_cached(&self, observation_domain_id : u32, id : u16) -> Option<&CachedTemplate> {
+		self.templates.get(&(observation_domain_id, id))
+	}
+
+	/// Template with a decode plan for `registry` and `options`,
+	/// compiled unless the cached plan has the same options or failed to compile
+	pub fn lookup_planned(
+		&mut self,
+		observation_domain_id : u32,
+		id : u16,
+		registry : &Registry,
+		options : &DecoderOptions,
+	) -> Option<Result<(&TemplateRecord, &DecodePlan), VerifyTemplateError>> {
+		let cached = self.templates.get_mut(&(observation_domain_id, id))?;
+		let current = match cached.plan {
+			Some(Ok(ref plan)) => plan.options() == options,
+			Some(Err(_)) => true,
+			None => false,
+		};
+		if !current {
+			cached.plan = Some(DecodePlan::compile(&cached.template, registry, options));
+		}
+		let cached = &*cached;
+		cached.plan.as_ref().map(|plan| match *plan {
+			Ok(ref plan) => Ok((&cached.template, plan)),
+			Err(e) => Err(e),
+		})
+	}
+
+	/// Plans have to be recompiled after changes to the registry or the options of failed plans
+	pub fn clear_plans(&mut self) {
+		for cached in self.templates.values_mut() {
+			cached.plan = None;
+		}
+	}
+
+	/// Sorted by observation domain and template id
+	pub fn templates(&self) -> Vec<&CachedTemplate> {
+		let mut templates = self.templates.values().collect::<Vec<_>>();
+		templates.sort_by_key(|cached| {
+			(cached.observation_domain_id, cached.template.header.template_id)
+		});
+		templates
+	}
+
+	/// Writes a JSON snapshot, replacing the file only once the snapshot is complete
+	pub fn save<P : AsRef<Path>>(&self, path : P) -> std::io::Result<()> {
+		let path = path.as_ref();
+		let mut temporary = path.as_os_str().to_owned();
+		temporary.push(".tmp");
+
+		let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+		serde_json::to_writer(&mut writer, self)?;
+		writer.flush()?;
+		std::fs::rename(&temporary, path)
+	}
+
+	/// Restores a snapshot written by `save`
+	pub fn load<P : AsRef<Path>>(path : P) -> std::io::Result<TemplateCache> {
+		let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+		Ok(serde_json::from_reader(reader)?)
+	}
+}
+
+impl Serialize for TemplateCache {
+	fn serialize<S>(&self, s : S) -> Result<S::Ok, S::Error>
+	where
+		S : Serializer,
+	{
+		s.collect_seq(self.templates())
+	}
+}
+
+impl<'de> Deserialize<'de> for TemplateCache {
+	fn deserialize<D>(d : D) -> Result<TemplateCache, D::Error>
+	where
+		D : Deserializer<'de>,
+	{
+		let templates = Vec::<CachedTemplate>::deserialize(d)?;
+		Ok(TemplateCache {
+			templates : templates
+				.into_iter()
+				.map(|cached| {
+					(
+						(cached.observation_domain_id, cached.template.header.template_id),
+						cached,
+					)
+				})
+				.collect(),
+		})
 	}
 }
 
@@ -79,15 +205,15 @@ mod template_cache_tests {
 			scope_fields : vec![],
 			fields : vec![DUMMY_FIELD],
 		};
-		assert_eq!(cache.update_with(template.clone()), Ok(UpdateOk::Addition));
-		assert_eq!(cache.lookup(FIRST_TEMPLATE_ID).unwrap(), &template);
+		assert_eq!(cache.update_with(1, template.clone()), Ok(UpdateOk::Addition));
+		assert_eq!(cache.lookup(1, FIRST_TEMPLATE_ID).unwrap(), &template);
 
 		// identical redefinition
 		assert_eq!(
-			cache.update_with(template.clone()),
+			cache.update_with(1, template.clone()),
 			Ok(UpdateOk::Redefinition)
 		);
-		assert_eq!(cache.lookup(FIRST_TEMPLATE_ID).unwrap(), &template);
+		assert_eq!(cache.lookup(1, FIRST_TEMPLATE_ID).unwrap(), &template);
 
 		let removal = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -98,8 +224,8 @@ mod template_cache_tests {
 			scope_fields : vec![],
 			fields : vec![],
 		};
-		assert_eq!(cache.update_with(removal.clone()), Ok(UpdateOk::Withdrawal));
-		assert!(cache.lookup(FIRST_TEMPLATE_ID).is_none());
+		assert_eq!(cache.update_with(1, removal.clone()), Ok(UpdateOk::Withdrawal));
+		assert!(cache.lookup(1, FIRST_TEMPLATE_ID).is_none());
 	}
 
 	#[test]
@@ -116,22 +242,22 @@ mod template_cache_tests {
 			fields : vec![DUMMY_FIELD],
 		};
 		let mut template2 = template.clone();
-		assert_eq!(cache.update_with(template.clone()), Ok(UpdateOk::Addition));
+		assert_eq!(cache.update_with(1, template.clone()), Ok(UpdateOk::Addition));
 		template2.header.field_count = 2;
 		template2.fields.push(DUMMY_FIELD);
 		assert_eq!(
-			cache.update_with(template2.clone()),
+			cache.update_with(1, template2.clone()),
 			Err(UpdateErr::RedefinitionDifferent)
 		);
 
-		assert!(cache.lookup(FIRST_TEMPLATE_ID).is_none());
+		assert!(cache.lookup(1, FIRST_TEMPLATE_ID).is_none());
 	}
 
 	#[test]
 	fn spurious_withdrawal() {
 		let mut cache = TemplateCache::default();
 		assert_eq!(
-			cache.update_with(TemplateRecord {
+			cache.update_with(1, TemplateRecord {
 				header : TemplateRecordHeader {
 					template_id : FIRST_TEMPLATE_ID,
 					field_count : 0,
@@ -142,14 +268,14 @@ mod template_cache_tests {
 			}),
 			Err(UpdateErr::WithdrawalUnknown)
 		);
-		assert!(cache.lookup(FIRST_TEMPLATE_ID).is_none());
+		assert!(cache.lookup(1, FIRST_TEMPLATE_ID).is_none());
 	}
 
 	#[test]
 	#[should_panic(expected = "assertion failed")]
 	fn panic_on_nontemplate() {
 		let mut cache = TemplateCache::default();
-		let _res = cache.update_with(TemplateRecord {
+		let _res = cache.update_with(1, TemplateRecord {
 			header : TemplateRecordHeader {
 				template_id : 0,
 				field_count : 0,
@@ -164,7 +290,7 @@ mod template_cache_tests {
 	#[should_panic(expected = "assertion failed")]
 	fn panic_on_wrong_size() {
 		let mut cache = TemplateCache::default();
-		let _res = cache.update_with(TemplateRecord {
+		let _res = cache.update_with(1, TemplateRecord {
 			header : TemplateRecordHeader {
 				template_id : 0,
 				field_count : 1,
@@ -174,6 +300,90 @@ mod template_cache_tests {
 			fields : vec![],
 		});
 	}
+
+	fn template(template_id : u16) -> TemplateRecord {
+		TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id,
+				field_count : 1,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields : vec![DUMMY_FIELD],
+		}
+	}
+
+	#[test]
+	fn observation_domain_test() {
+		let mut cache = TemplateCache::default();
+		assert_eq!(cache.update_with(1, template(256)), Ok(UpdateOk::Addition));
+		assert_eq!(cache.update_with(2, template(256)), Ok(UpdateOk::Addition));
+		assert!(cache.lookup(3, 256).is_none());
+
+		assert_eq!(cache.update_with(1, TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 256,
+				field_count : 0,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields : vec![],
+		}), Ok(UpdateOk::Withdrawal));
+		assert!(cache.lookup(1, 256).is_none());
+		assert_eq!(cache.lookup(2, 256), Some(&template(256)));
+	}
+
+	#[test]
+	fn lookup_planned_test() {
+		let mut cache = TemplateCache::default();
+		let mut registry = Registry::default();
+		let options = DecoderOptions::strict();
+		let mut template = template(256);
+		template.fields[0].enterprise_number = Some(6871);
+		cache.update_with(1, template).unwrap();
+
+		let error = VerifyTemplateError::EnterpriseInformationElementNotFound {
+			enterprise_number : 6871,
+			id : DUMMY_FIELD.information_element_id,
+		};
+		let planned = cache.lookup_planned(1, 256, &registry, &options);
+		assert_eq!(planned.map(|planned| planned.err()), Some(Some(error)));
+		assert_eq!(cache.lookup_cached(1, 256).unwrap().plan, Some(Err(error)));
+
+		// kept until the plans are cleared
+		let mut information_element = registry.lookup_field(&DUMMY_FIELD).unwrap().clone();
+		information_element.enterprise_number = Some(6871);
+		registry.insert(information_element);
+		assert!(cache.lookup_planned(1, 256, &registry, &options).unwrap().is_err());
+		cache.clear_plans();
+		assert!(cache.lookup_planned(1, 256, &registry, &options).unwrap().is_ok());
+	}
+
+	#[test]
+	fn snapshot_test() {
+		let mut cache = TemplateCache::default();
+		cache.update_with(2, template(257)).unwrap();
+		cache.update_with(1, template(256)).unwrap();
+		cache.update_with(2, template(256)).unwrap();
+
+		let ids = cache
+			.templates()
+			.iter()
+			.map(|cached| (cached.observation_domain_id, cached.template.header.template_id))
+			.collect::<Vec<_>>();
+		assert_eq!(ids, vec![(1, 256), (2, 256), (2, 257)]);
+
+		let path = std::env::temp_dir()
+			.join(format!("libipfix-templates-{}.json", std::process::id()));
+		cache.save(&path).unwrap();
+		let restored = TemplateCache::load(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(restored, cache);
+		assert_eq!(
+			restored.lookup_cached(2, 257).unwrap().received,
+			cache.lookup_cached(2, 257).unwrap().received
+		);
+	}
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -189,6 +399,11 @@ pub enum VerifyTemplateError {
 		fields_len : usize,
 	},
 	InformationElementIdNotFound(u16),
+	/// may be described later by type options records, see `type_options`
+	EnterpriseInformationElementNotFound {
+		enterprise_number : u32,
+		id : u16,
+	},
 	FieldLengthInvalid(u16),
 	FieldLengthMismatch {
 		length : u16,
@@ -199,7 +414,6 @@ pub enum VerifyTemplateError {
 		type_ : AbstractDataType,
 	},
 	TypeNotImplemented(AbstractDataType),
-	EnterpriseNumbersNotImplemented,
 }
 
 impl std::fmt::Display for VerifyTemplateError {
@@ -229,6 +443,14 @@ impl std::fmt::Display for VerifyTemplateError {
 			InformationElementIdNotFound(id) => {
 				write!(f, "information element with id {} not found", id)
 			}
+			EnterpriseInformationElementNotFound {
+				enterprise_number,
+				id,
+			} => write!(
+				f,
+				"information element with id {} of enterprise {} not found",
+				id, enterprise_number
+			),
 			FieldLengthInvalid(len) => write!(f, "field length {} is invalid", len),
 			FieldLengthMismatch { length, type_ } => write!(
 				f,
@@ -239,12 +461,43 @@ impl std::fmt::Display for VerifyTemplateError {
 				write!(f, "length {} not implemented for type {}", type_, length)
 			}
 			TypeNotImplemented(type_) => write!(f, "type {} not implemented", type_),
-			EnterpriseNumbersNotImplemented => write!(f, "enterprise numbers are not implemented"),
 		}
 	}
 }
 
-pub fn verify_template(template : &TemplateRecord) -> Result<(), VerifyTemplateError> {
+pub fn verify_template(
+	template : &TemplateRecord,
+	registry : &Registry,
+) -> Result<(), VerifyTemplateError> {
+	verify_template_with(template, registry, &DecoderOptions::strict())
+}
+
+/// Like `verify_template_with`, but accepts enterprise information elements missing from
+/// the registry, which type options records may describe later
+pub fn verify_deferred_template(
+	template : &TemplateRecord,
+	registry : &Registry,
+	options : &DecoderOptions,
+) -> Result<(), VerifyTemplateError> {
+	let keep_raw = DecoderOptions {
+		unknown_fields : UnknownFieldPolicy::KeepRaw,
+		..*options
+	};
+	verify_template_with(template, registry, &keep_raw)?;
+	for field in template.scope_fields.iter().chain(template.fields.iter()) {
+		if field.enterprise_number.is_none() {
+			verify_field_specifier(field, registry, options)?;
+		}
+	}
+	Ok(())
+}
+
+/// Accepts the fields that `options` allows to decode as raw octets
+pub fn verify_template_with(
+	template : &TemplateRecord,
+	registry : &Registry,
+	options : &DecoderOptions,
+) -> Result<(), VerifyTemplateError> {
 	use VerifyTemplateError::*;
 
 	if template.header.field_count == 0 {
@@ -268,27 +521,51 @@ pub fn verify_template(template : &TemplateRecord) -> Result<(), VerifyTemplateE
 	}
 
 	for field in template.scope_fields.iter().chain(template.fields.iter()) {
-		verify_field_specifier(field)?;
+		verify_field_specifier(field, registry, options)?;
 	}
 
 	Ok(())
 }
 
-fn verify_field_specifier(field : &FieldSpecifier) -> Result<(), VerifyTemplateError> {
-	use AbstractDataType::*;
+pub(crate) fn verify_field_specifier(
+	field : &FieldSpecifier,
+	registry : &Registry,
+	options : &DecoderOptions,
+) -> Result<(), VerifyTemplateError> {
 	use VerifyTemplateError::*;
 
-	let information_element = information_element::lookup(field.information_element_id)
-		.ok_or_else(|| (InformationElementIdNotFound(field.information_element_id)))?;
-
 	if field.field_length == 0 {
 		return Err(FieldLengthInvalid(field.field_length));
 	}
 
-	let type_ = information_element.abstract_data_type;
-	let length = field.field_length;
+	let information_element = match (registry.lookup_field(field), options.unknown_fields) {
+		(Some(information_element), _) => information_element,
+		// any length is valid for octet arrays
+		(None, UnknownFieldPolicy::KeepRaw) => return Ok(()),
+		(None, UnknownFieldPolicy::Reject) => {
+			return Err(match field.enterprise_number {
+				Some(enterprise_number) => EnterpriseInformationElementNotFound {
+					enterprise_number,
+					id : field.information_element_id,
+				},
+				None => InformationElementIdNotFound(field.information_element_id),
+			})
+		}
+	};
+
+	match verify_field_length(information_element.abstract_data_type, field.field_length) {
+		Err(_) if options.raw_field_lengths => Ok(()),
+		result => result,
+	}
+}
+
+pub(crate) fn verify_field_length(
+	type_ : AbstractDataType,
+	length : u16,
+) -> Result<(), VerifyTemplateError> {
+	use AbstractDataType::*;
+	use VerifyTemplateError::*;
 
-	// check length
 	match type_ {
 		// different lengths not implemented
 		Unsigned8 | Signed8 | Boolean => match length {
@@ -332,16 +609,19 @@ fn verify_field_specifier(field : &FieldSpecifier) -> Result<(), VerifyTemplateE
 		},
 		BasicList | SubTemplateList | SubTemplateMultiList => return Err(TypeNotImplemented(type_)),
 	};
-
-	match field.enterprise_number {
-		Some(_) => Err(EnterpriseNumbersNotImplemented),
-		None => Ok(()),
-	}
+	Ok(())
 }
 
 #[cfg(test)]
 mod verify_template_tests {
 	use super::*;
+	const STRICT : DecoderOptions = DecoderOptions {
+		unknown_fields : UnknownFieldPolicy::Reject,
+		raw_field_lengths : false,
+		raw_booleans : false,
+		lossy_strings : false,
+		skip_trailing_octets : false,
+	};
 	const DUMMY_FIELD : FieldSpecifier = FieldSpecifier {
 		information_element_id : 210,
 		field_length : 4,
@@ -359,7 +639,7 @@ mod verify_template_tests {
 			scope_fields : vec![],
 			fields : vec![DUMMY_FIELD],
 		};
-		assert!(verify_template(&template).is_ok());
+		assert!(verify_template(&template, &Registry::default()).is_ok());
 
 		let template = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -370,7 +650,7 @@ mod verify_template_tests {
 			scope_fields : vec![DUMMY_FIELD],
 			fields : vec![],
 		};
-		assert!(verify_template(&template).is_ok());
+		assert!(verify_template(&template, &Registry::default()).is_ok());
 
 		let template = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -381,7 +661,7 @@ mod verify_template_tests {
 			scope_fields : vec![DUMMY_FIELD],
 			fields : vec![DUMMY_FIELD],
 		};
-		assert!(verify_template(&template).is_ok());
+		assert!(verify_template(&template, &Registry::default()).is_ok());
 
 		let template = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -392,7 +672,7 @@ mod verify_template_tests {
 			scope_fields : vec![],
 			fields : vec![],
 		};
-		assert!(verify_template(&template).is_err());
+		assert!(verify_template(&template, &Registry::default()).is_err());
 
 		let template = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -403,7 +683,7 @@ mod verify_template_tests {
 			scope_fields : vec![],
 			fields : vec![],
 		};
-		assert!(verify_template(&template).is_err());
+		assert!(verify_template(&template, &Registry::default()).is_err());
 
 		let template = TemplateRecord {
 			header : TemplateRecordHeader {
@@ -414,39 +694,92 @@ mod verify_template_tests {
 			scope_fields : vec![DUMMY_FIELD],
 			fields : vec![],
 		};
-		assert!(verify_template(&template).is_err());
+		assert!(verify_template(&template, &Registry::default()).is_err());
 	}
 
 	#[test]
 	fn verify_field_specifier_test() {
-		assert!(verify_field_specifier(&DUMMY_FIELD).is_ok());
+		assert!(verify_field_specifier(&DUMMY_FIELD, &Registry::default(), &STRICT).is_ok());
 
 		let field = FieldSpecifier {
 			information_element_id : 0xffff,
 			field_length : 1,
 			enterprise_number : None,
 		};
-		assert!(verify_field_specifier(&field).is_err());
+		assert!(verify_field_specifier(&field, &Registry::default(), &STRICT).is_err());
 
 		let field = FieldSpecifier {
 			information_element_id : 210,
 			field_length : 0,
 			enterprise_number : None,
 		};
-		assert!(verify_field_specifier(&field).is_err());
+		assert!(verify_field_specifier(&field, &Registry::default(), &STRICT).is_err());
 
 		let field = FieldSpecifier {
 			information_element_id : 210,
 			field_length : 1,
 			enterprise_number : Some(32473),
 		};
-		assert!(verify_field_specifier(&field).is_err());
+		assert!(verify_field_specifier(&field, &Registry::default(), &STRICT).is_err());
 
 		let field = FieldSpecifier {
 			information_element_id : 1,
 			field_length : FIRST_TEMPLATE_ID,
 			enterprise_number : None,
 		};
-		assert!(verify_field_specifier(&field).is_err());
+		assert!(verify_field_specifier(&field, &Registry::default(), &STRICT).is_err());
+	}
+
+	#[test]
+	fn verify_unknown_fields_test() {
+		let template = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : FIRST_TEMPLATE_ID,
+				scope_field_count : 0,
+				field_count : 1,
+			},
+			scope_fields : vec![],
+			fields : vec![FieldSpecifier {
+				information_element_id : 18,
+				field_length : 3,
+				enterprise_number : Some(6871),
+			}],
+		};
+		let registry = Registry::default();
+		assert_eq!(
+			verify_template(&template, &registry),
+			Err(VerifyTemplateError::EnterpriseInformationElementNotFound {
+				enterprise_number : 6871,
+				id : 18,
+			})
+		);
+		let options = DecoderOptions {
+			unknown_fields : UnknownFieldPolicy::KeepRaw,
+			..DecoderOptions::strict()
+		};
+		assert!(verify_template_with(&template, &registry, &options).is_ok());
+
+		assert!(verify_deferred_template(&template, &registry, &STRICT).is_ok());
+		let mut invalid = template.clone();
+		invalid.header.field_count = 2;
+		// sourceIPv4Address of 3 octets
+		invalid.fields.push(FieldSpecifier {
+			information_element_id : 8,
+			field_length : 3,
+			enterprise_number : None,
+		});
+		assert_eq!(
+			verify_deferred_template(&invalid, &registry, &STRICT),
+			Err(VerifyTemplateError::FieldLengthMismatch {
+				length : 3,
+				type_ : AbstractDataType::Ipv4Address,
+			})
+		);
+		invalid.fields[1].information_element_id = 32767;
+		invalid.fields[1].field_length = 4;
+		assert_eq!(
+			verify_deferred_template(&invalid, &registry, &STRICT),
+			Err(VerifyTemplateError::InformationElementIdNotFound(32767))
+		);
 	}
 }
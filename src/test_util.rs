@@ -0,0 +1,22 @@
+use structs::*;
+
+/// Template 256 of IANA fields given as id and length, not verified
+/// so tests can use unknown elements and reduced lengths
+pub(crate) fn template(fields : &[(u16, u16)]) -> TemplateRecord {
+	TemplateRecord {
+		header : TemplateRecordHeader {
+			template_id : 256,
+			scope_field_count : 0,
+			field_count : fields.len() as u16,
+		},
+		scope_fields : vec![],
+		fields : fields
+			.iter()
+			.map(|&(information_element_id, field_length)| FieldSpecifier {
+				information_element_id,
+				field_length,
+				enterprise_number : None,
+			})
+			.collect(),
+	}
+}
@@ -0,0 +1,409 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use structs::*;
+
+#[cfg(feature = "chrono")]
+use chrono;
+
+/// Seconds from the NTP epoch 1900-01-01 to the Unix epoch 1970-01-01
+pub const NTP_UNIX_EPOCH_OFFSET : u64 = 2_208_988_800;
+
+// NTP seconds wrap in February 2036, see RFC 4330 section 3
+const NTP_ERA : u64 = 1 << 32;
+const NTP_ERA_1_BIT : u32 = 0x8000_0000;
+
+// lower 11 bits of dateTimeMicroseconds fractions are ignored, RFC 7011 section 6.1.9
+const MICROSECONDS_FRACTION_MASK : u32 = 0xFFFF_F800;
+
+impl DataValue {
+	/// Time since the Unix epoch of a `dateTime*` value.
+	/// `dateTimeSeconds` and `dateTimeMilliseconds` count from the Unix epoch,
+	/// `dateTimeMicroseconds` and `dateTimeNanoseconds` are NTP timestamps counting from 1900.
+	/// NTP seconds with the most significant bit unset are taken to be after February 2036.
+	pub fn as_unix_duration(&self) -> Option<Duration> {
+		use DataValue::*;
+
+		match *self {
+			DateTimeSeconds(seconds) => Some(Duration::from_secs(seconds as u64)),
+			DateTimeMilliseconds(milliseconds) => Some(Duration::from_millis(milliseconds)),
+			DateTimeMicroseconds { seconds, fraction } => {
+				let microseconds = round_fraction(fraction & MICROSECONDS_FRACTION_MASK, 1_000_000);
+				ntp_seconds(seconds).map(|seconds| {
+					Duration::from_secs(seconds) + Duration::from_micros(microseconds)
+				})
+			}
+			DateTimeNanoseconds { seconds, fraction } => {
+				let nanoseconds = round_fraction(fraction, 1_000_000_000);
+				ntp_seconds(seconds).map(|seconds| {
+					Duration::from_secs(seconds) + Duration::from_nanos(nanoseconds)
+				})
+			}
+			_ => None,
+		}
+	}
+
+	pub fn as_system_time(&self) -> Option<SystemTime> {
+		self.as_unix_duration().map(|duration| UNIX_EPOCH + duration)
+	}
+
+	/// `dateTime*` value of the given type, `None` for other types
+	/// or times not representable in it
+	pub fn from_system_time(
+		abstract_data_type : AbstractDataType,
+		time : SystemTime,
+	) -> Option<DataValue> {
+		let duration = time.duration_since(UNIX_EPOCH).ok()?;
+		let seconds = duration.as_secs();
+		let nanoseconds = duration.subsec_nanos() as u64;
+
+		match abstract_data_type {
+			AbstractDataType::DateTimeSeconds if seconds <= u32::MAX as u64 => {
+				Some(DataValue::DateTimeSeconds(seconds as u32))
+			}
+			AbstractDataType::DateTimeMilliseconds => Some(DataValue::DateTimeMilliseconds(
+				seconds.checked_mul(1000)? + nanoseconds / 1_000_000,
+			)),
+			AbstractDataType::DateTimeMicroseconds => {
+				let fraction = ((nanoseconds / 1000) << 32) / 1_000_000;
+				Some(DataValue::DateTimeMicroseconds {
+					seconds : to_ntp_seconds(seconds)?,
+					fraction : fraction as u32 & MICROSECONDS_FRACTION_MASK,
+				})
+			}
+			AbstractDataType::DateTimeNanoseconds => {
+				let fraction = (nanoseconds << 32) / 1_000_000_000;
+				Some(DataValue::DateTimeNanoseconds {
+					seconds : to_ntp_seconds(seconds)?,
+					fraction : fraction as u32,
+				})
+			}
+			_ => None,
+		}
+	}
+
+	/// RFC 3339 UTC timestamp of a `dateTime*` value
+	/// with the precision of its type, e.g. `2018-02-17T10:49:50.123Z`
+	pub fn to_rfc3339(&self) -> Option<String> {
+		use DataValue::*;
+
+		let digits = match *self {
+			DateTimeSeconds(_) => 0,
+			DateTimeMilliseconds(_) => 3,
+			DateTimeMicroseconds { .. } => 6,
+			DateTimeNanoseconds { .. } => 9,
+			_ => return None,
+		};
+		self.as_unix_duration()
+			.map(|duration| format_rfc3339(duration, digits))
+	}
+
+	/// `dateTime*` value of the given type from an RFC 3339 timestamp like
+	/// `2018-02-17T10:49:50.123Z` or `2018-02-17T11:49:50.123+01:00`,
+	/// digits beyond the precision of the type are truncated
+	pub fn from_rfc3339(
+		abstract_data_type : AbstractDataType,
+		rfc3339 : &str,
+	) -> Option<DataValue> {
+		DataValue::from_system_time(abstract_data_type, parse_rfc3339(rfc3339)?)
+	}
+
+	#[cfg(feature = "chrono")]
+	pub fn as_date_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+		self.as_unix_duration().and_then(|duration| {
+			chrono::DateTime::from_timestamp(duration.as_secs() as i64, duration.subsec_nanos())
+		})
+	}
+}
+
+// seconds since the Unix epoch of NTP seconds
+fn ntp_seconds(seconds : u32) -> Option<u64> {
+	let seconds = if seconds & NTP_ERA_1_BIT == 0 {
+		seconds as u64 + NTP_ERA
+	} else {
+		seconds as u64
+	};
+	seconds.checked_sub(NTP_UNIX_EPOCH_OFFSET)
+}
+
+fn to_ntp_seconds(seconds : u64) -> Option<u32> {
+	let seconds = seconds + NTP_UNIX_EPOCH_OFFSET;
+	// era 0 before 2036 must have the most significant bit set, era 1 must not
+	if seconds < NTP_ERA && seconds as u32 & NTP_ERA_1_BIT != 0
+		|| seconds >= NTP_ERA && seconds - NTP_ERA < NTP_ERA_1_BIT as u64
+	{
+		Some(seconds as u32)
+	} else {
+		None
+	}
+}
+
+fn round_fraction(fraction : u32, units_per_second : u64) -> u64 {
+	((fraction as u64 * units_per_second) + (1 << 31)) >> 32
+}
+
+pub(crate) fn format_rfc3339(duration : Duration, digits : usize) -> String {
+	let seconds = duration.as_secs();
+	let (year, month, day) = civil_from_days(seconds / 86400);
+	let time = seconds % 86400;
+	let mut rfc3339 = format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+		year,
+		month,
+		day,
+		time / 3600,
+		time / 60 % 60,
+		time % 60
+	);
+	if digits > 0 {
+		let nanoseconds = format!("{:09}", duration.subsec_nanos());
+		rfc3339.push('.');
+		rfc3339.push_str(&nanoseconds[..digits]);
+	}
+	rfc3339.push('Z');
+	rfc3339
+}
+
+fn parse_rfc3339(rfc3339 : &str) -> Option<SystemTime> {
+	let bytes = rfc3339.as_bytes();
+	let number = |start : usize, digits : usize| -> Option<u64> {
+		let slice = bytes.get(start..start + digits)?;
+		if !slice.iter().all(u8::is_ascii_digit) {
+			return None;
+		}
+		rfc3339[start..start + digits].parse().ok()
+	};
+	let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+	if !separators.iter().all(|&(index, separator)| bytes.get(index) == Some(&separator))
+		|| !matches!(bytes.get(10), Some(b'T') | Some(b't') | Some(b' '))
+	{
+		return None;
+	}
+	let (year, month, day) = (number(0, 4)?, number(5, 2)?, number(8, 2)?);
+	let (hour, minute, second) = (number(11, 2)?, number(14, 2)?, number(17, 2)?);
+	if !(1..=12).contains(&month)
+		|| !(1..=31).contains(&day)
+		|| hour > 23
+		|| minute > 59
+		|| second > 60
+	{
+		return None;
+	}
+
+	let mut index = 19;
+	let mut nanoseconds = 0;
+	if bytes.get(index) == Some(&b'.') {
+		let digits = bytes[index + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+		if digits == 0 {
+			return None;
+		}
+		let kept = digits.min(9);
+		nanoseconds = number(index + 1, kept)? * 10u64.pow(9 - kept as u32);
+		index += 1 + digits;
+	}
+	let offset = match bytes.get(index) {
+		Some(b'Z') | Some(b'z') if bytes.len() == index + 1 => 0,
+		Some(&sign) if (sign == b'+' || sign == b'-') && bytes.len() == index + 6 => {
+			if bytes[index + 3] != b':' {
+				return None;
+			}
+			let (hours, minutes) = (number(index + 1, 2)?, number(index + 4, 2)?);
+			if hours > 23 || minutes > 59 {
+				return None;
+			}
+			let offset = (hours * 3600 + minutes * 60) as i64;
+			if sign == b'+' {
+				offset
+			} else {
+				-offset
+			}
+		}
+		_ => return None,
+	};
+
+	let days = days_from_civil(year, month, day);
+	let seconds = days * 86400 + (hour * 3600 + minute * 60 + second) as i64 - offset;
+	if seconds < 0 {
+		return None;
+	}
+	Some(UNIX_EPOCH + Duration::new(seconds as u64, nanoseconds as u32))
+}
+
+// year, month, day to days since 1970-01-01
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year : u64, month : u64, day : u64) -> i64 {
+	let year = if month <= 2 { year as i64 - 1 } else { year as i64 };
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let month = month as i64;
+	let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+	let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146_097 + day_of_era - 719_468
+}
+
+// days since 1970-01-01 to year, month, day
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub(crate) fn civil_from_days(days : u64) -> (u64, u64, u64) {
+	let z = days + 719_468;
+	let era = z / 146_097;
+	let day_of_era = z - era * 146_097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn as_system_time_test() {
+		use DataValue::*;
+
+		let unix = |seconds, nanoseconds| UNIX_EPOCH + Duration::new(seconds, nanoseconds);
+
+		assert_eq!(
+			DateTimeSeconds(1_518_864_590).as_system_time(),
+			Some(unix(1_518_864_590, 0))
+		);
+		assert_eq!(
+			DateTimeMilliseconds(1_518_864_590_123).as_system_time(),
+			Some(unix(1_518_864_590, 123_000_000))
+		);
+		// 2018-02-17T10:49:50.5Z
+		assert_eq!(
+			DateTimeNanoseconds {
+				seconds : 3_727_853_390,
+				fraction : 0x8000_0000,
+			}.as_system_time(),
+			Some(unix(1_518_864_590, 500_000_000))
+		);
+		assert_eq!(
+			DateTimeMicroseconds {
+				seconds : 3_727_853_390,
+				fraction : 0x0000_1000,
+			}.as_system_time(),
+			Some(unix(1_518_864_590, 1000))
+		);
+		// 0.95 microseconds unless the lower 11 bits are ignored
+		assert_eq!(
+			DateTimeMicroseconds {
+				seconds : 3_727_853_390,
+				fraction : 0x0000_0fff,
+			}.as_system_time(),
+			Some(unix(1_518_864_590, 0))
+		);
+		// era 1 starts 2036-02-07T06:28:16Z
+		assert_eq!(
+			DateTimeNanoseconds {
+				seconds : 0,
+				fraction : 0,
+			}.as_system_time(),
+			Some(unix(2_085_978_496, 0))
+		);
+		assert_eq!(Unsigned32(0).as_system_time(), None);
+	}
+
+	#[test]
+	fn from_system_time_test() {
+		let time = UNIX_EPOCH + Duration::new(1_518_864_590, 123_456_789);
+		for &abstract_data_type in &[
+			AbstractDataType::DateTimeSeconds,
+			AbstractDataType::DateTimeMilliseconds,
+			AbstractDataType::DateTimeMicroseconds,
+			AbstractDataType::DateTimeNanoseconds,
+		] {
+			let value = DataValue::from_system_time(abstract_data_type, time).unwrap();
+			let round_trip = value.as_system_time().unwrap();
+			let error = time.duration_since(round_trip).unwrap_or_else(|e| e.duration());
+			assert!(error < Duration::from_secs(1), "{:?}", value);
+		}
+		assert_eq!(
+			DataValue::from_system_time(AbstractDataType::DateTimeMicroseconds, time)
+				.unwrap()
+				.as_system_time(),
+			Some(UNIX_EPOCH + Duration::new(1_518_864_590, 123_456_000))
+		);
+		assert_eq!(
+			DataValue::from_system_time(AbstractDataType::Unsigned32, time),
+			None
+		);
+	}
+
+	#[test]
+	fn rfc3339_test() {
+		use DataValue::*;
+
+		assert_eq!(
+			DateTimeSeconds(0).to_rfc3339(),
+			Some("1970-01-01T00:00:00Z".to_string())
+		);
+		assert_eq!(
+			DateTimeMilliseconds(1_518_864_590_123).to_rfc3339(),
+			Some("2018-02-17T10:49:50.123Z".to_string())
+		);
+		assert_eq!(
+			DateTimeNanoseconds {
+				seconds : 3_727_853_390,
+				fraction : 0x8000_0000,
+			}.to_rfc3339(),
+			Some("2018-02-17T10:49:50.500000000Z".to_string())
+		);
+		assert_eq!(
+			DateTimeSeconds(951_782_400).to_rfc3339(),
+			Some("2000-02-29T00:00:00Z".to_string())
+		);
+		assert_eq!(Unsigned32(0).to_rfc3339(), None);
+	}
+
+	#[test]
+	fn from_rfc3339_test() {
+		use AbstractDataType as T;
+		use DataValue::*;
+
+		assert_eq!(
+			DataValue::from_rfc3339(T::DateTimeMilliseconds, "2018-02-17T10:49:50.123Z"),
+			Some(DateTimeMilliseconds(1_518_864_590_123))
+		);
+		assert_eq!(
+			DataValue::from_rfc3339(T::DateTimeSeconds, "2018-02-17t11:49:50.999+01:00"),
+			Some(DateTimeSeconds(1_518_864_590))
+		);
+		assert_eq!(
+			DataValue::from_rfc3339(T::DateTimeNanoseconds, "2018-02-17T10:49:50.500000000Z"),
+			Some(DateTimeNanoseconds {
+				seconds : 3_727_853_390,
+				fraction : 0x8000_0000,
+			})
+		);
+		assert_eq!(
+			DataValue::from_rfc3339(T::DateTimeSeconds, "2000-02-29T00:00:00Z"),
+			Some(DateTimeSeconds(951_782_400))
+		);
+		for invalid in &[
+			"2018-02-17T10:49:50",
+			"2018-02-17T10:49:50.Z",
+			"2018-13-17T10:49:50Z",
+			"2018-02-17T10:49:50+0100",
+			"2018-02-17T10:49:50+24:00",
+			"2018-02-17T10:49:50-01:60",
+			"1969-12-31T23:59:59Z",
+		] {
+			assert_eq!(DataValue::from_rfc3339(T::DateTimeSeconds, invalid), None);
+		}
+		assert_eq!(DataValue::from_rfc3339(T::Unsigned32, "2018-02-17T10:49:50Z"), None);
+	}
+
+	#[cfg(feature = "chrono")]
+	#[test]
+	fn as_date_time_test() {
+		let date_time = DataValue::DateTimeMilliseconds(1_518_864_590_123)
+			.as_date_time()
+			.unwrap();
+		assert_eq!(date_time.timestamp_millis(), 1_518_864_590_123);
+	}
+}
@@ -0,0 +1,313 @@
+use information_element::*;
+use std;
+use std::borrow::Cow;
+use structs::*;
+
+// Information Element Type Options (RFC 5610) describe enterprise information elements in-band.
+// Codes are those of the IANA data type, semantics and units registries.
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TypeOptionsError {
+	Missing(&'static str),
+	UnknownValue {
+		field : &'static str,
+		value : u64,
+	},
+}
+
+impl std::fmt::Display for TypeOptionsError {
+	fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		use TypeOptionsError::*;
+
+		match *self {
+			Missing(field) => write!(f, "type options record without {}", field),
+			UnknownValue { field, value } => {
+				write!(f, "type options record has unknown {} {}", field, value)
+			}
+		}
+	}
+}
+
+/// Options templates whose records describe information elements
+pub fn is_type_options_template(template : &TemplateRecord) -> bool {
+	let has = |id| {
+		template
+			.scope_fields
+			.iter()
+			.chain(template.fields.iter())
+			.any(|field| field.information_element_id == id && field.enterprise_number.is_none())
+	};
+	has(ie::INFORMATION_ELEMENT_ID) && has(ie::INFORMATION_ELEMENT_DATA_TYPE)
+}
+
+/// Information element described by a type options record.
+/// Without `informationElementName` it is named like its JSON key, e.g. `6871:18`.
+pub fn information_element_from_type_record(
+	record : &TypedDataRecord,
+) -> Result<InformationElement, TypeOptionsError> {
+	use information_element::DataTypeSemantics::*;
+	use information_element::Units::*;
+	use structs::AbstractDataType::*;
+
+	let unsigned = |id| record.get(id).and_then(DataValue::as_u64);
+	let unknown = |field, value| TypeOptionsError::UnknownValue { field, value };
+
+	let id = unsigned(ie::INFORMATION_ELEMENT_ID)
+		.ok_or(TypeOptionsError::Missing("informationElementId"))?;
+	if id > 0x7fff {
+		return Err(unknown("information element id", id));
+	}
+	let id = id as u16;
+	let enterprise_number = match unsigned(ie::PRIVATE_ENTERPRISE_NUMBER) {
+		None | Some(0) => None,
+		Some(enterprise_number) if enterprise_number <= u32::MAX as u64 => {
+			Some(enterprise_number as u32)
+		}
+		Some(enterprise_number) => return Err(unknown("enterprise number", enterprise_number)),
+	};
+
+	let abstract_data_type = match unsigned(ie::INFORMATION_ELEMENT_DATA_TYPE)
+		.ok_or(TypeOptionsError::Missing("informationElementDataType"))?
+	{
+		0 => OctetArray,
+		1 => Unsigned8,
+		2 => Unsigned16,
+		3 => Unsigned32,
+		4 => Unsigned64,
+		5 => Signed8,
+		6 => Signed16,
+		7 => Signed32,
+		8 => Signed64,
+		9 => Float32,
+		10 => Float64,
+		11 => Boolean,
+		12 => MacAddress,
+		13 => String,
+		14 => DateTimeSeconds,
+		15 => DateTimeMilliseconds,
+		16 => DateTimeMicroseconds,
+		17 => DateTimeNanoseconds,
+		18 => Ipv4Address,
+		19 => Ipv6Address,
+		20 => BasicList,
+		21 => SubTemplateList,
+		22 => SubTemplateMultiList,
+		data_type => return Err(unknown("data type", data_type)),
+	};
+	let data_type_semantics = match unsigned(ie::INFORMATION_ELEMENT_SEMANTICS) {
+		None | Some(0) => None,
+		Some(1) => Some(Quantity),
+		Some(2) => Some(TotalCounter),
+		Some(3) => Some(DeltaCounter),
+		Some(4) => Some(Identifier),
+		Some(5) => Some(Flags),
+		Some(6) => Some(List),
+		Some(7) => Some(SnmpCounter),
+		Some(8) => Some(SnmpGauge),
+		Some(semantics) => return Err(unknown("data type semantics", semantics)),
+	};
+	let units = match unsigned(ie::INFORMATION_ELEMENT_UNITS) {
+		None | Some(0) => None,
+		Some(1) => Some(Bits),
+		Some(2) => Some(Octets),
+		Some(3) => Some(Packets),
+		Some(4) => Some(Flows),
+		Some(5) => Some(Seconds),
+		Some(6) => Some(Milliseconds),
+		Some(7) => Some(Microseconds),
+		Some(8) => Some(Nanoseconds),
+		Some(9) => Some(FourOctetWords),
+		Some(10) => Some(Messages),
+		Some(11) => Some(Hops),
+		Some(12) => Some(Entries),
+		Some(13) => Some(Frames),
+		Some(14) => Some(Ports),
+		Some(15) => Some(Inferred),
+		Some(units) => return Err(unknown("units", units)),
+	};
+	let range = match (
+		unsigned(ie::INFORMATION_ELEMENT_RANGE_BEGIN),
+		unsigned(ie::INFORMATION_ELEMENT_RANGE_END),
+	) {
+		(Some(begin), Some(end)) => Some((begin, end)),
+		_ => None,
+	};
+	let name = match record.get(ie::INFORMATION_ELEMENT_NAME).and_then(DataValue::as_str) {
+		Some(name) if !name.is_empty() => name.to_string(),
+		_ => match enterprise_number {
+			Some(enterprise_number) => format!("{}:{}", enterprise_number, id),
+			None => format!("{}", id),
+		},
+	};
+	let description = record
+		.get(ie::INFORMATION_ELEMENT_DESCRIPTION)
+		.and_then(DataValue::as_str)
+		.unwrap_or("")
+		.to_string();
+
+	Ok(InformationElement {
+		id,
+		enterprise_number,
+		name : Cow::Owned(name),
+		abstract_data_type,
+		data_type_semantics,
+		units,
+		range,
+		status : Status::Current,
+		description : Cow::Owned(description),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use collect::*;
+	use encode::*;
+	use registry::*;
+	use sink::*;
+	use std::net::Ipv4Addr;
+	use template_builder::*;
+
+	#[derive(Default)]
+	struct NamesSink {
+		names : Vec<Vec<String>>,
+	}
+
+	impl Sink for NamesSink {
+		fn record(
+			&mut self,
+			context : &RecordContext,
+			_record : &DataRecord,
+		) -> std::io::Result<()> {
+			let template = context.template;
+			self.names.push(
+				template
+					.scope_fields
+					.iter()
+					.chain(template.fields.iter())
+					.map(|field| field_name(field, context.registry).unwrap().to_string())
+					.collect(),
+			);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn information_element_test() {
+		let registry = Registry::default();
+		let template = TemplateBuilder::new(256, &registry)
+			.scope_field(ie::INFORMATION_ELEMENT_ID)
+			.scope_field(ie::PRIVATE_ENTERPRISE_NUMBER)
+			.field(ie::INFORMATION_ELEMENT_DATA_TYPE)
+			.field(ie::INFORMATION_ELEMENT_SEMANTICS)
+			.field(ie::INFORMATION_ELEMENT_NAME)
+			.build()
+			.unwrap();
+		assert!(is_type_options_template(&template));
+
+		let mut record = DataRecord {
+			fields : vec![
+				DataValue::Unsigned16(18),
+				DataValue::Unsigned32(6871),
+				DataValue::Unsigned8(18),
+				DataValue::Unsigned8(4),
+				DataValue::String("yafSourceAddress".to_string()),
+			],
+		};
+		let information_element = information_element_from_type_record(&TypedDataRecord {
+			data : &record,
+			template : &template,
+		})
+		.unwrap();
+		assert_eq!(information_element.id, 18);
+		assert_eq!(information_element.enterprise_number, Some(6871));
+		assert_eq!(information_element.name, "yafSourceAddress");
+		assert_eq!(information_element.abstract_data_type, AbstractDataType::Ipv4Address);
+		assert_eq!(
+			information_element.data_type_semantics,
+			Some(DataTypeSemantics::Identifier)
+		);
+
+		record.fields[4] = DataValue::String("".to_string());
+		let information_element = information_element_from_type_record(&TypedDataRecord {
+			data : &record,
+			template : &template,
+		})
+		.unwrap();
+		assert_eq!(information_element.name, "6871:18");
+
+		record.fields[2] = DataValue::Unsigned8(23);
+		assert_eq!(
+			information_element_from_type_record(&TypedDataRecord {
+				data : &record,
+				template : &template,
+			}),
+			Err(TypeOptionsError::UnknownValue {
+				field : "data type",
+				value : 23,
+			})
+		);
+	}
+
+	#[test]
+	fn collector_test() {
+		let registry = Registry::default();
+		let type_options = TemplateBuilder::new(256, &registry)
+			.scope_field(ie::INFORMATION_ELEMENT_ID)
+			.scope_field(ie::PRIVATE_ENTERPRISE_NUMBER)
+			.field(ie::INFORMATION_ELEMENT_DATA_TYPE)
+			.field(ie::INFORMATION_ELEMENT_NAME)
+			.build()
+			.unwrap();
+		let flows = TemplateRecord {
+			header : TemplateRecordHeader {
+				template_id : 257,
+				field_count : 1,
+				scope_field_count : 0,
+			},
+			scope_fields : vec![],
+			fields : vec![FieldSpecifier {
+				information_element_id : 18,
+				field_length : 4,
+				enterprise_number : Some(6871),
+			}],
+		};
+		let header = MessageHeader {
+			version_number : 10,
+			length : 0,
+			export_time : 0,
+			sequence_number : 0,
+			observation_domain_id : 1,
+		};
+		let flow = DataRecord {
+			fields : vec![DataValue::Ipv4Address(Ipv4Addr::new(192, 0, 2, 1))],
+		};
+		let description = DataRecord {
+			fields : vec![
+				DataValue::Unsigned16(18),
+				DataValue::Unsigned32(6871),
+				DataValue::Unsigned8(18),
+				DataValue::String("yafSourceAddress".to_string()),
+			],
+		};
+		let sets = vec![
+			encode_template_set(std::slice::from_ref(&flows)).unwrap(),
+			encode_template_set(std::slice::from_ref(&type_options)).unwrap(),
+			// not decodable before the description
+			encode_data_set(&flows, std::slice::from_ref(&flow)).unwrap(),
+			encode_data_set(&type_options, &[description]).unwrap(),
+			encode_data_set(&flows, &[flow]).unwrap(),
+		];
+		let message = encode_message(&header, &sets).unwrap();
+
+		let mut collector = Collector::default();
+		let mut sink = NamesSink::default();
+		collector.collect(&mut &message[..], &mut sink);
+		assert_eq!(sink.names.len(), 2);
+		assert_eq!(sink.names[1], vec!["yafSourceAddress".to_string()]);
+		assert_eq!(
+			collector.registry.lookup_enterprise(6871, 18).unwrap().abstract_data_type,
+			AbstractDataType::Ipv4Address
+		);
+	}
+}
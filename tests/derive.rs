@@ -0,0 +1,176 @@
+extern crate libipfix;
+extern crate libipfix_derive;
+
+use libipfix::*;
+use libipfix_derive::IpfixRecord;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, IpfixRecord, PartialEq)]
+struct Flow {
+	#[ipfix(ie = 8)]
+	src : Ipv4Addr,
+	#[ipfix(name = "destinationIPv4Address")]
+	dst : Ipv4Addr,
+	#[ipfix(name = "octetDeltaCount")]
+	bytes : u64,
+	#[ipfix(ie = 82)]
+	interface_name : String,
+	#[ipfix(ie = 18, enterprise = 6871)]
+	flags : Option<u8>,
+}
+
+#[derive(Debug, IpfixRecord, PartialEq)]
+struct Times {
+	#[ipfix(name = "flowStartSeconds")]
+	start : SystemTime,
+	#[ipfix(ie = 153)]
+	end : Option<SystemTime>,
+}
+
+// unsigned32
+#[derive(Debug, IpfixRecord, PartialEq)]
+struct WideInterface {
+	#[ipfix(name = "ingressInterface")]
+	interface : u64,
+}
+
+// unsigned8
+#[derive(Debug, IpfixRecord, PartialEq)]
+struct AddressProtocol {
+	#[ipfix(name = "protocolIdentifier")]
+	protocol : Ipv4Addr,
+}
+
+// unsigned64 in reduced size
+#[derive(Debug, IpfixRecord, PartialEq)]
+struct NarrowCounter {
+	#[ipfix(name = "octetDeltaCount")]
+	bytes : u32,
+}
+
+fn flow() -> Flow {
+	Flow {
+		src : Ipv4Addr::new(192, 0, 2, 1),
+		dst : Ipv4Addr::new(198, 51, 100, 1),
+		bytes : 1500,
+		interface_name : "eth0".to_string(),
+		flags : Some(2),
+	}
+}
+
+#[test]
+fn template_test() {
+	let template = Flow::template(256, &Registry::default()).unwrap();
+	assert_eq!(template.header.field_count, 5);
+	assert_eq!(
+		template.fields[1],
+		FieldSpecifier {
+			information_element_id : 12,
+			field_length : 4,
+			enterprise_number : None,
+		}
+	);
+	assert_eq!(template.fields[3].field_length, 0xffff);
+	assert_eq!(template.fields[4].enterprise_number, Some(6871));
+}
+
+#[test]
+fn round_trip_test() {
+	let registry = Registry::default();
+	let template = Flow::template(256, &registry).unwrap();
+	let record = flow().to_record(&registry).unwrap();
+	assert_eq!(record.fields[2], DataValue::Unsigned64(1500));
+
+	let typed = TypedDataRecord {
+		data : &record,
+		template : &template,
+	};
+	assert_eq!(Flow::from_record(&typed, &registry), Ok(flow()));
+
+	let encoded = encode_data_set(&template, &[record]).unwrap();
+	assert_eq!(encoded.len(), 4 + 4 + 4 + 8 + 5 + 1);
+}
+
+#[test]
+fn missing_test() {
+	let registry = Registry::default();
+	let mut template = Flow::template(256, &registry).unwrap();
+	let mut record = flow().to_record(&registry).unwrap();
+
+	// optional field
+	template.fields.pop();
+	record.fields.pop();
+	let typed = TypedDataRecord {
+		data : &record,
+		template : &template,
+	};
+	assert_eq!(
+		Flow::from_record(&typed, &registry).unwrap().flags,
+		None
+	);
+
+	template.fields.remove(0);
+	record.fields.remove(0);
+	let typed = TypedDataRecord {
+		data : &record,
+		template : &template,
+	};
+	assert_eq!(
+		Flow::from_record(&typed, &registry),
+		Err(RecordError::Missing("src"))
+	);
+
+	let mut flow = flow();
+	flow.flags = None;
+	assert_eq!(flow.to_record(&Registry::default()), Err(RecordError::Missing("flags")));
+}
+
+#[test]
+fn date_time_test() {
+	let registry = Registry::default();
+	let template = Times::template(256, &registry).unwrap();
+	assert_eq!(template.fields[0].field_length, 4);
+	assert_eq!(template.fields[1].field_length, 8);
+
+	let times = Times {
+		start : UNIX_EPOCH + Duration::from_secs(1_500_000_000),
+		end : Some(UNIX_EPOCH + Duration::from_millis(1_500_000_001_500)),
+	};
+	let record = times.to_record(&registry).unwrap();
+	assert_eq!(
+		record.fields,
+		vec![
+			DataValue::DateTimeSeconds(1_500_000_000),
+			DataValue::DateTimeMilliseconds(1_500_000_001_500),
+		]
+	);
+	let typed = TypedDataRecord {
+		data : &record,
+		template : &template,
+	};
+	assert_eq!(Times::from_record(&typed, &registry), Ok(times));
+	assert_eq!(encode_data_set(&template, &[record]).unwrap().len(), 4 + 4 + 8);
+}
+
+#[test]
+fn field_type_test() {
+	let registry = Registry::default();
+	assert_eq!(
+		WideInterface::template(256, &registry),
+		Err(RecordError::FieldType {
+			field : "interface",
+			abstract_data_type : AbstractDataType::Unsigned32,
+		})
+	);
+	assert_eq!(
+		AddressProtocol::template(256, &registry),
+		Err(RecordError::FieldType {
+			field : "protocol",
+			abstract_data_type : AbstractDataType::Unsigned8,
+		})
+	);
+
+	let template = NarrowCounter::template(256, &registry).unwrap();
+	assert_eq!(template.fields[0].field_length, 4);
+	assert!(verify_template(&template, &registry).is_ok());
+}